@@ -0,0 +1,41 @@
+use llm_api_rs::{
+    core::{ChatCompletionRequest, ChatMessage},
+    providers::cohere::Cohere,
+    LlmProvider,
+};
+
+#[tokio::main]
+async fn main() {
+    let api_key =
+        std::env::var("COHERE_API_KEY").expect("COHERE_API_KEY environment variable not set");
+
+    let client = Cohere::new(api_key);
+
+    let request = ChatCompletionRequest {
+        model: "command-r-plus-08-2024".to_string(),
+        messages: vec![
+            ChatMessage {
+                role: "system".to_string(),
+                content: "You are a helpful assistant.".to_string(),
+                ..Default::default()
+            },
+            ChatMessage {
+                role: "user".to_string(),
+                content: "Hello!".to_string(),
+                ..Default::default()
+            },
+        ],
+        temperature: Some(0.7),
+        max_tokens: Some(50),
+        ..Default::default()
+    };
+
+    match client.chat_completion(request).await {
+        Ok(response) => {
+            for choice in response.choices {
+                println!("Response: {}", choice.message.content);
+            }
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
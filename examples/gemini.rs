@@ -17,14 +17,17 @@ async fn main() {
             ChatMessage {
                 role: "model".to_string(),
                 content: "You are a helpful assistant.".to_string(),
+                ..Default::default()
             },
             ChatMessage {
                 role: "user".to_string(),
                 content: "Hello!".to_string(),
+                ..Default::default()
             },
         ],
         temperature: Some(0.7),
         max_tokens: Some(50),
+        ..Default::default()
     };
 
     match client.chat_completion(request).await {
@@ -24,14 +24,17 @@ async fn main() {
             ChatMessage {
                 role: "assistant".to_string(),
                 content: "You are a helpful assistant.".to_string(),
+                ..Default::default()
             },
             ChatMessage {
                 role: "user".to_string(),
                 content: "Hello!".to_string(),
+                ..Default::default()
             },
         ],
         temperature: None,
         max_tokens: None,
+        ..Default::default()
     };
 
     match client.chat_completion(request).await {
@@ -0,0 +1,65 @@
+use llm_api_rs::{
+    core::{ChatCompletionRequest, ChatMessage},
+    providers::openai::{OpenAI, ResponsesOptions},
+};
+
+#[tokio::main]
+async fn main() {
+    let api_key =
+        std::env::var("OPENAI_API_KEY").expect("OPENAI_API_KEY environment variable not set");
+
+    let client = OpenAI::new(api_key).with_responses_api(true);
+
+    let request = ChatCompletionRequest {
+        model: "o4-mini".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Pick a random prime number below 100.".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let options = ResponsesOptions {
+        store: Some(true),
+        reasoning_summary: Some("auto".to_string()),
+        ..Default::default()
+    };
+
+    let first = match client.create_response(request, options).await {
+        Ok(response) => response,
+        Err(e) => {
+            eprintln!("Error: {}", e);
+            return;
+        }
+    };
+    for choice in &first.choices {
+        if let Some(reasoning) = &choice.message.reasoning {
+            println!("Reasoning: {}", reasoning);
+        }
+        println!("Response: {}", choice.message.content);
+    }
+
+    // The server keeps the first turn, so only the new message is sent.
+    let request = ChatCompletionRequest {
+        model: "o4-mini".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Now double it.".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    let options = ResponsesOptions {
+        previous_response_id: Some(first.id),
+        ..Default::default()
+    };
+
+    match client.create_response(request, options).await {
+        Ok(response) => {
+            for choice in response.choices {
+                println!("Response: {}", choice.message.content);
+            }
+        }
+        Err(e) => eprintln!("Error: {}", e),
+    }
+}
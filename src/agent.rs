@@ -0,0 +1,270 @@
+// Tool registry and agent loop: the model calls registered Rust functions until it
+// gives a final answer.
+// Argument schemas are derived with `schemars`, so tools are declared once, as types.
+
+use crate::conversation::Conversation;
+use crate::core::runtime;
+use crate::core::{ChatCompletionRequest, ChatMessage, Tool, ToolCall};
+use crate::error::LlmApiError;
+use crate::providers::LlmProvider;
+use serde::de::DeserializeOwned;
+use serde::Serialize;
+use std::collections::BTreeMap;
+use std::future::Future;
+use std::pin::Pin;
+use std::sync::Arc;
+
+pub use schemars::JsonSchema;
+
+type ToolFuture = Pin<Box<dyn Future<Output = Result<String, LlmApiError>> + Send>>;
+type ToolHandler = Arc<dyn Fn(serde_json::Value) -> ToolFuture + Send + Sync>;
+type StepCallback = dyn Fn(&AgentStep) + Send + Sync;
+
+/// Async Rust functions the model can call, keyed by tool name.
+#[derive(Clone, Default)]
+pub struct ToolRegistry {
+    tools: BTreeMap<String, (Tool, ToolHandler)>,
+}
+
+impl ToolRegistry {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register `handler` as tool `name`. The parameter schema is derived from `A`,
+    /// and the model's arguments are deserialized into it before each call.
+    ///
+    /// String results are passed to the model as they are; other results are sent
+    /// as JSON.
+    pub fn register<A, R, F, Fut>(&mut self, name: &str, description: &str, handler: F)
+    where
+        A: DeserializeOwned + JsonSchema + Send + 'static,
+        R: Serialize,
+        F: Fn(A) -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = Result<R, LlmApiError>> + Send + 'static,
+    {
+        let mut parameters = serde_json::to_value(schemars::schema_for!(A))
+            .unwrap_or_else(|_| serde_json::json!({"type": "object"}));
+        if let Some(schema) = parameters.as_object_mut() {
+            schema.remove("$schema");
+            schema.remove("title");
+        }
+
+        let handler = Arc::new(handler);
+        let call: ToolHandler = Arc::new(move |arguments| {
+            let handler = handler.clone();
+            Box::pin(async move {
+                let arguments: A = serde_json::from_value(arguments)
+                    .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?;
+                let output = handler(arguments).await?;
+                match serde_json::to_value(output) {
+                    Ok(serde_json::Value::String(text)) => Ok(text),
+                    Ok(value) => Ok(value.to_string()),
+                    Err(e) => Err(LlmApiError::SerializationError(e.to_string())),
+                }
+            })
+        });
+
+        self.tools.insert(
+            name.to_string(),
+            (Tool::function(name, description, parameters), call),
+        );
+    }
+
+    /// Definitions of every registered tool, for `ChatCompletionRequest::tools`.
+    pub fn tools(&self) -> Vec<Tool> {
+        self.tools.values().map(|(tool, _)| tool.clone()).collect()
+    }
+
+    /// Run the tool the model asked for. Empty arguments are treated as `{}`.
+    pub async fn call(&self, call: &ToolCall) -> Result<String, LlmApiError> {
+        self.invoke(call)?.await
+    }
+
+    fn invoke(&self, call: &ToolCall) -> Result<ToolFuture, LlmApiError> {
+        let (_, handler) = self.tools.get(&call.function.name).ok_or_else(|| {
+            LlmApiError::ProviderError(format!("unknown tool {}", call.function.name))
+        })?;
+        let arguments = if call.function.arguments.trim().is_empty() {
+            serde_json::json!({})
+        } else {
+            serde_json::from_str(&call.function.arguments)
+                .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?
+        };
+        Ok(handler(arguments))
+    }
+}
+
+/// The outcome of one tool call within an agent step.
+#[derive(Debug, Clone)]
+pub struct ToolResult {
+    pub call: ToolCall,
+    /// What was sent back to the model: the tool's output, or the error message.
+    pub output: String,
+    pub is_error: bool,
+}
+
+/// One model response and the tool calls it triggered.
+#[derive(Debug, Clone)]
+pub struct AgentStep {
+    /// 1-based.
+    pub iteration: usize,
+    pub message: ChatMessage,
+    pub tool_results: Vec<ToolResult>,
+}
+
+/// The final answer and the full transcript that led to it.
+#[derive(Debug, Clone)]
+pub struct AgentOutput {
+    pub content: String,
+    /// The request's messages followed by every model and tool message of the run.
+    pub messages: Vec<ChatMessage>,
+    pub iterations: usize,
+}
+
+/// Drives a provider through tool calls until it answers without calling a tool.
+///
+/// Uses the same loop as `Conversation::send_with_tools`, so it works with every
+/// built-in provider. Tool failures, panics, unknown tools and malformed arguments are
+/// reported back to the model as the tool's output, so it can correct itself; provider
+/// errors end the run.
+pub struct Agent<P> {
+    provider: P,
+    registry: ToolRegistry,
+    max_iterations: usize,
+    parallel_tools: bool,
+    on_step: Option<Box<StepCallback>>,
+}
+
+impl<P: LlmProvider> Agent<P> {
+    pub fn new(provider: P, registry: ToolRegistry) -> Self {
+        Self {
+            provider,
+            registry,
+            max_iterations: 10,
+            parallel_tools: true,
+            on_step: None,
+        }
+    }
+
+    /// Model calls allowed per run before it fails. Defaults to 10.
+    pub fn with_max_iterations(mut self, max_iterations: usize) -> Self {
+        self.max_iterations = max_iterations;
+        self
+    }
+
+    /// Run the tool calls of one response concurrently. On by default.
+    pub fn with_parallel_tools(mut self, parallel_tools: bool) -> Self {
+        self.parallel_tools = parallel_tools;
+        self
+    }
+
+    /// Called after every model response, once its tool calls have run.
+    pub fn with_step_callback(
+        mut self,
+        on_step: impl Fn(&AgentStep) + Send + Sync + 'static,
+    ) -> Self {
+        self.on_step = Some(Box::new(on_step));
+        self
+    }
+
+    pub fn provider(&self) -> &P {
+        &self.provider
+    }
+
+    /// Run `request` to a final answer. Registered tools are added to the request's
+    /// own `tools`.
+    pub async fn run(&self, request: ChatCompletionRequest) -> Result<AgentOutput, LlmApiError> {
+        let mut tools = request.tools.clone().unwrap_or_default();
+        tools.extend(self.registry.tools());
+        let mut conversation = Conversation::from_request(ChatCompletionRequest {
+            tools: Some(tools),
+            ..request
+        });
+
+        let (message, iterations) = conversation
+            .run_tool_loop(
+                &self.provider,
+                self.max_iterations,
+                move |iteration, message| async move {
+                    let calls = message.tool_calls.clone().unwrap_or_default();
+                    let tool_results = self.run_tools(calls).await;
+                    let outputs = tool_results
+                        .iter()
+                        .map(|result| result.output.clone())
+                        .collect();
+                    self.report(AgentStep {
+                        iteration,
+                        message,
+                        tool_results,
+                    });
+                    Ok(outputs)
+                },
+            )
+            .await?;
+        self.report(AgentStep {
+            iteration: iterations,
+            message: message.clone(),
+            tool_results: Vec::new(),
+        });
+
+        Ok(AgentOutput {
+            content: message.content,
+            messages: conversation.messages().to_vec(),
+            iterations,
+        })
+    }
+
+    fn report(&self, step: AgentStep) {
+        if let Some(on_step) = &self.on_step {
+            on_step(&step);
+        }
+    }
+
+    async fn run_tools(&self, calls: Vec<ToolCall>) -> Vec<ToolResult> {
+        let mut outputs: Vec<Option<Result<String, LlmApiError>>> =
+            calls.iter().map(|_| None).collect();
+        let mut tasks: Vec<runtime::BoxFuture<_>> = Vec::new();
+        for (index, call) in calls.iter().enumerate() {
+            match self.registry.invoke(call) {
+                Ok(future) => tasks.push(Box::pin(async move { (index, future.await) })),
+                Err(error) => outputs[index] = Some(Err(error)),
+            }
+        }
+        // Sequential calls run as tasks too, so a panic is handled the same way in both
+        // modes: the panicked task loses its index and its slot is reported below.
+        let finished = if self.parallel_tools {
+            runtime::join_all(tasks).await
+        } else {
+            let mut finished = Vec::new();
+            for task in tasks {
+                finished.extend(runtime::join_all(vec![task]).await);
+            }
+            finished
+        };
+        for (index, output) in finished {
+            outputs[index] = Some(output);
+        }
+
+        calls
+            .into_iter()
+            .zip(outputs)
+            .map(|(call, output)| {
+                match output
+                    .unwrap_or_else(|| Err(LlmApiError::ProviderError("tool panicked".to_string())))
+                {
+                    Ok(output) => ToolResult {
+                        call,
+                        output,
+                        is_error: false,
+                    },
+                    Err(error) => ToolResult {
+                        call,
+                        output: format!("error: {}", error),
+                        is_error: true,
+                    },
+                }
+            })
+            .collect()
+    }
+}
@@ -0,0 +1,187 @@
+// Speech to text and text to speech.
+// https://platform.openai.com/docs/api-reference/audio
+
+use crate::error::LlmApiError;
+use crate::files::FileUpload;
+use async_trait::async_trait;
+
+pub use crate::core::client::ByteStream;
+
+/// Timestamps to include in a transcription.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TimestampGranularity {
+    Segment,
+    Word,
+}
+
+#[derive(Debug, Clone)]
+pub struct TranscriptionRequest {
+    pub model: String,
+    pub audio: FileUpload,
+    /// ISO-639-1 code of the spoken language, e.g. `en`. Detected when not given.
+    pub language: Option<String>,
+    /// Text to guide the style, or the transcript of the audio before this one.
+    pub prompt: Option<String>,
+    pub temperature: Option<f32>,
+    /// Empty for text only.
+    pub timestamps: Vec<TimestampGranularity>,
+}
+
+impl TranscriptionRequest {
+    pub fn new(model: &str, audio: FileUpload) -> Self {
+        Self {
+            model: model.to_string(),
+            audio,
+            language: None,
+            prompt: None,
+            temperature: None,
+            timestamps: Vec::new(),
+        }
+    }
+
+    pub fn with_language(mut self, language: &str) -> Self {
+        self.language = Some(language.to_string());
+        self
+    }
+
+    pub fn with_prompt(mut self, prompt: &str) -> Self {
+        self.prompt = Some(prompt.to_string());
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_timestamps(mut self, granularity: TimestampGranularity) -> Self {
+        if !self.timestamps.contains(&granularity) {
+            self.timestamps.push(granularity);
+        }
+        self
+    }
+}
+
+/// A stretch of the transcript, with times in seconds from the start of the audio.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TranscriptSegment {
+    pub start: f32,
+    pub end: f32,
+    pub text: String,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct Transcription {
+    pub text: String,
+    /// The detected or given language, when the provider reports it.
+    pub language: Option<String>,
+    /// Length of the audio in seconds, when the provider reports it.
+    pub duration: Option<f32>,
+    /// Filled in when segment timestamps were asked for.
+    pub segments: Vec<TranscriptSegment>,
+    /// Filled in when word timestamps were asked for, one segment per word.
+    pub words: Vec<TranscriptSegment>,
+}
+
+/// Providers that turn speech into text.
+#[async_trait]
+pub trait TranscriptionProvider {
+    async fn transcribe(&self, request: TranscriptionRequest)
+        -> Result<Transcription, LlmApiError>;
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum AudioFormat {
+    #[default]
+    Mp3,
+    Wav,
+    Opus,
+    Aac,
+    Flac,
+    /// Raw 16-bit little-endian samples, without a header.
+    Pcm,
+}
+
+impl AudioFormat {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "mp3",
+            AudioFormat::Wav => "wav",
+            AudioFormat::Opus => "opus",
+            AudioFormat::Aac => "aac",
+            AudioFormat::Flac => "flac",
+            AudioFormat::Pcm => "pcm",
+        }
+    }
+
+    pub fn mime_type(&self) -> &'static str {
+        match self {
+            AudioFormat::Mp3 => "audio/mpeg",
+            AudioFormat::Wav => "audio/wav",
+            AudioFormat::Opus => "audio/ogg",
+            AudioFormat::Aac => "audio/aac",
+            AudioFormat::Flac => "audio/flac",
+            AudioFormat::Pcm => "audio/pcm",
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeechRequest {
+    pub model: String,
+    /// The text to speak.
+    pub input: String,
+    pub voice: String,
+    pub format: AudioFormat,
+    /// Playback speed, where 1.0 is normal.
+    pub speed: Option<f32>,
+    /// How to speak, e.g. "cheerful and fast". Not every model supports it.
+    pub instructions: Option<String>,
+}
+
+impl SpeechRequest {
+    pub fn new(model: &str, input: &str, voice: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            input: input.to_string(),
+            voice: voice.to_string(),
+            format: AudioFormat::default(),
+            speed: None,
+            instructions: None,
+        }
+    }
+
+    pub fn with_format(mut self, format: AudioFormat) -> Self {
+        self.format = format;
+        self
+    }
+
+    pub fn with_speed(mut self, speed: f32) -> Self {
+        self.speed = Some(speed);
+        self
+    }
+
+    pub fn with_instructions(mut self, instructions: &str) -> Self {
+        self.instructions = Some(instructions.to_string());
+        self
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct SpeechAudio {
+    pub format: AudioFormat,
+    pub data: Vec<u8>,
+}
+
+/// Providers that turn text into speech.
+#[async_trait]
+pub trait SpeechProvider: Send + Sync {
+    async fn speech(&self, request: SpeechRequest) -> Result<SpeechAudio, LlmApiError>;
+
+    /// Speech as it is generated, so playback can start before the end.
+    ///
+    /// The default waits for `speech` and returns the audio as one chunk.
+    async fn speech_stream(&self, request: SpeechRequest) -> Result<ByteStream, LlmApiError> {
+        Ok(ByteStream::from_bytes(self.speech(request).await?.data))
+    }
+}
@@ -0,0 +1,151 @@
+// Batch jobs: many chat requests submitted at once, at the providers' batch discount,
+// with results collected later by custom id.
+// https://platform.openai.com/docs/guides/batch
+// https://docs.anthropic.com/en/docs/build-with-claude/batch-processing
+// https://ai.google.dev/gemini-api/docs/batch-mode
+
+use crate::core::runtime;
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use std::collections::HashMap;
+use std::time::Duration;
+
+/// One request of a batch, with the id its result is reported under.
+#[derive(Debug, Clone)]
+pub struct BatchRequest {
+    pub custom_id: String,
+    pub request: ChatCompletionRequest,
+}
+
+impl BatchRequest {
+    pub fn new(custom_id: &str, request: ChatCompletionRequest) -> Self {
+        Self {
+            custom_id: custom_id.to_string(),
+            request,
+        }
+    }
+}
+
+/// Where a batch is in its lifecycle, normalized across providers.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BatchStatus {
+    /// Validating, queued, running or finalizing.
+    InProgress,
+    /// Every request ran; some may still have failed individually.
+    Completed,
+    /// The batch as a whole was rejected, e.g. because its input was invalid.
+    Failed,
+    Cancelled,
+    /// The provider's completion window ran out before every request ran.
+    Expired,
+}
+
+impl BatchStatus {
+    /// Whether the status can no longer change.
+    pub fn is_done(&self) -> bool {
+        *self != BatchStatus::InProgress
+    }
+}
+
+/// Request counts reported by the provider. They may lag behind while in progress.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct BatchCounts {
+    pub total: u32,
+    pub succeeded: u32,
+    pub failed: u32,
+}
+
+#[derive(Debug, Clone)]
+pub struct BatchJob {
+    /// The provider's batch id, for `get_batch`, `batch_results` and `cancel_batch`.
+    pub id: String,
+    pub status: BatchStatus,
+    pub counts: BatchCounts,
+}
+
+/// The outcome of one request of a batch.
+#[derive(Debug)]
+pub struct BatchResult {
+    pub custom_id: String,
+    pub response: Result<ChatCompletionResponse, LlmApiError>,
+}
+
+/// Providers with an asynchronous batch API.
+#[async_trait]
+pub trait BatchProvider {
+    /// Submit `requests` as one batch. Custom ids must be unique within the batch.
+    async fn create_batch(&self, requests: Vec<BatchRequest>) -> Result<BatchJob, LlmApiError>;
+
+    async fn get_batch(&self, id: &str) -> Result<BatchJob, LlmApiError>;
+
+    async fn cancel_batch(&self, id: &str) -> Result<BatchJob, LlmApiError>;
+
+    /// Results of a finished batch, in no particular order. Requests that failed,
+    /// were cancelled or expired are reported with an error.
+    async fn batch_results(&self, id: &str) -> Result<Vec<BatchResult>, LlmApiError>;
+}
+
+/// Poll `get_batch` every `poll_interval` until the batch is done.
+pub async fn wait_for_batch<P>(
+    provider: &P,
+    id: &str,
+    poll_interval: Duration,
+) -> Result<BatchJob, LlmApiError>
+where
+    P: BatchProvider + Sync + ?Sized,
+{
+    loop {
+        let job = provider.get_batch(id).await?;
+        if job.status.is_done() {
+            return Ok(job);
+        }
+        runtime::sleep(poll_interval).await;
+    }
+}
+
+/// Submit `requests`, wait for the batch and return one result per request, in the
+/// order they were given.
+///
+/// Fails only if the batch itself fails. Individual failures, and requests the
+/// provider returned no result for, are reported in their `BatchResult`.
+pub async fn run_batch<P>(
+    provider: &P,
+    requests: Vec<BatchRequest>,
+    poll_interval: Duration,
+) -> Result<Vec<BatchResult>, LlmApiError>
+where
+    P: BatchProvider + Sync + ?Sized,
+{
+    let ids: Vec<String> = requests.iter().map(|r| r.custom_id.clone()).collect();
+    let job = provider.create_batch(requests).await?;
+    let job = wait_for_batch(provider, &job.id, poll_interval).await?;
+    if job.status == BatchStatus::Failed {
+        return Err(LlmApiError::ProviderError(format!(
+            "batch {} failed",
+            job.id
+        )));
+    }
+
+    let mut results: HashMap<String, Result<ChatCompletionResponse, LlmApiError>> = provider
+        .batch_results(&job.id)
+        .await?
+        .into_iter()
+        .map(|result| (result.custom_id, result.response))
+        .collect();
+    Ok(ids
+        .into_iter()
+        .map(|custom_id| {
+            let response = results.remove(&custom_id).unwrap_or_else(|| {
+                Err(LlmApiError::ProviderError(format!(
+                    "batch {} returned no result",
+                    job.id
+                )))
+            });
+            BatchResult {
+                custom_id,
+                response,
+            }
+        })
+        .collect())
+}
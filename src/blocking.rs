@@ -0,0 +1,133 @@
+// Synchronous access to the providers, for code that does not run an async runtime,
+// such as CLI tools and build scripts. Enabled by the `blocking` feature.
+
+use crate::audio::{
+    SpeechAudio, SpeechProvider, SpeechRequest, Transcription, TranscriptionProvider,
+    TranscriptionRequest,
+};
+use crate::batch::{BatchJob, BatchProvider, BatchRequest, BatchResult};
+use crate::core::tokens::TokenCount;
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse, FileHandle, ModelInfo};
+use crate::error::LlmApiError;
+use crate::files::{FileProvider, FileUpload};
+use crate::images::{ImageProvider, ImageRequest, ImageResponse};
+use crate::moderation::{ModerationProvider, ModerationRequest, ModerationResponse};
+use crate::providers::LlmProvider;
+use std::future::Future;
+use tokio::runtime::{Builder, Runtime};
+
+/// Wraps a provider and runs its async methods to completion on a private
+/// current-thread runtime.
+///
+/// Every provider trait the inner provider implements gets a synchronous method of the
+/// same name. Calling them from inside an async runtime panics; use the inner provider
+/// there instead.
+///
+/// ```no_run
+/// use llm_api_rs::blocking::Blocking;
+/// use llm_api_rs::{ChatCompletionRequest, OpenAI};
+///
+/// let client = Blocking::new(OpenAI::new("sk-...".to_string()))?;
+/// let response = client.chat_completion(ChatCompletionRequest::default())?;
+/// # Ok::<(), llm_api_rs::LlmApiError>(())
+/// ```
+pub struct Blocking<P> {
+    inner: P,
+    runtime: Runtime,
+}
+
+impl<P> Blocking<P> {
+    pub fn new(inner: P) -> Result<Self, LlmApiError> {
+        let runtime = Builder::new_current_thread()
+            .enable_all()
+            .build()
+            .map_err(|e| LlmApiError::ConfigError(format!("cannot start a runtime: {}", e)))?;
+        Ok(Self { inner, runtime })
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// Run any future to completion, e.g. one from `Conversation` or `Agent`.
+    pub fn block_on<F: Future>(&self, future: F) -> F::Output {
+        self.runtime.block_on(future)
+    }
+}
+
+impl<P: LlmProvider + Sync> Blocking<P> {
+    pub fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.block_on(self.inner.chat_completion(request))
+    }
+
+    pub fn count_tokens(&self, request: &ChatCompletionRequest) -> Result<TokenCount, LlmApiError> {
+        self.block_on(self.inner.count_tokens(request))
+    }
+
+    pub fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        self.block_on(self.inner.list_models())
+    }
+}
+
+impl<P: FileProvider> Blocking<P> {
+    pub fn upload_file(&self, upload: FileUpload) -> Result<FileHandle, LlmApiError> {
+        self.block_on(self.inner.upload_file(upload))
+    }
+
+    pub fn list_files(&self) -> Result<Vec<FileHandle>, LlmApiError> {
+        self.block_on(self.inner.list_files())
+    }
+
+    pub fn get_file(&self, id: &str) -> Result<FileHandle, LlmApiError> {
+        self.block_on(self.inner.get_file(id))
+    }
+
+    pub fn delete_file(&self, id: &str) -> Result<(), LlmApiError> {
+        self.block_on(self.inner.delete_file(id))
+    }
+}
+
+impl<P: BatchProvider> Blocking<P> {
+    pub fn create_batch(&self, requests: Vec<BatchRequest>) -> Result<BatchJob, LlmApiError> {
+        self.block_on(self.inner.create_batch(requests))
+    }
+
+    pub fn get_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        self.block_on(self.inner.get_batch(id))
+    }
+
+    pub fn cancel_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        self.block_on(self.inner.cancel_batch(id))
+    }
+
+    pub fn batch_results(&self, id: &str) -> Result<Vec<BatchResult>, LlmApiError> {
+        self.block_on(self.inner.batch_results(id))
+    }
+}
+
+impl<P: ImageProvider> Blocking<P> {
+    pub fn generate_image(&self, request: ImageRequest) -> Result<ImageResponse, LlmApiError> {
+        self.block_on(self.inner.generate_image(request))
+    }
+}
+
+impl<P: TranscriptionProvider> Blocking<P> {
+    pub fn transcribe(&self, request: TranscriptionRequest) -> Result<Transcription, LlmApiError> {
+        self.block_on(self.inner.transcribe(request))
+    }
+}
+
+impl<P: SpeechProvider> Blocking<P> {
+    pub fn speech(&self, request: SpeechRequest) -> Result<SpeechAudio, LlmApiError> {
+        self.block_on(self.inner.speech(request))
+    }
+}
+
+impl<P: ModerationProvider> Blocking<P> {
+    pub fn moderate(&self, request: ModerationRequest) -> Result<ModerationResponse, LlmApiError> {
+        self.block_on(self.inner.moderate(request))
+    }
+}
@@ -0,0 +1,318 @@
+// What each model supports, for checking requests before they are sent.
+// The built-in catalog is a snapshot of the providers' documentation and will drift;
+// override entries, or load them from a file, to keep it accurate.
+
+use crate::core::{ChatCompletionRequest, ContentPart};
+use crate::error::LlmApiError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ModelCapabilities {
+    /// Accepts image input. Only OpenAI, Anthropic and Gemini can send images
+    /// through this crate, so other providers' models are listed without it.
+    pub vision: bool,
+    /// Accepts tool definitions and returns tool calls.
+    pub tools: bool,
+    /// Can be constrained to answer with valid JSON.
+    pub json_mode: bool,
+    /// Can stream its answer.
+    pub streaming: bool,
+    /// Accepts system messages.
+    pub system_prompt: bool,
+    /// Input plus output tokens.
+    pub context_window: u32,
+    /// Limit on `max_tokens`, when the provider documents one separately.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_output_tokens: Option<u32>,
+}
+
+impl ModelCapabilities {
+    /// A text model with tools, JSON mode, streaming and system prompts, as most chat
+    /// models are.
+    pub fn new(context_window: u32) -> Self {
+        Self {
+            vision: false,
+            tools: true,
+            json_mode: true,
+            streaming: true,
+            system_prompt: true,
+            context_window,
+            max_output_tokens: None,
+        }
+    }
+
+    pub fn with_vision(mut self, vision: bool) -> Self {
+        self.vision = vision;
+        self
+    }
+
+    pub fn with_tools(mut self, tools: bool) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    pub fn with_json_mode(mut self, json_mode: bool) -> Self {
+        self.json_mode = json_mode;
+        self
+    }
+
+    pub fn with_streaming(mut self, streaming: bool) -> Self {
+        self.streaming = streaming;
+        self
+    }
+
+    pub fn with_system_prompt(mut self, system_prompt: bool) -> Self {
+        self.system_prompt = system_prompt;
+        self
+    }
+
+    pub fn with_max_output_tokens(mut self, max_output_tokens: u32) -> Self {
+        self.max_output_tokens = Some(max_output_tokens);
+        self
+    }
+}
+
+/// Capabilities keyed by provider (`openai`, `anthropic`, `gemini`, ...) and model id.
+///
+/// An entry also covers the model's dated or tagged snapshots: `gpt-4o` matches
+/// `gpt-4o-2024-08-06`, `mistral-large` matches `mistral-large-latest` and `llava`
+/// matches `llava:13b`, but `o3` does not match `o3-pro`, which is a different model.
+/// Providers check each request against their catalog with `validate` and reject
+/// what the model cannot do before calling the API.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct CapabilityCatalog {
+    models: BTreeMap<String, BTreeMap<String, ModelCapabilities>>,
+}
+
+impl Default for CapabilityCatalog {
+    fn default() -> Self {
+        Self::builtin()
+    }
+}
+
+impl CapabilityCatalog {
+    /// An empty catalog. Nothing is validated against it.
+    pub fn empty() -> Self {
+        Self {
+            models: BTreeMap::new(),
+        }
+    }
+
+    /// The built-in capabilities.
+    pub fn builtin() -> Self {
+        let mut catalog = Self::empty();
+        for (provider, model, vision, tools, json_mode, system_prompt, context, output) in
+            BUILTIN_CAPABILITIES
+        {
+            let mut capabilities = ModelCapabilities::new(*context)
+                .with_vision(*vision)
+                .with_tools(*tools)
+                .with_json_mode(*json_mode)
+                .with_system_prompt(*system_prompt);
+            capabilities.max_output_tokens = *output;
+            catalog.set(provider, model, capabilities);
+        }
+        catalog
+    }
+
+    pub fn set(&mut self, provider: &str, model: &str, capabilities: ModelCapabilities) {
+        self.models
+            .entry(provider.to_string())
+            .or_default()
+            .insert(model.to_string(), capabilities);
+    }
+
+    pub fn with_capabilities(
+        mut self,
+        provider: &str,
+        model: &str,
+        capabilities: ModelCapabilities,
+    ) -> Self {
+        self.set(provider, model, capabilities);
+        self
+    }
+
+    /// Merge capabilities from a `.json` or `.toml` file over this catalog.
+    ///
+    /// The file maps provider to model to capabilities, e.g. in TOML:
+    ///
+    /// ```toml
+    /// [openai."gpt-4o"]
+    /// vision = true
+    /// tools = true
+    /// json_mode = true
+    /// streaming = true
+    /// system_prompt = true
+    /// context_window = 128000
+    /// max_output_tokens = 16384
+    /// ```
+    pub fn load_overrides(&mut self, path: impl AsRef<Path>) -> Result<(), LlmApiError> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path).map_err(|e| {
+            LlmApiError::ConfigError(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        let overrides: CapabilityCatalog = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&data)
+                .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?,
+            #[cfg(feature = "toml")]
+            Some("toml") => toml::from_str(&data)
+                .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?,
+            _ => {
+                return Err(LlmApiError::ConfigError(format!(
+                    "unsupported capabilities file {}",
+                    path.display()
+                )))
+            }
+        };
+        for (provider, models) in overrides.models {
+            for (model, capabilities) in models {
+                self.set(&provider, &model, capabilities);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn get(&self, provider: &str, model: &str) -> Option<&ModelCapabilities> {
+        lookup(self.models.get(provider)?, model)
+    }
+
+    /// Like `get`, for a model of any provider.
+    pub fn find(&self, model: &str) -> Option<&ModelCapabilities> {
+        lookup(self.models.values().flatten(), model)
+    }
+
+    /// Fail with a `ConfigError` if `request` uses something its model does not
+    /// support: images, tools, system messages or more output tokens than allowed.
+    /// Models missing from the catalog pass.
+    pub fn validate(
+        &self,
+        provider: &str,
+        request: &ChatCompletionRequest,
+    ) -> Result<(), LlmApiError> {
+        let Some(capabilities) = self.get(provider, &request.model) else {
+            return Ok(());
+        };
+        let unsupported = |what: &str| {
+            Err(LlmApiError::ConfigError(format!(
+                "{} does not support {}",
+                request.model, what
+            )))
+        };
+
+        if !capabilities.tools && request.tools.as_ref().is_some_and(|t| !t.is_empty()) {
+            return unsupported("tools");
+        }
+        if !capabilities.system_prompt && request.messages.iter().any(|m| m.role == "system") {
+            return unsupported("system messages");
+        }
+        let has_images = request.messages.iter().flat_map(|m| &m.parts).any(
+            |part| matches!(part, ContentPart::File(file) if file.mime_type.starts_with("image/")),
+        );
+        if !capabilities.vision && has_images {
+            return unsupported("image input");
+        }
+        if let (Some(max_tokens), Some(limit)) =
+            (request.max_tokens, capabilities.max_output_tokens)
+        {
+            if max_tokens > limit {
+                return Err(LlmApiError::ConfigError(format!(
+                    "max_tokens {} exceeds the {} output tokens {} allows",
+                    max_tokens, limit, request.model
+                )));
+            }
+        }
+        Ok(())
+    }
+}
+
+// The value of the longest id in `entries` that is `model` or a snapshot of it.
+pub(crate) fn lookup<'a, V>(
+    entries: impl IntoIterator<Item = (&'a String, V)>,
+    model: &str,
+) -> Option<V> {
+    entries
+        .into_iter()
+        .filter(|(id, _)| names_snapshot(id, model))
+        .max_by_key(|(id, _)| id.len())
+        .map(|(_, value)| value)
+}
+
+// `model` is `id` itself, or `id` followed by a date, version or tag such as
+// `-2024-08-06`, `-001`, `-latest`, `-preview-05-20` or `:13b`.
+fn names_snapshot(id: &str, model: &str) -> bool {
+    let Some(rest) = model.strip_prefix(id) else {
+        return false;
+    };
+    if rest.is_empty() || rest.starts_with(':') {
+        return true;
+    }
+    rest.strip_prefix('-').is_some_and(|suffix| {
+        suffix.starts_with(|c: char| c.is_ascii_digit())
+            || ["latest", "preview", "exp"]
+                .iter()
+                .any(|tag| suffix.starts_with(tag))
+    })
+}
+
+// (provider, model id, vision, tools, json mode, system prompt, context window,
+// max output tokens). Every built-in model can stream.
+type CapabilityRow = (
+    &'static str,
+    &'static str,
+    bool,
+    bool,
+    bool,
+    bool,
+    u32,
+    Option<u32>,
+);
+
+#[rustfmt::skip]
+const BUILTIN_CAPABILITIES: &[CapabilityRow] = &[
+    ("openai", "gpt-3.5-turbo", false, true, true, true, 16_385, Some(4_096)),
+    ("openai", "gpt-4", false, true, false, true, 8_192, Some(8_192)),
+    ("openai", "gpt-4-turbo", true, true, true, true, 128_000, Some(4_096)),
+    ("openai", "gpt-4o", true, true, true, true, 128_000, Some(16_384)),
+    ("openai", "gpt-4o-mini", true, true, true, true, 128_000, Some(16_384)),
+    ("openai", "gpt-4.1", true, true, true, true, 1_047_576, Some(32_768)),
+    ("openai", "gpt-4.1-mini", true, true, true, true, 1_047_576, Some(32_768)),
+    ("openai", "gpt-4.1-nano", true, true, true, true, 1_047_576, Some(32_768)),
+    ("openai", "o1", true, true, true, true, 200_000, Some(100_000)),
+    ("openai", "o1-mini", false, false, false, false, 128_000, Some(65_536)),
+    ("openai", "o1-preview", false, false, false, false, 128_000, Some(32_768)),
+    ("openai", "o3", true, true, true, true, 200_000, Some(100_000)),
+    ("openai", "o3-mini", false, true, true, true, 200_000, Some(100_000)),
+    ("openai", "o3-pro", true, true, true, true, 200_000, Some(100_000)),
+    ("openai", "o4-mini", true, true, true, true, 200_000, Some(100_000)),
+    ("anthropic", "claude-3-haiku", true, true, false, true, 200_000, Some(4_096)),
+    ("anthropic", "claude-3-opus", true, true, false, true, 200_000, Some(4_096)),
+    ("anthropic", "claude-3-5-haiku", true, true, false, true, 200_000, Some(8_192)),
+    ("anthropic", "claude-3-5-sonnet", true, true, false, true, 200_000, Some(8_192)),
+    ("anthropic", "claude-3-7-sonnet", true, true, false, true, 200_000, Some(64_000)),
+    ("anthropic", "claude-sonnet-4", true, true, false, true, 200_000, Some(64_000)),
+    ("anthropic", "claude-opus-4", true, true, false, true, 200_000, Some(32_000)),
+    ("gemini", "gemini-1.5-flash", true, true, true, true, 1_048_576, Some(8_192)),
+    ("gemini", "gemini-1.5-pro", true, true, true, true, 2_097_152, Some(8_192)),
+    ("gemini", "gemini-2.0-flash", true, true, true, true, 1_048_576, Some(8_192)),
+    ("gemini", "gemini-2.0-flash-lite", true, true, true, true, 1_048_576, Some(8_192)),
+    ("gemini", "gemini-2.5-flash", true, true, true, true, 1_048_576, Some(65_536)),
+    ("gemini", "gemini-2.5-flash-lite", true, true, true, true, 1_048_576, Some(65_536)),
+    ("gemini", "gemini-2.5-pro", true, true, true, true, 1_048_576, Some(65_536)),
+    ("deepseek", "deepseek-chat", false, true, true, true, 65_536, Some(8_192)),
+    ("deepseek", "deepseek-reasoner", false, false, false, true, 65_536, Some(32_768)),
+    ("xai", "grok-2", false, true, true, true, 131_072, None),
+    ("xai", "grok-2-vision", false, true, true, true, 32_768, None),
+    ("xai", "grok-3", false, true, true, true, 131_072, None),
+    ("xai", "grok-3-mini", false, true, true, true, 131_072, None),
+    ("xai", "grok-4", false, true, true, true, 256_000, None),
+    ("mistral", "mistral-large", false, true, true, true, 131_072, None),
+    ("mistral", "mistral-small", false, true, true, true, 32_768, None),
+    ("mistral", "pixtral", false, true, true, true, 131_072, None),
+    ("mistral", "codestral", false, true, true, true, 256_000, None),
+    ("cohere", "command-r", false, true, true, true, 128_000, Some(4_096)),
+    ("cohere", "command-r-plus", false, true, true, true, 128_000, Some(4_096)),
+    ("cohere", "command-a", false, true, true, true, 256_000, Some(8_000)),
+];
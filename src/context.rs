@@ -0,0 +1,217 @@
+// Context window management: shrink the history of a request that would not fit the
+// model's context window, before it is sent.
+
+use crate::capabilities::{lookup, CapabilityCatalog};
+use crate::core::tokens::TokenCount;
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ModelInfo};
+use crate::error::LlmApiError;
+use crate::providers::LlmProvider;
+use async_trait::async_trait;
+use std::collections::BTreeMap;
+use std::sync::Arc;
+
+pub use crate::core::tokens::estimate_tokens;
+
+/// What to do with the history when a request does not fit.
+///
+/// Leading system messages are always kept. Whatever the strategy, the oldest
+/// remaining messages are dropped afterwards if the request still does not fit, and
+/// `ContextLengthExceeded` is returned if even the last message alone is too large.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TruncationStrategy {
+    /// Drop the oldest messages until the request fits.
+    DropOldest,
+    /// Keep only the last N messages.
+    KeepLast(usize),
+    /// Replace all but the last `keep_last` messages with a summary written by the
+    /// wrapped provider, using the request's model. The summary is added to the system
+    /// prompt.
+    Summarize { keep_last: usize },
+}
+
+/// Wraps a provider and fits every request into the model's context window,
+/// leaving room for `max_tokens` of output.
+///
+/// Context lengths come from the `context_window` of a `CapabilityCatalog` entry,
+/// matched like `CapabilityCatalog::get`, and can be overridden per model. Models
+/// without a known context length are sent unchanged.
+pub struct ContextManager<P> {
+    inner: P,
+    strategy: TruncationStrategy,
+    capabilities: Arc<CapabilityCatalog>,
+    context_lengths: BTreeMap<String, u32>,
+}
+
+impl<P: LlmProvider> ContextManager<P> {
+    /// Uses the built-in capabilities.
+    pub fn new(inner: P, strategy: TruncationStrategy) -> Self {
+        Self {
+            inner,
+            strategy,
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
+            context_lengths: BTreeMap::new(),
+        }
+    }
+
+    /// Read context windows from `catalog` instead of the built-in capabilities.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
+    /// Set or override the context length, in tokens, of `model` and its snapshots.
+    pub fn with_context_length(mut self, model: &str, tokens: u32) -> Self {
+        self.context_lengths.insert(model.to_string(), tokens);
+        self
+    }
+
+    pub fn context_length(&self, model: &str) -> Option<u32> {
+        lookup(&self.context_lengths, model)
+            .copied()
+            .or_else(|| Some(self.capabilities.find(model)?.context_window))
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    /// The request as it would be sent: unchanged if it fits, otherwise with its
+    /// history shortened according to the strategy.
+    pub async fn fit(
+        &self,
+        mut request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionRequest, LlmApiError> {
+        let Some(context_length) = self.context_length(&request.model) else {
+            return Ok(request);
+        };
+        let budget = context_length.saturating_sub(request.max_tokens.unwrap_or(0));
+        if estimate_tokens(&request) <= budget {
+            return Ok(request);
+        }
+
+        let system_count = request
+            .messages
+            .iter()
+            .take_while(|message| message.role == "system")
+            .count();
+        let mut history = request.messages.split_off(system_count);
+
+        match self.strategy {
+            TruncationStrategy::DropOldest => {}
+            TruncationStrategy::KeepLast(count) => {
+                let start = turn_start(&history, history.len().saturating_sub(count));
+                history.drain(..start);
+            }
+            TruncationStrategy::Summarize { keep_last } => {
+                let start = turn_start(&history, history.len().saturating_sub(keep_last));
+                if start > 0 {
+                    let older: Vec<ChatMessage> = history.drain(..start).collect();
+                    let summary = format!(
+                        "Summary of the earlier conversation:\n{}",
+                        self.summarize(&request.model, &older).await?
+                    );
+                    // Part of the system prompt, which providers such as Anthropic
+                    // and Gemini only take ahead of the conversation.
+                    match request.messages.last_mut() {
+                        Some(system) => {
+                            system.content = format!("{}\n\n{}", system.content, summary)
+                        }
+                        None => request.messages.push(ChatMessage {
+                            role: "system".to_string(),
+                            content: summary,
+                            ..Default::default()
+                        }),
+                    }
+                }
+            }
+        }
+
+        let system = request.messages;
+        loop {
+            request.messages = system.iter().chain(history.iter()).cloned().collect();
+            let estimate = estimate_tokens(&request);
+            if estimate <= budget {
+                return Ok(request);
+            }
+            if history.len() <= 1 {
+                return Err(LlmApiError::ContextLengthExceeded(format!(
+                    "request needs about {} tokens, {} allows {}",
+                    estimate, request.model, budget
+                )));
+            }
+            let start = turn_start(&history, 1);
+            history.drain(..start);
+        }
+    }
+
+    async fn summarize(
+        &self,
+        model: &str,
+        messages: &[ChatMessage],
+    ) -> Result<String, LlmApiError> {
+        let transcript: Vec<String> = messages
+            .iter()
+            .filter(|message| !message.content.is_empty())
+            .map(|message| format!("{}: {}", message.role, message.content))
+            .collect();
+        let request = ChatCompletionRequest {
+            model: model.to_string(),
+            messages: vec![
+                ChatMessage {
+                    role: "system".to_string(),
+                    content: SUMMARY_PROMPT.to_string(),
+                    ..Default::default()
+                },
+                ChatMessage {
+                    role: "user".to_string(),
+                    content: transcript.join("\n\n"),
+                    ..Default::default()
+                },
+            ],
+            temperature: Some(0.0),
+            ..Default::default()
+        };
+        let response = self.inner.chat_completion(request).await?;
+        response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message.content)
+            .ok_or_else(|| LlmApiError::ProviderError("summary has no choices".to_string()))
+    }
+}
+
+#[async_trait]
+impl<P: LlmProvider + Send + Sync> LlmProvider for ContextManager<P> {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        let request = self.fit(request).await?;
+        self.inner.chat_completion(request).await
+    }
+
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        self.inner.count_tokens(request).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        self.inner.list_models().await
+    }
+}
+
+/// The first index at or after `index` that does not start with tool results, which
+/// would be orphaned without the assistant message that requested them.
+fn turn_start(history: &[ChatMessage], index: usize) -> usize {
+    let mut start = index.min(history.len());
+    while start < history.len().saturating_sub(1) && history[start].role == "tool" {
+        start += 1;
+    }
+    start
+}
+
+const SUMMARY_PROMPT: &str = "Summarize the following conversation in a few sentences. \
+Keep names, facts, decisions and open questions; leave out pleasantries.";
@@ -0,0 +1,251 @@
+// A chat session that owns its message history.
+// Serializes to JSON so a conversation can be stored between requests and resumed.
+
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse, ChatMessage, Tool, ToolCall};
+use crate::error::LlmApiError;
+use crate::providers::LlmProvider;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+
+/// History, system prompt and request settings for one conversation.
+///
+/// The system prompt is kept apart from the history and sent first on every call, so
+/// it can be changed without rewriting past turns. If a call fails, the history is
+/// left as it was before the call.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct Conversation {
+    pub model: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub system_prompt: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub temperature: Option<f32>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub max_tokens: Option<u32>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub tools: Vec<Tool>,
+    #[serde(default)]
+    messages: Vec<ChatMessage>,
+}
+
+impl Conversation {
+    pub fn new(model: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_system_prompt(mut self, system_prompt: &str) -> Self {
+        self.system_prompt = Some(system_prompt.to_string());
+        self
+    }
+
+    pub fn with_temperature(mut self, temperature: f32) -> Self {
+        self.temperature = Some(temperature);
+        self
+    }
+
+    pub fn with_max_tokens(mut self, max_tokens: u32) -> Self {
+        self.max_tokens = Some(max_tokens);
+        self
+    }
+
+    pub fn with_tools(mut self, tools: Vec<Tool>) -> Self {
+        self.tools = tools;
+        self
+    }
+
+    /// The history, without the system prompt.
+    pub fn messages(&self) -> &[ChatMessage] {
+        &self.messages
+    }
+
+    pub fn push(&mut self, message: ChatMessage) {
+        self.messages.push(message);
+    }
+
+    /// Forget the history but keep the system prompt and settings.
+    pub fn clear(&mut self) {
+        self.messages.clear();
+    }
+
+    /// The request that the next call would send.
+    pub fn request(&self) -> ChatCompletionRequest {
+        let mut messages = Vec::with_capacity(self.messages.len() + 1);
+        if let Some(system_prompt) = &self.system_prompt {
+            messages.push(ChatMessage {
+                role: "system".to_string(),
+                content: system_prompt.clone(),
+                ..Default::default()
+            });
+        }
+        messages.extend(self.messages.iter().cloned());
+
+        ChatCompletionRequest {
+            model: self.model.clone(),
+            messages,
+            temperature: self.temperature,
+            max_tokens: self.max_tokens,
+            tools: (!self.tools.is_empty()).then(|| self.tools.clone()),
+        }
+    }
+
+    /// Add a user message, send the history and return the assistant's reply, which
+    /// is appended to the history as well.
+    ///
+    /// Fails with a `ProviderError` if the model calls a tool instead of answering;
+    /// use `send_with_tools` when the conversation has tools.
+    pub async fn send<P>(&mut self, provider: &P, user_text: &str) -> Result<String, LlmApiError>
+    where
+        P: LlmProvider + ?Sized,
+    {
+        let checkpoint = self.messages.len();
+        self.messages.push(user_message(user_text));
+        let result = match self.complete(provider).await {
+            Ok(message) if message.tool_calls.as_ref().is_some_and(|c| !c.is_empty()) => {
+                Err(LlmApiError::ProviderError(
+                    "model called a tool; use send_with_tools to run it".to_string(),
+                ))
+            }
+            result => result.map(|message| message.content),
+        };
+
+        if result.is_err() {
+            self.messages.truncate(checkpoint);
+        }
+        result
+    }
+
+    /// Like `send`, but runs tool calls requested by the model through `run_tool`
+    /// and sends the results back until the model answers without calling a tool.
+    ///
+    /// Fails with a `ProviderError` if the model is still calling tools after
+    /// `max_rounds` round trips. An error from `run_tool` aborts the call.
+    pub async fn send_with_tools<P, F, Fut>(
+        &mut self,
+        provider: &P,
+        user_text: &str,
+        max_rounds: usize,
+        mut run_tool: F,
+    ) -> Result<String, LlmApiError>
+    where
+        P: LlmProvider + ?Sized,
+        F: FnMut(ToolCall) -> Fut,
+        Fut: Future<Output = Result<String, LlmApiError>>,
+    {
+        let checkpoint = self.messages.len();
+        self.messages.push(user_message(user_text));
+
+        let result = self
+            .run_tool_loop(provider, max_rounds, |_, message| {
+                // `run_tool` only builds the futures here; they still run one at a time.
+                let calls: Vec<Fut> = message
+                    .tool_calls
+                    .unwrap_or_default()
+                    .into_iter()
+                    .map(&mut run_tool)
+                    .collect();
+                async move {
+                    let mut outputs = Vec::with_capacity(calls.len());
+                    for call in calls {
+                        outputs.push(call.await?);
+                    }
+                    Ok(outputs)
+                }
+            })
+            .await
+            .map(|(message, _)| message.content);
+
+        if result.is_err() {
+            self.messages.truncate(checkpoint);
+        }
+        result
+    }
+
+    pub fn to_json(&self) -> Result<String, LlmApiError> {
+        serde_json::to_string(self).map_err(|e| LlmApiError::SerializationError(e.to_string()))
+    }
+
+    pub fn from_json(json: &str) -> Result<Self, LlmApiError> {
+        serde_json::from_str(json).map_err(|e| LlmApiError::DeserializationError(e.to_string()))
+    }
+
+    /// A conversation whose history is `request`'s messages.
+    pub(crate) fn from_request(request: ChatCompletionRequest) -> Self {
+        Self {
+            model: request.model,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            tools: request.tools.unwrap_or_default(),
+            messages: request.messages,
+            ..Default::default()
+        }
+    }
+
+    /// The tool loop behind `send_with_tools` and `Agent::run`. Each response that
+    /// calls tools is passed to `run_tools` with its 1-based round, and the outputs,
+    /// one per call and in call order, are appended as tool messages. Returns the
+    /// final message and the number of model calls it took.
+    pub(crate) async fn run_tool_loop<P, F, Fut>(
+        &mut self,
+        provider: &P,
+        max_rounds: usize,
+        mut run_tools: F,
+    ) -> Result<(ChatMessage, usize), LlmApiError>
+    where
+        P: LlmProvider + ?Sized,
+        F: FnMut(usize, ChatMessage) -> Fut,
+        Fut: Future<Output = Result<Vec<String>, LlmApiError>>,
+    {
+        for round in 1..=max_rounds {
+            let message = self.complete(provider).await?;
+            let ids: Vec<String> = match &message.tool_calls {
+                Some(calls) if !calls.is_empty() => {
+                    calls.iter().map(|call| call.id.clone()).collect()
+                }
+                _ => return Ok((message, round)),
+            };
+            let outputs = run_tools(round, message).await?;
+            for (id, output) in ids.into_iter().zip(outputs) {
+                self.messages.push(ChatMessage {
+                    role: "tool".to_string(),
+                    content: output,
+                    tool_call_id: Some(id),
+                    ..Default::default()
+                });
+            }
+        }
+        Err(LlmApiError::ProviderError(format!(
+            "model still calling tools after {} rounds",
+            max_rounds
+        )))
+    }
+
+    /// Send the current history and append the first choice to it. The history
+    /// copy leaves out the reasoning summary, which is not part of the conversation.
+    async fn complete<P>(&mut self, provider: &P) -> Result<ChatMessage, LlmApiError>
+    where
+        P: LlmProvider + ?Sized,
+    {
+        let response: ChatCompletionResponse = provider.chat_completion(self.request()).await?;
+        let message = response
+            .choices
+            .into_iter()
+            .next()
+            .map(|choice| choice.message)
+            .ok_or_else(|| LlmApiError::ProviderError("response has no choices".to_string()))?;
+        self.messages.push(ChatMessage {
+            reasoning: None,
+            ..message.clone()
+        });
+        Ok(message)
+    }
+}
+
+fn user_message(content: &str) -> ChatMessage {
+    ChatMessage {
+        role: "user".to_string(),
+        content: content.to_string(),
+        ..Default::default()
+    }
+}
@@ -0,0 +1,198 @@
+// Record-and-replay of HTTP traffic for deterministic tests.
+// Credentials are redacted before anything is written to disk.
+
+use crate::core::middleware::{HttpRequest, HttpResponse};
+use crate::error::LlmApiError;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+const REDACTED: &str = "[REDACTED]";
+const SECRET_HEADERS: &[&str] = &["authorization", "x-api-key", "x-goog-api-key", "api-key"];
+const SECRET_QUERY_PARAMS: &[&str] = &["key", "api_key"];
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum CassetteMode {
+    /// Send requests to the network and append each exchange to the cassette file.
+    Record,
+    /// Serve responses from the cassette file; requests without a match fail.
+    Replay,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Interaction {
+    pub request: RecordedRequest,
+    pub response: RecordedResponse,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedRequest {
+    pub method: String,
+    pub url: String,
+    pub headers: BTreeMap<String, String>,
+    pub body: serde_json::Value,
+}
+
+/// The response body is kept verbatim, so non-JSON bodies round-trip unchanged.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct RecordedResponse {
+    pub status: u16,
+    #[serde(default)]
+    pub headers: BTreeMap<String, String>,
+    pub body: String,
+}
+
+#[derive(Debug, Default, Serialize, Deserialize)]
+struct CassetteFile {
+    interactions: Vec<Interaction>,
+}
+
+pub struct Cassette {
+    path: PathBuf,
+    mode: CassetteMode,
+    interactions: Mutex<Vec<(Interaction, bool)>>,
+}
+
+impl Cassette {
+    /// Start a new recording at `path`, replacing any existing cassette there.
+    pub fn record(path: impl AsRef<Path>) -> Self {
+        Self {
+            path: path.as_ref().to_path_buf(),
+            mode: CassetteMode::Record,
+            interactions: Mutex::new(Vec::new()),
+        }
+    }
+
+    /// Load a previously recorded cassette for replay.
+    pub fn replay(path: impl AsRef<Path>) -> Result<Self, LlmApiError> {
+        let path = path.as_ref().to_path_buf();
+        let data = std::fs::read_to_string(&path).map_err(|e| {
+            LlmApiError::ConfigError(format!("cannot read cassette {}: {}", path.display(), e))
+        })?;
+        let file: CassetteFile = serde_json::from_str(&data)
+            .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?;
+        Ok(Self {
+            path,
+            mode: CassetteMode::Replay,
+            interactions: Mutex::new(file.interactions.into_iter().map(|i| (i, false)).collect()),
+        })
+    }
+
+    pub fn mode(&self) -> CassetteMode {
+        self.mode
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Return the first unused recorded response whose method, redacted URL and body match.
+    pub fn find(&self, request: &HttpRequest) -> Result<HttpResponse, LlmApiError> {
+        let method = request.method.as_str();
+        let url = redact_url(&request.url);
+        let mut interactions = self.interactions.lock().unwrap();
+        let entry = interactions.iter_mut().find(|(interaction, used)| {
+            !*used
+                && interaction.request.method == method
+                && interaction.request.url == url
+                && interaction.request.body == request.body
+        });
+        match entry {
+            Some((interaction, used)) => {
+                *used = true;
+                let recorded = &interaction.response;
+                let mut headers = HeaderMap::new();
+                for (name, value) in &recorded.headers {
+                    if let (Ok(name), Ok(value)) = (
+                        HeaderName::from_bytes(name.as_bytes()),
+                        HeaderValue::from_str(value),
+                    ) {
+                        headers.insert(name, value);
+                    }
+                }
+                Ok(HttpResponse {
+                    status: recorded.status,
+                    headers,
+                    body: recorded.body.clone(),
+                })
+            }
+            None => Err(LlmApiError::ConfigError(format!(
+                "no unused interaction in cassette {} matches {} {}",
+                self.path.display(),
+                method,
+                url
+            ))),
+        }
+    }
+
+    /// Append an exchange and rewrite the cassette file.
+    pub fn append(
+        &self,
+        request: &HttpRequest,
+        response: &HttpResponse,
+    ) -> Result<(), LlmApiError> {
+        let request = RecordedRequest {
+            method: request.method.to_string(),
+            url: redact_url(&request.url),
+            headers: redact_headers(&request.headers),
+            body: request.body.clone(),
+        };
+        let response = RecordedResponse {
+            status: response.status,
+            headers: redact_headers(&response.headers),
+            body: response.body.clone(),
+        };
+
+        let mut interactions = self.interactions.lock().unwrap();
+        interactions.push((Interaction { request, response }, true));
+
+        let file = CassetteFile {
+            interactions: interactions.iter().map(|(i, _)| i.clone()).collect(),
+        };
+        let data = serde_json::to_string_pretty(&file)
+            .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+        if let Some(dir) = self.path.parent() {
+            std::fs::create_dir_all(dir).map_err(|e| LlmApiError::ConfigError(e.to_string()))?;
+        }
+        std::fs::write(&self.path, data).map_err(|e| {
+            LlmApiError::ConfigError(format!(
+                "cannot write cassette {}: {}",
+                self.path.display(),
+                e
+            ))
+        })
+    }
+}
+
+fn redact_headers(headers: &HeaderMap) -> BTreeMap<String, String> {
+    headers
+        .iter()
+        .map(|(name, value)| {
+            let value = if SECRET_HEADERS.contains(&name.as_str()) {
+                REDACTED.to_string()
+            } else {
+                String::from_utf8_lossy(value.as_bytes()).into_owned()
+            };
+            (name.to_string(), value)
+        })
+        .collect()
+}
+
+fn redact_url(url: &str) -> String {
+    let Some((base, query)) = url.split_once('?') else {
+        return url.to_string();
+    };
+    let query = query
+        .split('&')
+        .map(|pair| match pair.split_once('=') {
+            Some((name, _)) if SECRET_QUERY_PARAMS.contains(&name) => {
+                format!("{}={}", name, REDACTED)
+            }
+            _ => pair.to_string(),
+        })
+        .collect::<Vec<_>>()
+        .join("&");
+    format!("{}?{}", base, query)
+}
@@ -1,9 +1,21 @@
+use crate::core::cassette::{Cassette, CassetteMode};
+use crate::core::middleware::{HttpRequest, HttpResponse, Middleware};
+use crate::core::runtime::{self, SystemTime, UNIX_EPOCH};
 use crate::error::LlmApiError;
-use reqwest::{header::HeaderName, Client};
+use base64::Engine;
+use reqwest::header::{HeaderMap, HeaderName, HeaderValue};
+use reqwest::{Client, Method};
 use serde::{de::DeserializeOwned, Serialize};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::Arc;
 
+static NEXT_REQUEST_ID: AtomicU64 = AtomicU64::new(1);
+
+#[derive(Clone)]
 pub struct APIClient {
     client: Client,
+    cassette: Option<Arc<Cassette>>,
+    middleware: Vec<Arc<dyn Middleware>>,
 }
 
 impl Default for APIClient {
@@ -16,9 +28,23 @@ impl APIClient {
     pub fn new() -> Self {
         Self {
             client: Client::new(),
+            cassette: None,
+            middleware: Vec::new(),
         }
     }
 
+    /// Record traffic to, or replay it from, `cassette` depending on its mode.
+    pub fn with_cassette(mut self, cassette: Arc<Cassette>) -> Self {
+        self.cassette = Some(cassette);
+        self
+    }
+
+    /// Add a middleware to the end of the chain.
+    pub fn with_middleware(mut self, middleware: Arc<dyn Middleware>) -> Self {
+        self.middleware.push(middleware);
+        self
+    }
+
     pub async fn send_request<T, U>(
         &self,
         url: String,
@@ -29,30 +55,581 @@ impl APIClient {
         T: Serialize + ?Sized,
         U: DeserializeOwned,
     {
-        let mut req = self.client.post(url);
+        let body = serde_json::to_value(request)
+            .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+        self.send(Method::POST, url, headers, Payload::Json(body), parse_json)
+            .await
+    }
+
+    /// GET `url` and decode the JSON response.
+    pub async fn get_request<U>(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+    ) -> Result<U, LlmApiError>
+    where
+        U: DeserializeOwned,
+    {
+        self.send(Method::GET, url, headers, Payload::Empty, parse_json)
+            .await
+    }
+
+    /// GET `url` and return the response body as it is, e.g. for JSONL downloads.
+    pub async fn get_text(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+    ) -> Result<String, LlmApiError> {
+        self.send(Method::GET, url, headers, Payload::Empty, |response| {
+            Ok(response.body)
+        })
+        .await
+    }
+
+    /// DELETE `url` and decode the JSON response.
+    pub async fn delete_request<U>(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+    ) -> Result<U, LlmApiError>
+    where
+        U: DeserializeOwned,
+    {
+        self.send(Method::DELETE, url, headers, Payload::Empty, parse_json)
+            .await
+    }
+
+    /// POST a JSON body and return the whole response, for endpoints that answer in
+    /// headers rather than the body.
+    pub async fn send_request_raw<T>(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+        request: &T,
+    ) -> Result<HttpResponse, LlmApiError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let body = serde_json::to_value(request)
+            .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+        self.send(Method::POST, url, headers, Payload::Json(body), Ok)
+            .await
+    }
+
+    /// POST raw bytes and decode the JSON response.
+    ///
+    /// Middleware and cassettes see the body as its content type and size.
+    pub async fn send_bytes<U>(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+        content_type: &str,
+        data: Vec<u8>,
+    ) -> Result<U, LlmApiError>
+    where
+        U: DeserializeOwned,
+    {
+        let payload = Payload::Bytes {
+            content_type: content_type.to_string(),
+            data,
+        };
+        self.send(Method::POST, url, headers, payload, parse_json)
+            .await
+    }
+
+    /// POST a `multipart/form-data` body and decode the JSON response.
+    pub async fn send_multipart<U>(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+        form: Multipart,
+    ) -> Result<U, LlmApiError>
+    where
+        U: DeserializeOwned,
+    {
+        self.send(
+            Method::POST,
+            url,
+            headers,
+            Payload::Multipart(form),
+            parse_json,
+        )
+        .await
+    }
+
+    /// POST a JSON body and return the response body as bytes, e.g. for audio.
+    ///
+    /// Middleware and cassettes see the response body base64-encoded.
+    pub async fn send_request_bytes<T>(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+        request: &T,
+    ) -> Result<Vec<u8>, LlmApiError>
+    where
+        T: Serialize + ?Sized,
+    {
+        let body = serde_json::to_value(request)
+            .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+        let payload = Payload::Json(body);
+        let call = self.prepare(Method::POST, url, headers, &payload).await?;
+        let result = self
+            .dispatch(&call.request, &payload, true)
+            .await
+            .and_then(parse_base64);
+        call.finish(result).await
+    }
 
+    /// POST a JSON body and return the response body as it arrives, chunk by chunk.
+    ///
+    /// Middleware sees the request and the response status and headers, but not the
+    /// body. With a cassette the body is read in full, recorded or replayed like
+    /// `send_request_bytes`, and returned as one chunk.
+    pub async fn send_request_stream<T>(
+        &self,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+        request: &T,
+    ) -> Result<ByteStream, LlmApiError>
+    where
+        T: Serialize + ?Sized,
+    {
+        if self.cassette.is_some() {
+            let data = self.send_request_bytes(url, headers, request).await?;
+            return Ok(ByteStream::from_bytes(data));
+        }
+        let body = serde_json::to_value(request)
+            .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+        let payload = Payload::Json(body);
+        let call = self.prepare(Method::POST, url, headers, &payload).await?;
+        // Natively `local` does nothing; on wasm32 it lets the fetch response through
+        // the `Send` futures providers return.
+        let result = runtime::local(self.open(&call.request, &payload))
+            .await
+            .map(ByteStream::from_response);
+        call.finish(result).await
+    }
+
+    async fn send<U>(
+        &self,
+        method: Method,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+        payload: Payload,
+        decode: fn(HttpResponse) -> Result<U, LlmApiError>,
+    ) -> Result<U, LlmApiError> {
+        let call = self.prepare(method, url, headers, &payload).await?;
+        let result = self
+            .dispatch(&call.request, &payload, false)
+            .await
+            .and_then(decode);
+        call.finish(result).await
+    }
+
+    // Build the request middleware and cassettes see, and run `before_request`.
+    async fn prepare(
+        &self,
+        method: Method,
+        url: String,
+        headers: Vec<(HeaderName, String)>,
+        payload: &Payload,
+    ) -> Result<InFlight<'_>, LlmApiError> {
+        let mut header_map = HeaderMap::new();
         for (key, value) in headers {
-            req = req.header(key, value);
+            let value = HeaderValue::from_str(&value)
+                .map_err(|e| LlmApiError::ConfigError(e.to_string()))?;
+            header_map.insert(key, value);
+        }
+
+        let body = match payload {
+            Payload::Json(body) => body.clone(),
+            Payload::Empty => serde_json::Value::Null,
+            Payload::Multipart(form) => form.summary(),
+            Payload::Bytes { content_type, data } => serde_json::json!({
+                "content_type": content_type,
+                "bytes": data.len(),
+            }),
+        };
+        let mut call = InFlight {
+            middleware: &self.middleware,
+            request: HttpRequest {
+                id: NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed),
+                method,
+                url,
+                headers: header_map,
+                body,
+            },
+            started: 0,
+        };
+
+        for (i, middleware) in self.middleware.iter().enumerate() {
+            if let Err(error) = middleware.before_request(&mut call.request).await {
+                return call.finish(Err(error)).await;
+            }
+            call.started = i + 1;
+        }
+        Ok(call)
+    }
+
+    async fn dispatch(
+        &self,
+        request: &HttpRequest,
+        payload: &Payload,
+        binary: bool,
+    ) -> Result<HttpResponse, LlmApiError> {
+        let mut response = match &self.cassette {
+            Some(cassette) if cassette.mode() == CassetteMode::Replay => cassette.find(request)?,
+            _ => {
+                let response = runtime::local(self.execute(request, payload, binary)).await?;
+                if let Some(cassette) = &self.cassette {
+                    cassette.append(request, &response)?;
+                }
+                response
+            }
+        };
+
+        for middleware in self.middleware.iter().rev() {
+            middleware.after_response(request, &mut response).await?;
+        }
+
+        if !response.is_success() {
+            return Err(LlmApiError::from_response(response.status, response.body));
         }
+        Ok(response)
+    }
+
+    // Like `dispatch`, but a successful response is returned unread.
+    async fn open(
+        &self,
+        request: &HttpRequest,
+        payload: &Payload,
+    ) -> Result<reqwest::Response, LlmApiError> {
+        let response = self.start(request, payload).await?;
+        let mut head = HttpResponse {
+            status: response.status().as_u16(),
+            headers: response.headers().clone(),
+            body: String::new(),
+        };
+        let response = if head.is_success() {
+            Some(response)
+        } else {
+            head.body = response
+                .text()
+                .await
+                .map_err(|e| LlmApiError::NetworkError(e.to_string()))?;
+            None
+        };
+
+        for middleware in self.middleware.iter().rev() {
+            middleware.after_response(request, &mut head).await?;
+        }
+
+        match response {
+            Some(response) if head.is_success() => Ok(response),
+            _ => Err(LlmApiError::from_response(head.status, head.body)),
+        }
+    }
+
+    // Successful binary bodies are base64-encoded so they fit `HttpResponse::body`.
+    async fn execute(
+        &self,
+        request: &HttpRequest,
+        payload: &Payload,
+        binary: bool,
+    ) -> Result<HttpResponse, LlmApiError> {
+        let response = self.start(request, payload).await?;
+
+        let status = response.status().as_u16();
+        let headers = response.headers().clone();
+        let body = if binary && response.status().is_success() {
+            let data = response
+                .bytes()
+                .await
+                .map_err(|e| LlmApiError::NetworkError(e.to_string()))?;
+            base64::engine::general_purpose::STANDARD.encode(data)
+        } else {
+            response
+                .text()
+                .await
+                .map_err(|e| LlmApiError::NetworkError(e.to_string()))?
+        };
 
-        let response = req
-            .json(request)
+        Ok(HttpResponse {
+            status,
+            headers,
+            body,
+        })
+    }
+
+    async fn start(
+        &self,
+        request: &HttpRequest,
+        payload: &Payload,
+    ) -> Result<reqwest::Response, LlmApiError> {
+        let builder = self
+            .client
+            .request(request.method.clone(), &request.url)
+            .headers(request.headers.clone());
+        // Middleware may rewrite a JSON body, so that one is sent from `request`.
+        let builder = match payload {
+            Payload::Json(_) => builder.json(&request.body),
+            Payload::Empty => builder,
+            Payload::Multipart(form) => builder
+                .header(
+                    reqwest::header::CONTENT_TYPE,
+                    format!("multipart/form-data; boundary={}", form.boundary),
+                )
+                .body(form.encode()),
+            Payload::Bytes { content_type, data } => builder
+                .header(reqwest::header::CONTENT_TYPE, content_type.as_str())
+                .body(data.clone()),
+        };
+        builder
             .send()
             .await
-            .map_err(|e| LlmApiError::NetworkError(e.to_string()))?;
+            .map_err(|e| LlmApiError::NetworkError(e.to_string()))
+    }
+}
 
-        if !response.status().is_success() {
-            let error_text = response
-                .json()
-                .await
-                .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?;
-            let error = LlmApiError::NetworkError(error_text);
-            return Err(error);
+// A call whose `before_request` hooks have run. If it is dropped before `finish`,
+// e.g. because the caller's future was cancelled, the middleware that saw
+// `before_request` gets `on_cancel` instead of an `after_response` or `on_error`.
+struct InFlight<'a> {
+    middleware: &'a [Arc<dyn Middleware>],
+    request: HttpRequest,
+    // How many middleware, from the front, still expect a closing hook.
+    started: usize,
+}
+
+impl InFlight<'_> {
+    async fn finish<U>(mut self, result: Result<U, LlmApiError>) -> Result<U, LlmApiError> {
+        if let Err(error) = &result {
+            while self.started > 0 {
+                self.started -= 1;
+                self.middleware[self.started]
+                    .on_error(&self.request, error)
+                    .await;
+            }
+        }
+        self.started = 0;
+        result
+    }
+}
+
+impl Drop for InFlight<'_> {
+    fn drop(&mut self) {
+        for middleware in self.middleware[..self.started].iter().rev() {
+            middleware.on_cancel(&self.request);
+        }
+    }
+}
+
+enum Payload {
+    Json(serde_json::Value),
+    Empty,
+    Multipart(Multipart),
+    Bytes { content_type: String, data: Vec<u8> },
+}
+
+fn parse_base64(response: HttpResponse) -> Result<Vec<u8>, LlmApiError> {
+    base64::engine::general_purpose::STANDARD
+        .decode(response.body)
+        .map_err(|e| LlmApiError::DeserializationError(e.to_string()))
+}
+
+fn parse_json<U: DeserializeOwned>(response: HttpResponse) -> Result<U, LlmApiError> {
+    serde_json::from_str(&response.body)
+        .map_err(|e| LlmApiError::DeserializationError(e.to_string()))
+}
+
+/// A `multipart/form-data` body of text fields and files.
+///
+/// Middleware and cassettes see it as a JSON object of the text fields, with each file
+/// replaced by its name, content type and size.
+#[derive(Debug, Clone)]
+pub struct Multipart {
+    boundary: String,
+    parts: Vec<Part>,
+}
+
+#[derive(Debug, Clone)]
+struct Part {
+    name: String,
+    file: Option<(String, String)>,
+    data: Vec<u8>,
+}
+
+impl Default for Multipart {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Multipart {
+    pub fn new() -> Self {
+        // Unique enough that it cannot plausibly occur inside a part.
+        let nanos = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_nanos())
+            .unwrap_or(0);
+        Self {
+            boundary: format!(
+                "llm-api-rs-{:x}-{:x}",
+                nanos,
+                NEXT_REQUEST_ID.fetch_add(1, Ordering::Relaxed)
+            ),
+            parts: Vec::new(),
+        }
+    }
+
+    pub fn text(mut self, name: &str, value: &str) -> Self {
+        self.parts.push(Part {
+            name: name.to_string(),
+            file: None,
+            data: value.as_bytes().to_vec(),
+        });
+        self
+    }
+
+    pub fn file(mut self, name: &str, filename: &str, content_type: &str, data: Vec<u8>) -> Self {
+        self.parts.push(Part {
+            name: name.to_string(),
+            file: Some((filename.to_string(), content_type.to_string())),
+            data,
+        });
+        self
+    }
+
+    fn summary(&self) -> serde_json::Value {
+        let mut fields = serde_json::Map::new();
+        for part in &self.parts {
+            let value = match &part.file {
+                Some((filename, content_type)) => serde_json::json!({
+                    "filename": filename,
+                    "content_type": content_type,
+                    "bytes": part.data.len(),
+                }),
+                None => String::from_utf8_lossy(&part.data).into_owned().into(),
+            };
+            // Repeated fields, such as `timestamp_granularities[]`, become arrays.
+            match fields.get_mut(&part.name) {
+                Some(serde_json::Value::Array(values)) => values.push(value),
+                Some(first) => *first = serde_json::Value::Array(vec![first.take(), value]),
+                None => {
+                    fields.insert(part.name.clone(), value);
+                }
+            }
+        }
+        serde_json::Value::Object(fields)
+    }
+
+    fn encode(&self) -> Vec<u8> {
+        let mut body = Vec::new();
+        for part in &self.parts {
+            body.extend_from_slice(format!("--{}\r\n", self.boundary).as_bytes());
+            match &part.file {
+                Some((filename, content_type)) => body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"; filename=\"{}\"\r\nContent-Type: {}\r\n\r\n",
+                        escape(&part.name),
+                        escape(filename),
+                        content_type
+                    )
+                    .as_bytes(),
+                ),
+                None => body.extend_from_slice(
+                    format!(
+                        "Content-Disposition: form-data; name=\"{}\"\r\n\r\n",
+                        escape(&part.name)
+                    )
+                    .as_bytes(),
+                ),
+            }
+            body.extend_from_slice(&part.data);
+            body.extend_from_slice(b"\r\n");
+        }
+        body.extend_from_slice(format!("--{}--\r\n", self.boundary).as_bytes());
+        body
+    }
+}
+
+// Quoted `name` and `filename` values, escaped the way browsers encode form data, so
+// a quote or line break cannot end the header early.
+fn escape(value: &str) -> String {
+    value
+        .replace('"', "%22")
+        .replace('\r', "%0D")
+        .replace('\n', "%0A")
+}
+
+/// A response body read chunk by chunk, as it arrives.
+#[derive(Debug)]
+pub struct ByteStream {
+    inner: StreamInner,
+}
+
+#[derive(Debug)]
+enum StreamInner {
+    #[cfg(not(target_arch = "wasm32"))]
+    Response(reqwest::Response),
+    // The fetch body stream.
+    #[cfg(target_arch = "wasm32")]
+    Response(runtime::SingleThreaded<FetchBody>),
+    Buffered(Option<Vec<u8>>),
+}
+
+#[cfg(target_arch = "wasm32")]
+type FetchBody = std::pin::Pin<Box<dyn futures_core::Stream<Item = reqwest::Result<bytes::Bytes>>>>;
+
+impl ByteStream {
+    #[cfg(not(target_arch = "wasm32"))]
+    fn from_response(response: reqwest::Response) -> Self {
+        Self {
+            inner: StreamInner::Response(response),
         }
+    }
 
-        response
-            .json()
+    #[cfg(target_arch = "wasm32")]
+    fn from_response(response: reqwest::Response) -> Self {
+        Self {
+            inner: StreamInner::Response(runtime::local(Box::pin(response.bytes_stream()))),
+        }
+    }
+
+    /// A stream of a body that has already been read, as one chunk.
+    pub fn from_bytes(data: Vec<u8>) -> Self {
+        Self {
+            inner: StreamInner::Buffered(Some(data)),
+        }
+    }
+
+    /// The next chunk, or `None` once the body has been read.
+    pub async fn next_chunk(&mut self) -> Result<Option<Vec<u8>>, LlmApiError> {
+        match &mut self.inner {
+            #[cfg(not(target_arch = "wasm32"))]
+            StreamInner::Response(response) => response
+                .chunk()
+                .await
+                .map(|chunk| chunk.map(|chunk| chunk.to_vec()))
+                .map_err(|e| LlmApiError::NetworkError(e.to_string())),
+            #[cfg(target_arch = "wasm32")]
+            StreamInner::Response(body) => runtime::local(std::future::poll_fn(|cx| {
+                futures_core::Stream::poll_next(body.0.as_mut(), cx)
+            }))
             .await
-            .map_err(|e| LlmApiError::DeserializationError(e.to_string()))
+            .transpose()
+            .map(|chunk| chunk.map(|chunk| chunk.to_vec()))
+            .map_err(|e| LlmApiError::NetworkError(e.to_string())),
+            StreamInner::Buffered(data) => Ok(data.take()),
+        }
+    }
+
+    /// Read the rest of the body.
+    pub async fn collect(mut self) -> Result<Vec<u8>, LlmApiError> {
+        let mut data = Vec::new();
+        while let Some(chunk) = self.next_chunk().await? {
+            data.extend_from_slice(&chunk);
+        }
+        Ok(data)
     }
 }
@@ -0,0 +1,60 @@
+// Hooks around every HTTP call made by `APIClient`.
+
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use reqwest::Method;
+
+/// A provider request after serialization, before it is sent.
+#[derive(Debug, Clone)]
+pub struct HttpRequest {
+    /// Unique per call, for correlating the hooks of one request.
+    pub id: u64,
+    pub method: Method,
+    pub url: String,
+    pub headers: HeaderMap,
+    pub body: serde_json::Value,
+}
+
+/// A raw provider response, before it is deserialized.
+#[derive(Debug, Clone)]
+pub struct HttpResponse {
+    pub status: u16,
+    pub headers: HeaderMap,
+    pub body: String,
+}
+
+impl HttpResponse {
+    pub fn is_success(&self) -> bool {
+        (200..300).contains(&self.status)
+    }
+}
+
+/// Cross-cutting behavior for every provider call.
+///
+/// `before_request` hooks run in the order the middleware was added; `after_response`
+/// and `on_error` run in reverse, so the first middleware wraps all the others.
+/// Returning an error from a hook aborts the call with that error. If a `before_request`
+/// hook fails, `on_error` is called on the middleware whose `before_request` already ran.
+#[async_trait]
+pub trait Middleware: Send + Sync {
+    async fn before_request(&self, _request: &mut HttpRequest) -> Result<(), LlmApiError> {
+        Ok(())
+    }
+
+    async fn after_response(
+        &self,
+        _request: &HttpRequest,
+        _response: &mut HttpResponse,
+    ) -> Result<(), LlmApiError> {
+        Ok(())
+    }
+
+    /// Called for transport failures, non-success statuses and undecodable bodies.
+    async fn on_error(&self, _request: &HttpRequest, _error: &LlmApiError) {}
+
+    /// Called when the call is dropped before it finished, e.g. by a timeout, to
+    /// release anything `before_request` holds. It runs from `Drop`, so it cannot
+    /// wait, and it may follow an `after_response` that was interrupted.
+    fn on_cancel(&self, _request: &HttpRequest) {}
+}
@@ -1,40 +1,307 @@
+pub mod cassette;
 pub mod client;
+pub mod middleware;
+pub mod rate_limit;
+pub(crate) mod runtime;
+pub mod telemetry;
+pub mod tokens;
 
-use serde::{Deserialize, Serialize};
+use crate::error::LlmApiError;
+use serde::{Deserialize, Deserializer, Serialize};
 use thiserror::Error;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ChatMessage {
     pub role: String,
+    #[serde(default, deserialize_with = "null_as_empty")]
     pub content: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub tool_call_id: Option<String>,
+    /// Reasoning summary returned by reasoning models, when the provider exposes one.
+    /// Providers never send it back to the model.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<String>,
+    /// Content sent along with `content`, such as uploaded files. OpenAI, Anthropic
+    /// and Gemini resolve these into their own content blocks; the other providers
+    /// reject messages that have them.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub parts: Vec<ContentPart>,
 }
 
-#[derive(Debug, Serialize)]
+/// Message content other than plain text.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum ContentPart {
+    /// A file uploaded with `files::FileProvider`, referenced instead of inlined.
+    File(FileHandle),
+}
+
+impl From<FileHandle> for ContentPart {
+    fn from(file: FileHandle) -> Self {
+        ContentPart::File(file)
+    }
+}
+
+/// A file stored with a provider. It can only be used with the provider that holds it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FileHandle {
+    /// `openai`, `anthropic` or `gemini`.
+    pub provider: String,
+    /// The provider's id, e.g. `file-abc` for OpenAI or `files/abc` for Gemini.
+    pub id: String,
+    pub filename: String,
+    pub mime_type: String,
+    pub size_bytes: u64,
+    /// The URI Gemini requests reference the file by.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub uri: Option<String>,
+}
+
+impl FileHandle {
+    /// Fail unless the file is held by `provider`.
+    #[cfg_attr(
+        not(any(feature = "openai", feature = "anthropic", feature = "gemini")),
+        allow(dead_code)
+    )]
+    pub(crate) fn check_provider(&self, provider: &str) -> Result<(), LlmApiError> {
+        if self.provider == provider {
+            return Ok(());
+        }
+        Err(LlmApiError::ConfigError(format!(
+            "file {} was uploaded to {}, not {}",
+            self.id, self.provider, provider
+        )))
+    }
+}
+
+/// A model a provider offers, normalized across providers' model listings.
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+pub struct ModelInfo {
+    /// The id to put in `ChatCompletionRequest::model`.
+    pub id: String,
+    pub provider: String,
+    pub display_name: Option<String>,
+    /// Who published the model, when the provider says.
+    pub owned_by: Option<String>,
+    /// When the model was created or, for Ollama, last pulled, in seconds since the
+    /// Unix epoch.
+    pub created: Option<u64>,
+    pub input_token_limit: Option<u32>,
+    pub output_token_limit: Option<u32>,
+    /// Methods the model supports, e.g. `generateContent`. Only Gemini reports these.
+    pub generation_methods: Vec<String>,
+}
+
+// Seconds since the Unix epoch for an RFC 3339 timestamp such as
+// `2025-02-19T00:00:00Z` or `2024-05-01T10:11:12.5-07:00`.
+#[cfg_attr(not(any(feature = "anthropic", feature = "ollama")), allow(dead_code))]
+pub(crate) fn unix_timestamp(value: &str) -> Option<u64> {
+    let number = |from: usize, to: usize| value.get(from..to)?.parse::<i64>().ok();
+    let (year, month, day) = (number(0, 4)?, number(5, 7)?, number(8, 10)?);
+    let (hour, minute, second) = (number(11, 13)?, number(14, 16)?, number(17, 19)?);
+    let zone = value
+        .get(19..)?
+        .trim_start_matches(|c: char| c == '.' || c.is_ascii_digit());
+    let offset = match zone {
+        "Z" | "z" => 0,
+        _ => {
+            let sign = match zone.get(..1)? {
+                "+" => 1,
+                "-" => -1,
+                _ => return None,
+            };
+            let hours = zone.get(1..3)?.parse::<i64>().ok()?;
+            let minutes = zone.get(4..6)?.parse::<i64>().ok()?;
+            sign * (hours * 3600 + minutes * 60)
+        }
+    };
+    // Days since 1970-01-01, counting years from March so leap days come last.
+    let (year, month) = if month <= 2 {
+        (year - 1, month + 9)
+    } else {
+        (year, month - 3)
+    };
+    let era = year.div_euclid(400);
+    let year_of_era = year - era * 400;
+    let day_of_year = (153 * month + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    let days = era * 146_097 + day_of_era - 719_468;
+    u64::try_from(days * 86_400 + hour * 3600 + minute * 60 + second - offset).ok()
+}
+
+// Assistant messages that only call tools come back with `"content": null`.
+fn null_as_empty<'de, D: Deserializer<'de>>(deserializer: D) -> Result<String, D::Error> {
+    Ok(Option::<String>::deserialize(deserializer)?.unwrap_or_default())
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct ChatCompletionRequest {
     pub model: String,
     pub messages: Vec<ChatMessage>,
     pub temperature: Option<f32>,
     pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ChatCompletionResponse {
     pub id: String,
     pub model: String,
     pub choices: Vec<ChatChoice>,
     pub usage: Option<ChatUsage>,
+    #[serde(default)]
+    pub metadata: ResponseMetadata,
+}
+
+/// Information about how a response was produced, filled in by this crate rather
+/// than the provider.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ResponseMetadata {
+    /// The response was served from a cache instead of the provider.
+    pub cache_hit: bool,
 }
 
-#[derive(Debug, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct ChatChoice {
     pub message: ChatMessage,
     pub finish_reason: String,
 }
 
-#[derive(Debug, Deserialize)]
+/// Token counts normalized across providers. `input_tokens` includes cached input
+/// tokens and cache writes, and `output_tokens` includes reasoning tokens.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
 pub struct ChatUsage {
-    pub input_tokens: Option<u32>,  // Anthropic only
-    pub output_tokens: Option<u32>, // Anthropic only
+    pub input_tokens: Option<u32>,
+    pub output_tokens: Option<u32>,
+    pub cached_input_tokens: Option<u32>,
+    pub reasoning_tokens: Option<u32>,
+    /// Input tokens written to the prompt cache. Only Anthropic reports these.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write_tokens: Option<u32>,
+}
+
+/// A function the model may call, in the OpenAI `tools` shape.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Tool {
+    #[serde(rename = "type")]
+    pub tool_type: String,
+    pub function: ToolFunction,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolFunction {
+    pub name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub parameters: serde_json::Value,
+}
+
+impl Tool {
+    pub fn function(name: &str, description: &str, parameters: serde_json::Value) -> Self {
+        Self {
+            tool_type: "function".to_string(),
+            function: ToolFunction {
+                name: name.to_string(),
+                description: Some(description.to_string()),
+                parameters,
+            },
+        }
+    }
+}
+
+/// A tool invocation requested by the model. `arguments` is the raw JSON string.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ToolCall {
+    pub id: String,
+    #[serde(rename = "type")]
+    pub call_type: String,
+    pub function: FunctionCall,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FunctionCall {
+    pub name: String,
+    pub arguments: String,
+}
+
+impl ToolCall {
+    // `arguments` parsed, for providers that take them as a JSON object. Empty
+    // arguments are `{}`.
+    #[cfg_attr(
+        not(any(feature = "anthropic", feature = "gemini", feature = "ollama")),
+        allow(dead_code)
+    )]
+    pub(crate) fn arguments_value(&self) -> Result<serde_json::Value, LlmApiError> {
+        if self.function.arguments.trim().is_empty() {
+            return Ok(serde_json::json!({}));
+        }
+        serde_json::from_str(&self.function.arguments).map_err(|e| {
+            LlmApiError::SerializationError(format!(
+                "arguments of tool call {} are not JSON: {}",
+                self.id, e
+            ))
+        })
+    }
+}
+
+// The function named by the latest tool call `id` in `earlier`. Gemini and Ollama
+// identify tool results by function name rather than by call id.
+#[cfg_attr(not(any(feature = "gemini", feature = "ollama")), allow(dead_code))]
+pub(crate) fn tool_call_name(earlier: &[ChatMessage], id: &str) -> Option<String> {
+    earlier
+        .iter()
+        .rev()
+        .filter_map(|msg| msg.tool_calls.as_ref())
+        .flatten()
+        .find(|call| call.id == id)
+        .map(|call| call.function.name.clone())
+}
+
+// Remove the leading system messages from `messages` and return their text, for
+// providers that take the system prompt apart from the conversation. A system
+// message after the first other message has no place in such a request.
+#[cfg_attr(not(any(feature = "anthropic", feature = "gemini")), allow(dead_code))]
+pub(crate) fn take_system_prompt(
+    provider: &str,
+    messages: &mut Vec<ChatMessage>,
+) -> Result<Option<String>, LlmApiError> {
+    let count = messages
+        .iter()
+        .take_while(|msg| msg.role == "system")
+        .count();
+    if messages[count..].iter().any(|msg| msg.role == "system") {
+        return Err(LlmApiError::ConfigError(format!(
+            "{} only accepts system messages at the start of the conversation",
+            provider
+        )));
+    }
+    let system: Vec<String> = messages.drain(..count).map(|msg| msg.content).collect();
+    Ok((!system.is_empty()).then(|| system.join("\n\n")))
+}
+
+// Providers without file support send only `content`, so a message with `parts`
+// would silently lose them.
+#[cfg_attr(
+    not(any(
+        feature = "deepseek",
+        feature = "xai",
+        feature = "mistral",
+        feature = "cohere",
+        feature = "ollama"
+    )),
+    allow(dead_code)
+)]
+pub(crate) fn reject_parts(provider: &str, messages: &[ChatMessage]) -> Result<(), LlmApiError> {
+    if messages.iter().any(|msg| !msg.parts.is_empty()) {
+        return Err(LlmApiError::ConfigError(format!(
+            "{} does not support file parts",
+            provider
+        )));
+    }
+    Ok(())
 }
 
 #[derive(Debug, Error)]
@@ -0,0 +1,349 @@
+// Client-side rate limiting as an `APIClient` middleware.
+// Calls over budget wait in `before_request` until they fit instead of failing.
+
+use crate::core::middleware::{HttpRequest, HttpResponse, Middleware};
+use crate::core::runtime::{self, Instant};
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use reqwest::header::HeaderMap;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use tokio::sync::Notify;
+
+const WINDOW: Duration = Duration::from_secs(60);
+
+/// Budgets for one provider or model. `None` means unlimited.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub struct RateLimits {
+    pub requests_per_minute: Option<u32>,
+    pub tokens_per_minute: Option<u32>,
+    pub max_concurrent: Option<u32>,
+}
+
+type TokenEstimator = dyn Fn(&serde_json::Value) -> u32 + Send + Sync;
+
+/// Shared limiter for one provider, with optional per-model budgets.
+///
+/// Models with their own limits get their own budget; every other model shares the
+/// provider-wide one. Wrap in an `Arc` and add it to each `APIClient` that should share
+/// the budget with `APIClient::with_middleware`.
+///
+/// Token usage is estimated from the request body before sending. Remaining budgets
+/// reported in `x-ratelimit-remaining-*` response headers, and `retry-after` on 429s,
+/// pause further calls until the reported reset time.
+pub struct RateLimiter {
+    limits: RateLimits,
+    model_limits: HashMap<String, RateLimits>,
+    estimator: Arc<TokenEstimator>,
+    shared: Arc<Shared>,
+    in_flight: Mutex<HashMap<u64, Permit>>,
+}
+
+struct Shared {
+    buckets: Mutex<HashMap<String, Bucket>>,
+    released: Notify,
+}
+
+// A concurrency slot in bucket `key`, given back when dropped.
+struct Permit {
+    key: String,
+    shared: Arc<Shared>,
+}
+
+impl Drop for Permit {
+    fn drop(&mut self) {
+        if let Some(bucket) = self.shared.buckets.lock().unwrap().get_mut(&self.key) {
+            bucket.in_flight = bucket.in_flight.saturating_sub(1);
+        }
+        self.shared.released.notify_waiters();
+    }
+}
+
+impl RateLimiter {
+    pub fn new(limits: RateLimits) -> Self {
+        Self {
+            limits,
+            model_limits: HashMap::new(),
+            estimator: Arc::new(estimate_tokens),
+            shared: Arc::new(Shared {
+                buckets: Mutex::new(HashMap::new()),
+                released: Notify::new(),
+            }),
+            in_flight: Mutex::new(HashMap::new()),
+        }
+    }
+
+    pub fn with_model_limits(mut self, model: &str, limits: RateLimits) -> Self {
+        self.model_limits.insert(model.to_string(), limits);
+        self
+    }
+
+    /// Replace the default estimate of roughly four characters per token.
+    pub fn with_token_estimator(
+        mut self,
+        estimator: impl Fn(&serde_json::Value) -> u32 + Send + Sync + 'static,
+    ) -> Self {
+        self.estimator = Arc::new(estimator);
+        self
+    }
+
+    fn bucket_key(&self, request: &HttpRequest) -> (String, RateLimits) {
+        match request_model(request) {
+            Some(model) => match self.model_limits.get(&model) {
+                Some(limits) => (model, *limits),
+                None => (String::new(), self.limits),
+            },
+            None => (String::new(), self.limits),
+        }
+    }
+
+    async fn acquire(&self, id: u64, key: String, limits: RateLimits, tokens: u32) {
+        loop {
+            // Register for wakeups before checking, so a release in between is not missed.
+            let released = self.shared.released.notified();
+            let wait = {
+                let mut buckets = self.shared.buckets.lock().unwrap();
+                let bucket = buckets.entry(key.clone()).or_default();
+                bucket.try_acquire(&limits, Instant::now(), tokens)
+            };
+            let wait = match wait {
+                Ok(()) => {
+                    let permit = Permit {
+                        key,
+                        shared: self.shared.clone(),
+                    };
+                    self.in_flight.lock().unwrap().insert(id, permit);
+                    return;
+                }
+                Err(wait) => wait,
+            };
+            match wait {
+                Some(wait) => {
+                    let _ = runtime::timeout(wait, released).await;
+                }
+                None => released.await,
+            }
+        }
+    }
+
+    // Dropping the permit frees the concurrency slot and wakes waiting calls.
+    fn release(&self, id: u64, headers: Option<(u16, &HeaderMap)>) {
+        let Some(permit) = self.in_flight.lock().unwrap().remove(&id) else {
+            return;
+        };
+        if let Some((status, headers)) = headers {
+            if let Some(bucket) = self.shared.buckets.lock().unwrap().get_mut(&permit.key) {
+                bucket.update_from_headers(Instant::now(), status, headers);
+            }
+        }
+    }
+}
+
+#[async_trait]
+impl Middleware for RateLimiter {
+    async fn before_request(&self, request: &mut HttpRequest) -> Result<(), LlmApiError> {
+        let (key, limits) = self.bucket_key(request);
+        let tokens = (self.estimator)(&request.body);
+        self.acquire(request.id, key, limits, tokens).await;
+        Ok(())
+    }
+
+    async fn after_response(
+        &self,
+        request: &HttpRequest,
+        response: &mut HttpResponse,
+    ) -> Result<(), LlmApiError> {
+        self.release(request.id, Some((response.status, &response.headers)));
+        Ok(())
+    }
+
+    async fn on_error(&self, request: &HttpRequest, _error: &LlmApiError) {
+        self.release(request.id, None);
+    }
+
+    fn on_cancel(&self, request: &HttpRequest) {
+        self.release(request.id, None);
+    }
+}
+
+#[derive(Default)]
+struct Bucket {
+    // Start time and estimated tokens of each request in the last minute.
+    window: VecDeque<(Instant, u32)>,
+    in_flight: u32,
+    remaining_requests: Option<(u32, Instant)>,
+    remaining_tokens: Option<(u32, Instant)>,
+    paused_until: Option<Instant>,
+}
+
+impl Bucket {
+    /// Admit the request, or return how long to wait. `Err(None)` means only the
+    /// concurrency limit is exhausted, so the caller waits for a release.
+    fn try_acquire(
+        &mut self,
+        limits: &RateLimits,
+        now: Instant,
+        tokens: u32,
+    ) -> Result<(), Option<Duration>> {
+        while let Some((started, _)) = self.window.front() {
+            if now.duration_since(*started) >= WINDOW {
+                self.window.pop_front();
+            } else {
+                break;
+            }
+        }
+
+        let mut blocked = false;
+        let mut wait = Duration::ZERO;
+        let mut wait_until = |until: Instant| {
+            if until > now {
+                blocked = true;
+                wait = wait.max(until - now);
+            }
+        };
+
+        if let Some(until) = self.paused_until {
+            wait_until(until);
+        }
+        if let Some((0, reset)) = self.remaining_requests {
+            wait_until(reset);
+        }
+        if let Some((remaining, reset)) = self.remaining_tokens {
+            if tokens > remaining {
+                wait_until(reset);
+            }
+        }
+        if let Some(rpm) = limits.requests_per_minute {
+            if self.window.len() >= rpm as usize {
+                let (started, _) = self.window[self.window.len() - rpm as usize];
+                wait_until(started + WINDOW);
+            }
+        }
+        if let Some(tpm) = limits.tokens_per_minute {
+            let mut used = self
+                .window
+                .iter()
+                .fold(0u32, |used, (_, spent)| used.saturating_add(*spent));
+            // A single request above the budget is let through once the window is empty.
+            for (started, spent) in &self.window {
+                if used.saturating_add(tokens) <= tpm {
+                    break;
+                }
+                used = used.saturating_sub(*spent);
+                wait_until(*started + WINDOW);
+            }
+        }
+
+        let concurrency_full = limits
+            .max_concurrent
+            .is_some_and(|max| self.in_flight >= max);
+
+        if blocked {
+            return Err(Some(wait));
+        }
+        if concurrency_full {
+            return Err(None);
+        }
+
+        self.window.push_back((now, tokens));
+        self.in_flight += 1;
+        if let Some((remaining, _)) = &mut self.remaining_requests {
+            *remaining = remaining.saturating_sub(1);
+        }
+        if let Some((remaining, _)) = &mut self.remaining_tokens {
+            *remaining = remaining.saturating_sub(tokens);
+        }
+        Ok(())
+    }
+
+    fn update_from_headers(&mut self, now: Instant, status: u16, headers: &HeaderMap) {
+        let header = |name: &str| headers.get(name).and_then(|v| v.to_str().ok());
+        let reset = |name: &str| {
+            header(name)
+                .and_then(parse_duration)
+                .and_then(|duration| now.checked_add(duration))
+                .unwrap_or(now + Duration::from_secs(1))
+        };
+
+        if let Some(remaining) =
+            header("x-ratelimit-remaining-requests").and_then(|v| v.parse().ok())
+        {
+            self.remaining_requests = Some((remaining, reset("x-ratelimit-reset-requests")));
+        }
+        if let Some(remaining) = header("x-ratelimit-remaining-tokens").and_then(|v| v.parse().ok())
+        {
+            self.remaining_tokens = Some((remaining, reset("x-ratelimit-reset-tokens")));
+        }
+        if status == 429 {
+            if let Some(retry_after) = header("retry-after").and_then(parse_duration) {
+                self.paused_until = now.checked_add(retry_after);
+            }
+        }
+    }
+}
+
+fn request_model(request: &HttpRequest) -> Option<String> {
+    if let Some(model) = request.body.get("model").and_then(|m| m.as_str()) {
+        return Some(model.to_string());
+    }
+    // Gemini carries the model in the path: `/models/{model}:generateContent`.
+    let (_, rest) = request.url.split_once("/models/")?;
+    let end = rest.find([':', '?', '/']).unwrap_or(rest.len());
+    Some(rest[..end].to_string())
+}
+
+/// Roughly four characters per token over every string in the body, plus the
+/// requested output budget, which providers count against tokens-per-minute.
+pub fn estimate_tokens(body: &serde_json::Value) -> u32 {
+    fn chars(value: &serde_json::Value) -> usize {
+        match value {
+            serde_json::Value::String(s) => s.chars().count(),
+            serde_json::Value::Array(items) => items.iter().map(chars).sum(),
+            serde_json::Value::Object(map) => map.values().map(chars).sum(),
+            _ => 0,
+        }
+    }
+
+    let max_output = ["max_tokens", "max_output_tokens", "max_completion_tokens"]
+        .iter()
+        .find_map(|key| body.get(key).and_then(|v| v.as_u64()))
+        .unwrap_or(0);
+    (chars(body) / 4) as u32 + max_output as u32
+}
+
+/// Parse `retry-after` seconds and OpenAI-style resets such as `1s`, `6m0s` or `20ms`.
+/// Values that are not finite or do not fit a `Duration` are ignored.
+fn parse_duration(value: &str) -> Option<Duration> {
+    if let Ok(seconds) = value.parse::<f64>() {
+        if !seconds.is_finite() {
+            return None;
+        }
+        return Duration::try_from_secs_f64(seconds.max(0.0)).ok();
+    }
+    let mut total = 0.0;
+    let mut number = String::new();
+    let mut chars = value.chars().peekable();
+    while let Some(c) = chars.next() {
+        if c.is_ascii_digit() || c == '.' {
+            number.push(c);
+            continue;
+        }
+        let amount: f64 = number.parse().ok()?;
+        number.clear();
+        total += match c {
+            'h' => amount * 3600.0,
+            'm' if chars.peek() == Some(&'s') => {
+                chars.next();
+                amount / 1000.0
+            }
+            'm' => amount * 60.0,
+            's' => amount,
+            _ => return None,
+        };
+    }
+    if !number.is_empty() {
+        return None;
+    }
+    Duration::try_from_secs_f64(total).ok()
+}
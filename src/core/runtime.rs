@@ -0,0 +1,138 @@
+// Clocks, timers and task helpers for both native and wasm32 builds.
+// Native builds use tokio. On wasm32 there is no tokio runtime or system clock: time
+// comes from JavaScript and futures run on the host's event loop, one at a time.
+
+use std::future::Future;
+use std::pin::Pin;
+use std::time::Duration;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use std::time::{SystemTime, UNIX_EPOCH};
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) use tokio::time::Instant;
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) use web_time::{Instant, SystemTime, UNIX_EPOCH};
+
+pub(crate) type BoxFuture<T> = Pin<Box<dyn Future<Output = T> + Send>>;
+
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn sleep(duration: Duration) {
+    tokio::time::sleep(duration).await
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn sleep(duration: Duration) {
+    SingleThreaded(gloo_timers::future::sleep(duration)).await
+}
+
+/// `future`'s output, or `None` if it takes longer than `duration`.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    tokio::time::timeout(duration, future).await.ok()
+}
+
+/// `future`'s output, or `None` if it takes longer than `duration`.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn timeout<F: Future>(duration: Duration, future: F) -> Option<F::Output> {
+    use std::task::Poll;
+
+    let mut future = std::pin::pin!(future);
+    let mut timer = std::pin::pin!(sleep(duration));
+    std::future::poll_fn(|cx| {
+        if let Poll::Ready(output) = future.as_mut().poll(cx) {
+            return Poll::Ready(Some(output));
+        }
+        timer.as_mut().poll(cx).map(|()| None)
+    })
+    .await
+}
+
+/// Run `futures` concurrently and return the outputs in completion order.
+///
+/// Natively each future is a tokio task, so they also run in parallel; a task that
+/// panics is left out of the outputs.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) async fn join_all<T: Send + 'static>(futures: Vec<BoxFuture<T>>) -> Vec<T> {
+    let mut tasks = tokio::task::JoinSet::new();
+    for future in futures {
+        tasks.spawn(future);
+    }
+    let mut outputs = Vec::new();
+    while let Some(joined) = tasks.join_next().await {
+        if let Ok(output) = joined {
+            outputs.push(output);
+        }
+    }
+    outputs
+}
+
+/// Run `futures` concurrently and return the outputs in completion order.
+#[cfg(target_arch = "wasm32")]
+pub(crate) async fn join_all<T: Send + 'static>(futures: Vec<BoxFuture<T>>) -> Vec<T> {
+    use std::task::Poll;
+
+    let mut pending: Vec<Option<BoxFuture<T>>> = futures.into_iter().map(Some).collect();
+    let mut outputs = Vec::new();
+    std::future::poll_fn(|cx| {
+        for slot in pending.iter_mut() {
+            if let Some(future) = slot {
+                if let Poll::Ready(output) = future.as_mut().poll(cx) {
+                    outputs.push(output);
+                    *slot = None;
+                }
+            }
+        }
+        if pending.iter().all(Option::is_none) {
+            Poll::Ready(())
+        } else {
+            Poll::Pending
+        }
+    })
+    .await;
+    outputs
+}
+
+/// Lets a value that wraps JavaScript objects, such as a fetch response, cross `Send`
+/// bounds. Natively this is `value` itself.
+#[cfg(not(target_arch = "wasm32"))]
+pub(crate) fn local<T>(value: T) -> T {
+    value
+}
+
+/// Lets a value that wraps JavaScript objects, such as a fetch response, cross `Send`
+/// bounds. Natively this is `value` itself.
+#[cfg(target_arch = "wasm32")]
+pub(crate) fn local<T>(value: T) -> SingleThreaded<T> {
+    SingleThreaded(value)
+}
+
+#[cfg(target_arch = "wasm32")]
+pub(crate) struct SingleThreaded<T>(pub(crate) T);
+
+// Without the `atomics` target feature a wasm32 module has exactly one thread, so
+// nothing can be sent or shared across threads.
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl<T> Send for SingleThreaded<T> {}
+#[cfg(all(target_arch = "wasm32", not(target_feature = "atomics")))]
+unsafe impl<T> Sync for SingleThreaded<T> {}
+
+#[cfg(target_arch = "wasm32")]
+impl<T> std::fmt::Debug for SingleThreaded<T> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str("SingleThreaded")
+    }
+}
+
+#[cfg(target_arch = "wasm32")]
+impl<F: Future> Future for SingleThreaded<F> {
+    type Output = F::Output;
+
+    fn poll(
+        self: Pin<&mut Self>,
+        cx: &mut std::task::Context<'_>,
+    ) -> std::task::Poll<Self::Output> {
+        // SAFETY: the inner future is never moved out of the pinned wrapper.
+        unsafe { self.map_unchecked_mut(|wrapper| &mut wrapper.0) }.poll(cx)
+    }
+}
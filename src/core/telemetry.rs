@@ -0,0 +1,173 @@
+// `tracing` spans for chat calls, following the OpenTelemetry GenAI semantic conventions.
+// https://opentelemetry.io/docs/specs/semconv/gen-ai/gen-ai-spans/
+// Without the `tracing` feature every helper here compiles down to a plain `.await`.
+
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::error::LlmApiError;
+use std::future::Future;
+
+#[cfg(feature = "tracing")]
+use std::sync::atomic::{AtomicBool, Ordering};
+
+#[cfg(feature = "tracing")]
+static CAPTURE_CONTENT: AtomicBool = AtomicBool::new(false);
+
+/// Attach prompts and completions to spans as events. Off by default because they may
+/// contain sensitive data; `OTEL_INSTRUMENTATION_GENAI_CAPTURE_MESSAGE_CONTENT=true`
+/// enables it as well.
+#[cfg(feature = "tracing")]
+pub fn set_capture_content(capture: bool) {
+    CAPTURE_CONTENT.store(capture, Ordering::Relaxed);
+}
+
+#[cfg(feature = "tracing")]
+fn capture_content() -> bool {
+    CAPTURE_CONTENT.load(Ordering::Relaxed)
+        || std::env::var("OTEL_INSTRUMENTATION_GENAI_CAPTURE_MESSAGE_CONTENT")
+            .map(|value| value.eq_ignore_ascii_case("true"))
+            .unwrap_or(false)
+}
+
+// Unused when every provider is disabled.
+#[cfg_attr(
+    not(any(
+        feature = "openai",
+        feature = "anthropic",
+        feature = "gemini",
+        feature = "ollama",
+        feature = "deepseek",
+        feature = "xai",
+        feature = "mistral",
+        feature = "cohere"
+    )),
+    allow(dead_code)
+)]
+pub(crate) struct ChatSpan {
+    #[cfg(feature = "tracing")]
+    span: tracing::Span,
+}
+
+#[cfg_attr(
+    not(any(
+        feature = "openai",
+        feature = "anthropic",
+        feature = "gemini",
+        feature = "ollama",
+        feature = "deepseek",
+        feature = "xai",
+        feature = "mistral",
+        feature = "cohere"
+    )),
+    allow(dead_code)
+)]
+impl ChatSpan {
+    /// `system` is the `gen_ai.system` value, e.g. `openai` or `anthropic`.
+    #[cfg(feature = "tracing")]
+    pub(crate) fn new(system: &'static str, request: &ChatCompletionRequest) -> Self {
+        let span = tracing::info_span!(
+            "chat",
+            otel.name = format!("chat {}", request.model),
+            otel.kind = "client",
+            otel.status_code = tracing::field::Empty,
+            gen_ai.operation.name = "chat",
+            gen_ai.system = system,
+            gen_ai.request.model = request.model.as_str(),
+            gen_ai.request.temperature = request.temperature,
+            gen_ai.request.max_tokens = request.max_tokens,
+            gen_ai.response.id = tracing::field::Empty,
+            gen_ai.response.model = tracing::field::Empty,
+            gen_ai.response.finish_reasons = tracing::field::Empty,
+            gen_ai.usage.input_tokens = tracing::field::Empty,
+            gen_ai.usage.output_tokens = tracing::field::Empty,
+            error.type = tracing::field::Empty,
+        );
+        if capture_content() {
+            if let Ok(messages) = serde_json::to_string(&request.messages) {
+                tracing::info!(parent: &span, gen_ai.prompt = messages, "gen_ai.content.prompt");
+            }
+        }
+        Self { span }
+    }
+
+    #[cfg(not(feature = "tracing"))]
+    pub(crate) fn new(_system: &'static str, _request: &ChatCompletionRequest) -> Self {
+        Self {}
+    }
+
+    /// Run `future` inside the span and record the outcome on it.
+    pub(crate) async fn instrument<F>(
+        self,
+        future: F,
+    ) -> Result<ChatCompletionResponse, LlmApiError>
+    where
+        F: Future<Output = Result<ChatCompletionResponse, LlmApiError>>,
+    {
+        #[cfg(feature = "tracing")]
+        {
+            use tracing::Instrument;
+
+            let result = future.instrument(self.span.clone()).await;
+            match &result {
+                Ok(response) => self.record_response(response),
+                Err(error) => self.record_error(error),
+            }
+            result
+        }
+
+        #[cfg(not(feature = "tracing"))]
+        future.await
+    }
+
+    #[cfg(feature = "tracing")]
+    fn record_response(&self, response: &ChatCompletionResponse) {
+        let span = &self.span;
+        span.record("gen_ai.response.id", response.id.as_str());
+        span.record("gen_ai.response.model", response.model.as_str());
+        let finish_reasons: Vec<&str> = response
+            .choices
+            .iter()
+            .map(|choice| choice.finish_reason.as_str())
+            .collect();
+        span.record(
+            "gen_ai.response.finish_reasons",
+            tracing::field::debug(&finish_reasons),
+        );
+        if let Some(usage) = &response.usage {
+            if let Some(input_tokens) = usage.input_tokens {
+                span.record("gen_ai.usage.input_tokens", input_tokens);
+            }
+            if let Some(output_tokens) = usage.output_tokens {
+                span.record("gen_ai.usage.output_tokens", output_tokens);
+            }
+        }
+        if capture_content() {
+            let messages: Vec<_> = response.choices.iter().map(|c| &c.message).collect();
+            if let Ok(messages) = serde_json::to_string(&messages) {
+                tracing::info!(
+                    parent: span,
+                    gen_ai.completion = messages,
+                    "gen_ai.content.completion"
+                );
+            }
+        }
+    }
+
+    #[cfg(feature = "tracing")]
+    fn record_error(&self, error: &LlmApiError) {
+        let error_type = match error {
+            LlmApiError::CoreError(_) => "core_error",
+            LlmApiError::ProviderError(_) => "provider_error",
+            LlmApiError::NetworkError(_) => "network_error",
+            LlmApiError::ApiError { .. } => "api_error",
+            LlmApiError::ContextLengthExceeded(_) => "context_length_exceeded",
+            LlmApiError::ContentFlagged { .. } => "content_flagged",
+            LlmApiError::AuthError(_) => "auth_error",
+            LlmApiError::ConfigError(_) => "config_error",
+            LlmApiError::SerializationError(_) => "serialization_error",
+            LlmApiError::DeserializationError(_) => "deserialization_error",
+        };
+        self.span.record("otel.status_code", "ERROR");
+        self.span.record("error.type", error_type);
+        tracing::warn!(parent: &self.span, error = %error, "chat completion failed");
+    }
+}
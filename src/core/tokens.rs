@@ -0,0 +1,96 @@
+// Token counting for prompts before they are sent.
+// Providers use their counting endpoint or a local tokenizer where one exists, and
+// fall back to `estimate_tokens` otherwise.
+
+use crate::core::ChatCompletionRequest;
+
+/// Input tokens of a request, and how they were obtained.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TokenCount {
+    pub input_tokens: u32,
+    pub source: TokenCountSource,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenCountSource {
+    /// Counted by the provider's API.
+    Provider,
+    /// Counted with the model's tokenizer locally.
+    Tokenizer,
+    /// Estimated from the length of the text.
+    Estimate,
+}
+
+impl TokenCount {
+    pub fn estimate(request: &ChatCompletionRequest) -> Self {
+        Self {
+            input_tokens: estimate_tokens(request),
+            source: TokenCountSource::Estimate,
+        }
+    }
+}
+
+/// Estimated prompt tokens of a request: about four characters per token over message
+/// text, tool calls and tool definitions, plus a small per-message overhead for roles
+/// and separators. Errs on the large side for English text.
+pub fn estimate_tokens(request: &ChatCompletionRequest) -> u32 {
+    let messages: u32 = request
+        .messages
+        .iter()
+        .map(|message| {
+            let tool_calls: u32 = message
+                .tool_calls
+                .iter()
+                .flatten()
+                .map(|call| {
+                    text_tokens(&call.function.name) + text_tokens(&call.function.arguments)
+                })
+                .sum();
+            4 + text_tokens(&message.content) + tool_calls
+        })
+        .sum();
+    messages + estimate_tool_tokens(request) + 3
+}
+
+fn text_tokens(text: &str) -> u32 {
+    (text.chars().count() as u32).div_ceil(4)
+}
+
+fn estimate_tool_tokens(request: &ChatCompletionRequest) -> u32 {
+    request
+        .tools
+        .as_ref()
+        .and_then(|tools| serde_json::to_string(tools).ok())
+        .map(|json| text_tokens(&json))
+        .unwrap_or(0)
+}
+
+/// Count a chat request with the bundled BPE vocabulary of an OpenAI model, following
+/// OpenAI's accounting of 3 tokens per message and 3 to prime the reply. Tool
+/// definitions are not part of that accounting and are estimated. `None` if the model
+/// has no known chat tokenizer.
+#[cfg(feature = "tiktoken")]
+pub fn count_openai_tokens(request: &ChatCompletionRequest) -> Option<TokenCount> {
+    use tiktoken_rs::tokenizer::{get_tokenizer, Tokenizer};
+
+    let bpe = match get_tokenizer(&request.model)? {
+        Tokenizer::O200kBase => tiktoken_rs::o200k_base_singleton(),
+        Tokenizer::Cl100kBase => tiktoken_rs::cl100k_base_singleton(),
+        _ => return None,
+    };
+    let encoded = |text: &str| bpe.encode_with_special_tokens(text).len() as u32;
+
+    let mut input_tokens = 3;
+    for message in &request.messages {
+        input_tokens += 3 + encoded(&message.role) + encoded(&message.content);
+        for call in message.tool_calls.iter().flatten() {
+            input_tokens += encoded(&call.function.name) + encoded(&call.function.arguments);
+        }
+    }
+    input_tokens += estimate_tool_tokens(request);
+
+    Some(TokenCount {
+        input_tokens,
+        source: TokenCountSource::Tokenizer,
+    })
+}
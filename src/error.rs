@@ -11,6 +11,18 @@ pub enum LlmApiError {
     #[error("Network error: {0}")]
     NetworkError(String),
 
+    /// The provider answered with a non-success HTTP status.
+    #[error("API error ({status}): {message}")]
+    ApiError { status: u16, message: String },
+
+    /// The request does not fit the model's context window.
+    #[error("Context length exceeded: {0}")]
+    ContextLengthExceeded(String),
+
+    /// A moderation guard flagged the request before it was sent.
+    #[error("Content flagged by moderation: {}", categories.join(", "))]
+    ContentFlagged { categories: Vec<String> },
+
     #[error("Authentication error: {0}")]
     AuthError(String),
 
@@ -23,3 +35,50 @@ pub enum LlmApiError {
     #[error("Deserialization error: {0}")]
     DeserializationError(String),
 }
+
+impl LlmApiError {
+    /// The error for a non-success response, recognizing context window overflows
+    /// from the wording providers use for them.
+    pub(crate) fn from_response(status: u16, body: String) -> Self {
+        const CONTEXT_LENGTH_MARKERS: &[&str] = &[
+            "context_length_exceeded",
+            "maximum context length",
+            "context window",
+            "prompt is too long",
+            "input token count",
+            "too many tokens",
+        ];
+        let lowercase = body.to_lowercase();
+        if matches!(status, 400 | 413)
+            && CONTEXT_LENGTH_MARKERS
+                .iter()
+                .any(|marker| lowercase.contains(marker))
+        {
+            return LlmApiError::ContextLengthExceeded(body);
+        }
+        LlmApiError::ApiError {
+            status,
+            message: body,
+        }
+    }
+
+    /// HTTP status of an `ApiError`.
+    pub fn status(&self) -> Option<u16> {
+        match self {
+            LlmApiError::ApiError { status, .. } => Some(*status),
+            _ => None,
+        }
+    }
+
+    /// Whether the same request may succeed if sent again, possibly elsewhere:
+    /// transport failures, timeouts, rate limits and server-side errors.
+    pub fn is_retryable(&self) -> bool {
+        match self {
+            LlmApiError::NetworkError(_) => true,
+            LlmApiError::ApiError { status, .. } => {
+                matches!(status, 408 | 409 | 429) || *status >= 500
+            }
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,88 @@
+// Files stored with a provider, uploaded once and referenced by id in later requests.
+// https://platform.openai.com/docs/api-reference/files
+// https://docs.anthropic.com/en/docs/build-with-claude/files
+// https://ai.google.dev/gemini-api/docs/files
+
+use crate::core::FileHandle;
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use std::path::Path;
+
+/// A file to upload.
+#[derive(Debug, Clone)]
+pub struct FileUpload {
+    pub filename: String,
+    pub mime_type: String,
+    pub data: Vec<u8>,
+    /// OpenAI's `purpose`. Defaults to `user_data`; other providers ignore it.
+    pub purpose: Option<String>,
+}
+
+impl FileUpload {
+    pub fn new(filename: &str, mime_type: &str, data: Vec<u8>) -> Self {
+        Self {
+            filename: filename.to_string(),
+            mime_type: mime_type.to_string(),
+            data,
+            purpose: None,
+        }
+    }
+
+    /// Read a file from disk. The MIME type is guessed from its extension.
+    pub fn from_path(path: impl AsRef<Path>) -> Result<Self, LlmApiError> {
+        let path = path.as_ref();
+        let data = std::fs::read(path).map_err(|e| {
+            LlmApiError::ConfigError(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        let filename = path
+            .file_name()
+            .map(|name| name.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        Ok(Self::new(&filename, mime_type_for(&filename), data))
+    }
+
+    pub fn with_purpose(mut self, purpose: &str) -> Self {
+        self.purpose = Some(purpose.to_string());
+        self
+    }
+}
+
+/// MIME type for a file name, from its extension. Unknown extensions map to
+/// `application/octet-stream`.
+pub fn mime_type_for(filename: &str) -> &'static str {
+    let extension = filename
+        .rsplit_once('.')
+        .map(|(_, extension)| extension.to_ascii_lowercase())
+        .unwrap_or_default();
+    match extension.as_str() {
+        "pdf" => "application/pdf",
+        "txt" => "text/plain",
+        "md" => "text/markdown",
+        "csv" => "text/csv",
+        "html" | "htm" => "text/html",
+        "json" => "application/json",
+        "jsonl" => "application/jsonl",
+        "png" => "image/png",
+        "jpg" | "jpeg" => "image/jpeg",
+        "gif" => "image/gif",
+        "webp" => "image/webp",
+        "mp3" => "audio/mpeg",
+        "wav" => "audio/wav",
+        "mp4" => "video/mp4",
+        _ => "application/octet-stream",
+    }
+}
+
+/// Providers that store files for use in later requests.
+///
+/// Put the returned handle in `ChatMessage::parts` to reference the file.
+#[async_trait]
+pub trait FileProvider {
+    async fn upload_file(&self, upload: FileUpload) -> Result<FileHandle, LlmApiError>;
+
+    async fn list_files(&self) -> Result<Vec<FileHandle>, LlmApiError>;
+
+    async fn get_file(&self, id: &str) -> Result<FileHandle, LlmApiError>;
+
+    async fn delete_file(&self, id: &str) -> Result<(), LlmApiError>;
+}
@@ -0,0 +1,104 @@
+// Image generation from a text prompt.
+// https://platform.openai.com/docs/api-reference/images
+// https://docs.x.ai/docs/guides/image-generations
+// https://ai.google.dev/gemini-api/docs/imagen
+
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use base64::Engine;
+
+/// How generated images are returned.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    /// A URL to download the image from. Providers expire these after a while.
+    Url,
+    /// The image itself, base64-encoded.
+    Base64,
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ImageRequest {
+    pub model: String,
+    pub prompt: String,
+    /// Number of images to generate. Defaults to one.
+    pub count: Option<u32>,
+    /// Size as the provider spells it, e.g. `1024x1024` for OpenAI, or an aspect ratio
+    /// such as `16:9` for Imagen.
+    pub size: Option<String>,
+    /// Quality as the provider spells it, e.g. `hd` or `high`.
+    pub quality: Option<String>,
+    /// Defaults to whatever the model returns by default.
+    pub format: Option<ImageFormat>,
+}
+
+impl ImageRequest {
+    pub fn new(model: &str, prompt: &str) -> Self {
+        Self {
+            model: model.to_string(),
+            prompt: prompt.to_string(),
+            ..Default::default()
+        }
+    }
+
+    pub fn with_count(mut self, count: u32) -> Self {
+        self.count = Some(count);
+        self
+    }
+
+    pub fn with_size(mut self, size: &str) -> Self {
+        self.size = Some(size.to_string());
+        self
+    }
+
+    pub fn with_quality(mut self, quality: &str) -> Self {
+        self.quality = Some(quality.to_string());
+        self
+    }
+
+    pub fn with_format(mut self, format: ImageFormat) -> Self {
+        self.format = Some(format);
+        self
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ImageData {
+    Url(String),
+    Base64(String),
+}
+
+#[derive(Debug, Clone)]
+pub struct GeneratedImage {
+    pub data: ImageData,
+    /// Known for base64 images only.
+    pub mime_type: Option<String>,
+    /// The prompt the provider actually used, if it rewrote the one given.
+    pub revised_prompt: Option<String>,
+}
+
+impl GeneratedImage {
+    /// Decode a base64 image. URLs are not downloaded.
+    pub fn bytes(&self) -> Result<Vec<u8>, LlmApiError> {
+        match &self.data {
+            ImageData::Base64(data) => base64::engine::general_purpose::STANDARD
+                .decode(data)
+                .map_err(|e| LlmApiError::DeserializationError(e.to_string())),
+            ImageData::Url(url) => Err(LlmApiError::ConfigError(format!(
+                "image is a URL, not inline data: {}",
+                url
+            ))),
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ImageResponse {
+    pub model: String,
+    pub images: Vec<GeneratedImage>,
+}
+
+/// Providers that generate images from a text prompt.
+#[async_trait]
+pub trait ImageProvider {
+    async fn generate_image(&self, request: ImageRequest) -> Result<ImageResponse, LlmApiError>;
+}
@@ -1,5 +1,21 @@
+#[cfg(all(target_arch = "wasm32", not(feature = "wasm")))]
+compile_error!("building for wasm32 requires the `wasm` feature");
+
+pub mod agent;
+pub mod audio;
+pub mod batch;
+#[cfg(all(feature = "blocking", not(target_arch = "wasm32")))]
+pub mod blocking;
+pub mod capabilities;
+pub mod context;
+pub mod conversation;
 pub mod core;
 pub mod error;
+pub mod files;
+pub mod images;
+pub mod moderation;
+pub mod pricing;
+pub mod prompt;
 pub mod providers;
 
 pub use core::*;
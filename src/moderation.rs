@@ -0,0 +1,159 @@
+// Moderation: classifying text and images as potentially harmful.
+// https://platform.openai.com/docs/api-reference/moderations
+
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ModerationInput {
+    Text(String),
+    /// An image URL, or a `data:` URL with base64 data.
+    ImageUrl(String),
+}
+
+#[derive(Debug, Clone, Default)]
+pub struct ModerationRequest {
+    /// Defaults to the provider's latest moderation model.
+    pub model: Option<String>,
+    pub inputs: Vec<ModerationInput>,
+}
+
+impl ModerationRequest {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    pub fn with_text(mut self, text: &str) -> Self {
+        self.inputs.push(ModerationInput::Text(text.to_string()));
+        self
+    }
+
+    pub fn with_image_url(mut self, url: &str) -> Self {
+        self.inputs.push(ModerationInput::ImageUrl(url.to_string()));
+        self
+    }
+}
+
+/// Moderation category names, as OpenAI spells them, in `ModerationCategories` field order.
+pub const CATEGORY_NAMES: [&str; 13] = [
+    "harassment",
+    "harassment/threatening",
+    "hate",
+    "hate/threatening",
+    "illicit",
+    "illicit/violent",
+    "self-harm",
+    "self-harm/intent",
+    "self-harm/instructions",
+    "sexual",
+    "sexual/minors",
+    "violence",
+    "violence/graphic",
+];
+
+/// One value per moderation category: a flag or a score.
+///
+/// Categories a model does not classify are left at the default.
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct ModerationCategories<T> {
+    pub harassment: T,
+    pub harassment_threatening: T,
+    pub hate: T,
+    pub hate_threatening: T,
+    pub illicit: T,
+    pub illicit_violent: T,
+    pub self_harm: T,
+    pub self_harm_intent: T,
+    pub self_harm_instructions: T,
+    pub sexual: T,
+    pub sexual_minors: T,
+    pub violence: T,
+    pub violence_graphic: T,
+}
+
+impl<T: Copy> ModerationCategories<T> {
+    /// Build from a function of the category name, as in `CATEGORY_NAMES`.
+    pub fn from_fn(mut value: impl FnMut(&'static str) -> T) -> Self {
+        Self {
+            harassment: value("harassment"),
+            harassment_threatening: value("harassment/threatening"),
+            hate: value("hate"),
+            hate_threatening: value("hate/threatening"),
+            illicit: value("illicit"),
+            illicit_violent: value("illicit/violent"),
+            self_harm: value("self-harm"),
+            self_harm_intent: value("self-harm/intent"),
+            self_harm_instructions: value("self-harm/instructions"),
+            sexual: value("sexual"),
+            sexual_minors: value("sexual/minors"),
+            violence: value("violence"),
+            violence_graphic: value("violence/graphic"),
+        }
+    }
+
+    /// `(name, value)` for every category.
+    pub fn iter(&self) -> impl Iterator<Item = (&'static str, T)> {
+        let values = [
+            self.harassment,
+            self.harassment_threatening,
+            self.hate,
+            self.hate_threatening,
+            self.illicit,
+            self.illicit_violent,
+            self.self_harm,
+            self.self_harm_intent,
+            self.self_harm_instructions,
+            self.sexual,
+            self.sexual_minors,
+            self.violence,
+            self.violence_graphic,
+        ];
+        CATEGORY_NAMES.into_iter().zip(values)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct ModerationResult {
+    /// Whether any category was flagged.
+    pub flagged: bool,
+    pub categories: ModerationCategories<bool>,
+    /// Confidence per category, from 0 to 1.
+    pub scores: ModerationCategories<f64>,
+}
+
+impl ModerationResult {
+    /// Names of the flagged categories.
+    pub fn flagged_categories(&self) -> Vec<&'static str> {
+        self.categories
+            .iter()
+            .filter(|(_, flagged)| *flagged)
+            .map(|(name, _)| name)
+            .collect()
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct ModerationResponse {
+    pub id: String,
+    pub model: String,
+    /// One result per text input, or a single result when images are included.
+    pub results: Vec<ModerationResult>,
+}
+
+impl ModerationResponse {
+    pub fn flagged(&self) -> bool {
+        self.results.iter().any(|result| result.flagged)
+    }
+}
+
+/// Providers with a moderation endpoint.
+#[async_trait]
+pub trait ModerationProvider {
+    async fn moderate(&self, request: ModerationRequest)
+        -> Result<ModerationResponse, LlmApiError>;
+}
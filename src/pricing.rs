@@ -0,0 +1,273 @@
+// Cost estimation from `ChatUsage` and a per-model price table.
+// Prices are USD per million tokens. The built-in table is a snapshot of public list
+// prices and will drift; load overrides from a file to keep it accurate.
+
+use crate::capabilities::lookup;
+use crate::core::{ChatCompletionResponse, ChatUsage};
+use crate::error::LlmApiError;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::path::Path;
+use std::sync::Mutex;
+
+/// USD per million tokens. `cached_input` and `cache_write` fall back to the input
+/// price and `reasoning` to the output price when unset.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct ModelPrice {
+    pub input: f64,
+    pub output: f64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cached_input: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cache_write: Option<f64>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub reasoning: Option<f64>,
+}
+
+impl ModelPrice {
+    pub fn new(input: f64, output: f64) -> Self {
+        Self {
+            input,
+            output,
+            cached_input: None,
+            cache_write: None,
+            reasoning: None,
+        }
+    }
+
+    pub fn with_cached_input(mut self, cached_input: f64) -> Self {
+        self.cached_input = Some(cached_input);
+        self
+    }
+
+    /// Price of input tokens written to the prompt cache.
+    pub fn with_cache_write(mut self, cache_write: f64) -> Self {
+        self.cache_write = Some(cache_write);
+        self
+    }
+
+    pub fn with_reasoning(mut self, reasoning: f64) -> Self {
+        self.reasoning = Some(reasoning);
+        self
+    }
+
+    /// Cost in USD of the given usage. Missing counts are treated as zero.
+    pub fn cost(&self, usage: &ChatUsage) -> f64 {
+        let input = usage.input_tokens.unwrap_or(0) as f64;
+        let output = usage.output_tokens.unwrap_or(0) as f64;
+        let cached = (usage.cached_input_tokens.unwrap_or(0) as f64).min(input);
+        let written = (usage.cache_write_tokens.unwrap_or(0) as f64).min(input - cached);
+        let reasoning = (usage.reasoning_tokens.unwrap_or(0) as f64).min(output);
+
+        let input_cost = (input - cached - written) * self.input
+            + cached * self.cached_input.unwrap_or(self.input)
+            + written * self.cache_write.unwrap_or(self.input);
+        let output_cost =
+            (output - reasoning) * self.output + reasoning * self.reasoning.unwrap_or(self.output);
+        (input_cost + output_cost) / 1_000_000.0
+    }
+}
+
+/// Prices keyed by provider (`openai`, `anthropic`, `gemini`, ...) and model id.
+///
+/// Lookups match model ids like `capabilities::CapabilityCatalog`: an entry also
+/// prices its dated or tagged snapshots, so `gpt-4o-mini` covers
+/// `gpt-4o-mini-2024-07-18`, but `o3` does not price `o3-pro`. Unknown models
+/// have no price rather than a wrong one.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(transparent)]
+pub struct PricingTable {
+    prices: BTreeMap<String, BTreeMap<String, ModelPrice>>,
+}
+
+impl PricingTable {
+    /// An empty table, for callers that want to supply every price themselves.
+    pub fn empty() -> Self {
+        Self {
+            prices: BTreeMap::new(),
+        }
+    }
+
+    /// The built-in list prices.
+    pub fn builtin() -> Self {
+        let mut table = Self::empty();
+        for (provider, model, input, cached_input, output) in BUILTIN_PRICES {
+            let mut price = ModelPrice::new(*input, *output);
+            price.cached_input = *cached_input;
+            // Anthropic bills writes to its default 5-minute cache at 1.25x input.
+            if *provider == "anthropic" {
+                price.cache_write = Some(input * 1.25);
+            }
+            table.set(provider, model, price);
+        }
+        table
+    }
+
+    pub fn set(&mut self, provider: &str, model: &str, price: ModelPrice) {
+        self.prices
+            .entry(provider.to_string())
+            .or_default()
+            .insert(model.to_string(), price);
+    }
+
+    pub fn with_price(mut self, provider: &str, model: &str, price: ModelPrice) -> Self {
+        self.set(provider, model, price);
+        self
+    }
+
+    /// Merge prices from a `.json` or `.toml` file over this table.
+    ///
+    /// The file maps provider to model to price, e.g. in TOML:
+    ///
+    /// ```toml
+    /// [openai."gpt-4o"]
+    /// input = 2.5
+    /// cached_input = 1.25
+    /// output = 10.0
+    /// ```
+    pub fn load_overrides(&mut self, path: impl AsRef<Path>) -> Result<(), LlmApiError> {
+        let path = path.as_ref();
+        let data = std::fs::read_to_string(path).map_err(|e| {
+            LlmApiError::ConfigError(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        let overrides: PricingTable = match path.extension().and_then(|ext| ext.to_str()) {
+            Some("json") => serde_json::from_str(&data)
+                .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?,
+            #[cfg(feature = "toml")]
+            Some("toml") => toml::from_str(&data)
+                .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?,
+            _ => {
+                return Err(LlmApiError::ConfigError(format!(
+                    "unsupported pricing file {}",
+                    path.display()
+                )))
+            }
+        };
+        for (provider, models) in overrides.prices {
+            for (model, price) in models {
+                self.set(&provider, &model, price);
+            }
+        }
+        Ok(())
+    }
+
+    pub fn price(&self, provider: &str, model: &str) -> Option<&ModelPrice> {
+        lookup(self.prices.get(provider)?, model)
+    }
+
+    /// Cost in USD of a response, or `None` if the model is unknown or there is no usage.
+    pub fn cost(&self, provider: &str, response: &ChatCompletionResponse) -> Option<f64> {
+        let usage = response.usage.as_ref()?;
+        Some(self.price(provider, &response.model)?.cost(usage))
+    }
+}
+
+/// Accumulated usage and cost for one tag.
+#[derive(Debug, Clone, Default, PartialEq, Serialize)]
+pub struct Spend {
+    pub calls: u64,
+    /// Calls whose cost could not be computed; they are not included in `cost`.
+    pub unpriced_calls: u64,
+    pub input_tokens: u64,
+    pub output_tokens: u64,
+    pub cost: f64,
+}
+
+/// Aggregates spend per caller-chosen tag (feature, team, customer...) across calls.
+pub struct SpendTracker {
+    pricing: PricingTable,
+    totals: Mutex<BTreeMap<String, Spend>>,
+}
+
+impl SpendTracker {
+    pub fn new(pricing: PricingTable) -> Self {
+        Self {
+            pricing,
+            totals: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    /// Add a response to `tag`'s totals and return its cost, if it could be priced.
+    pub fn record(
+        &self,
+        tag: &str,
+        provider: &str,
+        response: &ChatCompletionResponse,
+    ) -> Option<f64> {
+        let cost = self.pricing.cost(provider, response);
+        let mut totals = self.totals.lock().unwrap();
+        let spend = totals.entry(tag.to_string()).or_default();
+        spend.calls += 1;
+        if let Some(usage) = &response.usage {
+            spend.input_tokens += usage.input_tokens.unwrap_or(0) as u64;
+            spend.output_tokens += usage.output_tokens.unwrap_or(0) as u64;
+        }
+        match cost {
+            Some(cost) => spend.cost += cost,
+            None => spend.unpriced_calls += 1,
+        }
+        cost
+    }
+
+    pub fn spend(&self, tag: &str) -> Spend {
+        self.totals
+            .lock()
+            .unwrap()
+            .get(tag)
+            .cloned()
+            .unwrap_or_default()
+    }
+
+    pub fn totals(&self) -> BTreeMap<String, Spend> {
+        self.totals.lock().unwrap().clone()
+    }
+
+    pub fn total_cost(&self) -> f64 {
+        self.totals.lock().unwrap().values().map(|s| s.cost).sum()
+    }
+
+    pub fn reset(&self) {
+        self.totals.lock().unwrap().clear();
+    }
+}
+
+// (provider, model id, input, cached input, output), USD per million tokens.
+const BUILTIN_PRICES: &[(&str, &str, f64, Option<f64>, f64)] = &[
+    ("openai", "gpt-3.5-turbo", 0.50, None, 1.50),
+    ("openai", "gpt-4o", 2.50, Some(1.25), 10.00),
+    ("openai", "gpt-4o-mini", 0.15, Some(0.075), 0.60),
+    ("openai", "gpt-4.1", 2.00, Some(0.50), 8.00),
+    ("openai", "gpt-4.1-mini", 0.40, Some(0.10), 1.60),
+    ("openai", "gpt-4.1-nano", 0.10, Some(0.025), 0.40),
+    ("openai", "o1", 15.00, Some(7.50), 60.00),
+    ("openai", "o1-mini", 1.10, Some(0.55), 4.40),
+    ("openai", "o1-pro", 150.00, None, 600.00),
+    ("openai", "o3", 2.00, Some(0.50), 8.00),
+    ("openai", "o3-mini", 1.10, Some(0.55), 4.40),
+    ("openai", "o3-pro", 20.00, None, 80.00),
+    ("openai", "o4-mini", 1.10, Some(0.275), 4.40),
+    ("anthropic", "claude-3-haiku", 0.25, Some(0.03), 1.25),
+    ("anthropic", "claude-3-5-haiku", 0.80, Some(0.08), 4.00),
+    ("anthropic", "claude-3-5-sonnet", 3.00, Some(0.30), 15.00),
+    ("anthropic", "claude-3-7-sonnet", 3.00, Some(0.30), 15.00),
+    ("anthropic", "claude-sonnet-4", 3.00, Some(0.30), 15.00),
+    ("anthropic", "claude-3-opus", 15.00, Some(1.50), 75.00),
+    ("anthropic", "claude-opus-4", 15.00, Some(1.50), 75.00),
+    ("gemini", "gemini-2.0-flash", 0.10, Some(0.025), 0.40),
+    ("gemini", "gemini-2.0-flash-lite", 0.075, None, 0.30),
+    ("gemini", "gemini-2.5-flash", 0.30, Some(0.075), 2.50),
+    ("gemini", "gemini-2.5-flash-lite", 0.10, Some(0.025), 0.40),
+    ("gemini", "gemini-2.5-pro", 1.25, Some(0.31), 10.00),
+    ("deepseek", "deepseek-chat", 0.27, Some(0.07), 1.10),
+    ("deepseek", "deepseek-reasoner", 0.55, Some(0.14), 2.19),
+    ("xai", "grok-2", 2.00, None, 10.00),
+    ("xai", "grok-2-vision", 2.00, None, 10.00),
+    ("xai", "grok-3", 3.00, Some(0.75), 15.00),
+    ("xai", "grok-3-mini", 0.30, Some(0.075), 0.50),
+    ("mistral", "mistral-large", 2.00, None, 6.00),
+    ("mistral", "mistral-small", 0.10, None, 0.30),
+    ("mistral", "codestral", 0.30, None, 0.90),
+    ("cohere", "command-r", 0.15, None, 0.60),
+    ("cohere", "command-r-plus", 2.50, None, 10.00),
+    ("cohere", "command-a", 2.50, None, 10.00),
+];
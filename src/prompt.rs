@@ -0,0 +1,341 @@
+// Prompt templates with named variables, rendered into chat requests.
+// Templates are plain text split into message sections, so they can live in files
+// next to the code that uses them.
+
+use crate::core::{ChatCompletionRequest, ChatMessage};
+use crate::error::LlmApiError;
+use serde::Serialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, BTreeSet};
+use std::path::Path;
+
+// Partials may include other partials, up to this depth.
+const MAX_PARTIAL_DEPTH: usize = 8;
+
+/// A system prompt and message list with `{{variable}}` placeholders.
+///
+/// A template is a sequence of sections, each starting with a `--- <role>` line:
+///
+/// ```text
+/// --- system
+/// You classify the sentiment of {{ product }} reviews.
+/// {{> format }}
+/// --- examples shots
+/// --- user
+/// {{ review }}
+/// --- assistant
+/// {{ label }}
+/// --- end
+/// --- user
+/// {{ review }}
+/// ```
+///
+/// - `{{ name }}` inserts a variable. Dotted names such as `{{ user.name }}` reach into
+///   nested values. Strings are inserted as they are, other values as JSON.
+/// - `{{> name }}` inserts a partial registered with `with_partial`.
+/// - `--- examples <name>` up to `--- end` is a few-shot block: its messages are
+///   repeated once for each item of the array variable `<name>`, and each item's
+///   fields are available as variables inside the block.
+/// - `--- system` sections come before all others.
+#[derive(Debug, Clone, Default)]
+pub struct PromptTemplate {
+    sections: Vec<Section>,
+    partials: BTreeMap<String, String>,
+}
+
+#[derive(Debug, Clone)]
+enum Section {
+    Message {
+        role: String,
+        text: String,
+    },
+    Examples {
+        variable: String,
+        messages: Vec<(String, String)>,
+    },
+}
+
+impl PromptTemplate {
+    /// Parse a template from its text.
+    pub fn parse(source: &str) -> Result<Self, LlmApiError> {
+        let mut sections = Vec::new();
+        // Open examples block: its variable and the messages read so far.
+        let mut examples: Option<(String, Vec<(String, String)>)> = None;
+        let mut current: Option<(String, String)> = None;
+
+        fn close(
+            current: &mut Option<(String, String)>,
+            examples: &mut Option<(String, Vec<(String, String)>)>,
+            sections: &mut Vec<Section>,
+        ) {
+            if let Some((role, text)) = current.take() {
+                let text = text.trim().to_string();
+                match examples {
+                    Some((_, messages)) => messages.push((role, text)),
+                    None => sections.push(Section::Message { role, text }),
+                }
+            }
+        }
+
+        for (number, line) in source.lines().enumerate() {
+            let Some(header) = line.strip_prefix("--- ") else {
+                match &mut current {
+                    Some((_, text)) => {
+                        text.push_str(line);
+                        text.push('\n');
+                    }
+                    None if line.trim().is_empty() => {}
+                    None => {
+                        return Err(LlmApiError::ConfigError(format!(
+                            "prompt template line {}: text outside a section",
+                            number + 1
+                        )))
+                    }
+                }
+                continue;
+            };
+
+            close(&mut current, &mut examples, &mut sections);
+            let mut words = header.split_whitespace();
+            match (words.next(), words.next(), words.next()) {
+                // Most providers take the system prompt only ahead of the conversation.
+                (Some("system"), None, None)
+                    if examples.is_some()
+                        || sections.iter().any(|section| {
+                            !matches!(section, Section::Message { role, .. } if role == "system")
+                        }) =>
+                {
+                    return Err(LlmApiError::ConfigError(format!(
+                        "prompt template line {}: system section after the conversation started",
+                        number + 1
+                    )))
+                }
+                (Some(role @ ("system" | "user" | "assistant")), None, None) => {
+                    current = Some((role.to_string(), String::new()));
+                }
+                (Some("examples"), Some(variable), None) if examples.is_none() => {
+                    examples = Some((variable.to_string(), Vec::new()));
+                }
+                (Some("end"), None, None) if examples.is_some() => {
+                    let (variable, messages) = examples.take().unwrap_or_default();
+                    sections.push(Section::Examples { variable, messages });
+                }
+                _ => {
+                    return Err(LlmApiError::ConfigError(format!(
+                        "prompt template line {}: unexpected section `{}`",
+                        number + 1,
+                        line
+                    )))
+                }
+            }
+        }
+        close(&mut current, &mut examples, &mut sections);
+        if let Some((variable, _)) = examples {
+            return Err(LlmApiError::ConfigError(format!(
+                "prompt template: examples block `{}` has no `--- end`",
+                variable
+            )));
+        }
+
+        Ok(Self {
+            sections,
+            partials: BTreeMap::new(),
+        })
+    }
+
+    /// Read and parse a template file.
+    pub fn from_file(path: impl AsRef<Path>) -> Result<Self, LlmApiError> {
+        let path = path.as_ref();
+        let source = std::fs::read_to_string(path).map_err(|e| {
+            LlmApiError::ConfigError(format!("cannot read {}: {}", path.display(), e))
+        })?;
+        Self::parse(&source)
+    }
+
+    /// Register text that `{{> name }}` inserts. Partials can use variables and other
+    /// partials.
+    pub fn with_partial(mut self, name: &str, text: &str) -> Self {
+        self.partials.insert(name.to_string(), text.to_string());
+        self
+    }
+
+    /// Variables the template needs, outside of examples blocks, including those used
+    /// by partials.
+    pub fn variables(&self) -> BTreeSet<String> {
+        let mut names = BTreeSet::new();
+        for section in &self.sections {
+            match section {
+                Section::Message { text, .. } => self.collect(text, 0, &mut names),
+                Section::Examples { variable, .. } => {
+                    names.insert(variable.clone());
+                }
+            }
+        }
+        names
+    }
+
+    fn collect(&self, text: &str, depth: usize, names: &mut BTreeSet<String>) {
+        for tag in tags(text) {
+            match tag.strip_prefix('>') {
+                Some(partial) if depth < MAX_PARTIAL_DEPTH => {
+                    if let Some(text) = self.partials.get(partial.trim()) {
+                        self.collect(text, depth + 1, names);
+                    }
+                }
+                Some(_) => {}
+                None => {
+                    names.insert(tag.to_string());
+                }
+            }
+        }
+    }
+
+    /// Render the messages with `variables`, any value that serializes to a JSON
+    /// object, such as a struct or a `serde_json::json!` map.
+    ///
+    /// Fails with `ConfigError` naming every missing variable or partial. `null`
+    /// values count as missing.
+    pub fn render_messages<T: Serialize>(
+        &self,
+        variables: &T,
+    ) -> Result<Vec<ChatMessage>, LlmApiError> {
+        let variables = serde_json::to_value(variables)
+            .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+        if !variables.is_object() {
+            return Err(LlmApiError::ConfigError(
+                "prompt variables must serialize to an object".to_string(),
+            ));
+        }
+
+        let mut missing = BTreeSet::new();
+        let mut messages = Vec::new();
+        for section in &self.sections {
+            match section {
+                Section::Message { role, text } => {
+                    let content = self.render_text(text, &[&variables], 0, &mut missing);
+                    messages.push(message(role, content));
+                }
+                Section::Examples {
+                    variable,
+                    messages: block,
+                } => match lookup(&variables, variable) {
+                    Some(Value::Array(items)) => {
+                        for item in items {
+                            for (role, text) in block {
+                                let scopes = [item, &variables];
+                                let content = self.render_text(text, &scopes, 0, &mut missing);
+                                messages.push(message(role, content));
+                            }
+                        }
+                    }
+                    Some(_) => {
+                        return Err(LlmApiError::ConfigError(format!(
+                            "prompt variable `{}` must be an array of examples",
+                            variable
+                        )))
+                    }
+                    None => {
+                        missing.insert(variable.clone());
+                    }
+                },
+            }
+        }
+
+        if !missing.is_empty() {
+            let missing: Vec<_> = missing.into_iter().collect();
+            return Err(LlmApiError::ConfigError(format!(
+                "missing prompt variables: {}",
+                missing.join(", ")
+            )));
+        }
+        Ok(messages)
+    }
+
+    /// Render a request for `model`. Other request fields are left at their defaults.
+    pub fn render<T: Serialize>(
+        &self,
+        model: &str,
+        variables: &T,
+    ) -> Result<ChatCompletionRequest, LlmApiError> {
+        Ok(ChatCompletionRequest {
+            model: model.to_string(),
+            messages: self.render_messages(variables)?,
+            ..Default::default()
+        })
+    }
+
+    fn render_text(
+        &self,
+        text: &str,
+        scopes: &[&Value],
+        depth: usize,
+        missing: &mut BTreeSet<String>,
+    ) -> String {
+        let mut output = String::new();
+        let mut rest = text;
+        while let Some(start) = rest.find("{{") {
+            let Some(len) = rest[start + 2..].find("}}") else {
+                break;
+            };
+            output.push_str(&rest[..start]);
+            let tag = rest[start + 2..start + 2 + len].trim();
+            rest = &rest[start + 2 + len + 2..];
+
+            if let Some(partial) = tag.strip_prefix('>') {
+                let partial = partial.trim();
+                match self.partials.get(partial) {
+                    Some(text) if depth < MAX_PARTIAL_DEPTH => {
+                        output.push_str(&self.render_text(text, scopes, depth + 1, missing));
+                    }
+                    Some(_) => {
+                        missing.insert(format!("> {} (nested too deeply)", partial));
+                    }
+                    None => {
+                        missing.insert(format!("> {}", partial));
+                    }
+                }
+                continue;
+            }
+
+            match scopes.iter().find_map(|scope| lookup(scope, tag)) {
+                Some(Value::String(value)) => output.push_str(value),
+                Some(value) => output.push_str(&value.to_string()),
+                None => {
+                    missing.insert(tag.to_string());
+                }
+            }
+        }
+        output.push_str(rest);
+        output
+    }
+}
+
+// Trimmed contents of every complete `{{ ... }}` tag in `text`.
+fn tags(text: &str) -> Vec<&str> {
+    let mut tags = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("{{") {
+        let Some(len) = rest[start + 2..].find("}}") else {
+            break;
+        };
+        tags.push(rest[start + 2..start + 2 + len].trim());
+        rest = &rest[start + 2 + len + 2..];
+    }
+    tags
+}
+
+// A dotted path into `value`; `null` counts as missing.
+fn lookup<'a>(value: &'a Value, path: &str) -> Option<&'a Value> {
+    let found = path
+        .split('.')
+        .try_fold(value, |value, key| value.as_object()?.get(key))?;
+    (!found.is_null()).then_some(found)
+}
+
+fn message(role: &str, content: String) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content,
+        ..Default::default()
+    }
+}
@@ -1,20 +1,63 @@
 // Anthropic API provider
 // https://docs.anthropic.com/en/api/getting-started
 // https://docs.anthropic.com/en/api/versioning
+// https://docs.anthropic.com/en/api/creating-message-batches
+// https://docs.anthropic.com/en/api/files-create
+// https://docs.anthropic.com/en/api/models-list
 // https://console.anthropic.com/dashboard
 
+use crate::batch::{BatchCounts, BatchJob, BatchProvider, BatchRequest, BatchResult, BatchStatus};
+use crate::capabilities::CapabilityCatalog;
 use crate::core::client::APIClient;
-use crate::core::{ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage};
+use crate::core::client::Multipart;
+use crate::core::telemetry::ChatSpan;
+use crate::core::tokens::{TokenCount, TokenCountSource};
+use crate::core::unix_timestamp;
+use crate::core::{
+    take_system_prompt, ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage,
+    ContentPart, FileHandle, FunctionCall, ModelInfo, Tool, ToolCall,
+};
 use crate::error::LlmApiError;
+use crate::files::{FileProvider, FileUpload};
 use reqwest::header::HeaderName;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Serialize)]
 struct AnthropicChatCompletionRequest {
-    messages: Vec<ChatMessage>,
+    // Leading system messages, which Anthropic takes apart from `messages`.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    // `ChatMessage`s, with file parts and tool calls resolved into content blocks.
+    messages: Vec<serde_json::Value>,
     model: String,
     max_tokens: u32,
     temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<AnthropicTool>>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicTool {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    input_schema: serde_json::Value,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicCountTokensRequest<'a> {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    system: Option<String>,
+    messages: Vec<serde_json::Value>,
+    model: &'a str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<AnthropicTool>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicCountTokensResponse {
+    input_tokens: u32,
 }
 
 #[derive(Debug, Deserialize)]
@@ -27,21 +70,111 @@ struct AnthropicChatCompletionResponse {
     usage: AnthropicUsage,
 }
 
+#[derive(Debug, Serialize)]
+struct AnthropicCreateBatchRequest {
+    requests: Vec<AnthropicBatchItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct AnthropicBatchItem {
+    custom_id: String,
+    params: AnthropicChatCompletionRequest,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicBatch {
+    id: String,
+    processing_status: String,
+    request_counts: AnthropicBatchCounts,
+    cancel_initiated_at: Option<String>,
+    results_url: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicBatchCounts {
+    processing: u32,
+    succeeded: u32,
+    errored: u32,
+    canceled: u32,
+    expired: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicBatchResultLine {
+    custom_id: String,
+    result: AnthropicBatchOutcome,
+}
+
 #[derive(Debug, Deserialize)]
-struct AnthropicContent {
-    text: String,
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicBatchOutcome {
+    Succeeded {
+        message: AnthropicChatCompletionResponse,
+    },
+    Errored {
+        error: serde_json::Value,
+    },
+    Canceled,
+    Expired,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicFile {
+    id: String,
+    filename: String,
+    mime_type: String,
+    size_bytes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicFileList {
+    data: Vec<AnthropicFile>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum AnthropicContent {
+    Text {
+        text: String,
+    },
+    ToolUse {
+        id: String,
+        name: String,
+        input: serde_json::Value,
+    },
+    // Thinking and other blocks that have no `ChatMessage` equivalent.
+    #[serde(other)]
+    Other,
 }
 
 #[derive(Debug, Deserialize)]
 struct AnthropicUsage {
     input_tokens: u32,
     output_tokens: u32,
+    cache_creation_input_tokens: Option<u32>,
+    cache_read_input_tokens: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicModelList {
+    data: Vec<AnthropicModel>,
+    #[serde(default)]
+    has_more: bool,
+    last_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct AnthropicModel {
+    id: String,
+    display_name: Option<String>,
+    created_at: Option<String>,
 }
 
 pub struct Anthropic {
     domain: String,
     api_key: String,
     client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
 }
 
 impl Anthropic {
@@ -50,54 +183,432 @@ impl Anthropic {
             domain: "https://api.anthropic.com".to_string(),
             api_key,
             client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl super::LlmProvider for Anthropic {
-    async fn chat_completion<'a>(
-        &'a self,
-        request: ChatCompletionRequest,
-    ) -> Result<ChatCompletionResponse, LlmApiError> {
-        let url = format!("{}/v1/messages", self.domain);
+    /// Override the API base URL, e.g. for a proxy or a local test server.
+    pub fn with_domain(mut self, domain: String) -> Self {
+        self.domain = domain;
+        self
+    }
 
-        let headers = vec![
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
+    fn headers(&self) -> Vec<(HeaderName, String)> {
+        vec![
             (HeaderName::from_static("x-api-key"), self.api_key.clone()),
             (
                 HeaderName::from_static("anthropic-version"),
                 "2023-06-01".to_string(),
             ),
-        ];
+        ]
+    }
 
-        let req = AnthropicChatCompletionRequest {
-            messages: request.messages,
+    // Files are still a beta feature, enabled per request.
+    fn files_headers(&self) -> Vec<(HeaderName, String)> {
+        let mut headers = self.headers();
+        headers.push((
+            HeaderName::from_static("anthropic-beta"),
+            "files-api-2025-04-14".to_string(),
+        ));
+        headers
+    }
+
+    fn chat_body(
+        request: ChatCompletionRequest,
+    ) -> Result<AnthropicChatCompletionRequest, LlmApiError> {
+        let mut messages = request.messages;
+        Ok(AnthropicChatCompletionRequest {
+            system: take_system_prompt("anthropic", &mut messages)?,
+            messages: Self::convert_messages(messages)?,
             model: request.model,
             max_tokens: request.max_tokens.unwrap_or(1000),
             temperature: request.temperature,
-        };
-
-        let res: AnthropicChatCompletionResponse =
-            self.client.send_request(url, headers, &req).await?;
+            tools: Self::convert_tools(request.tools),
+        })
+    }
 
-        Ok(ChatCompletionResponse {
-            id: res.id,
-            model: res.model,
-            choices: res
-                .content
+    fn convert_tools(tools: Option<Vec<Tool>>) -> Option<Vec<AnthropicTool>> {
+        let tools = tools.filter(|tools| !tools.is_empty())?;
+        Some(
+            tools
                 .into_iter()
-                .map(|content| ChatChoice {
+                .map(|tool| AnthropicTool {
+                    name: tool.function.name,
+                    description: tool.function.description,
+                    input_schema: tool.function.parameters,
+                })
+                .collect(),
+        )
+    }
+
+    // Images become `image` blocks and every other file a `document` block, placed
+    // before the text. Tool calls become `tool_use` blocks after the text, and tool
+    // results `tool_result` blocks in a user turn; results of consecutive tool
+    // messages share one turn, as Anthropic expects.
+    fn convert_messages(messages: Vec<ChatMessage>) -> Result<Vec<serde_json::Value>, LlmApiError> {
+        let mut converted: Vec<serde_json::Value> = Vec::with_capacity(messages.len());
+        let mut in_tool_results = false;
+        for msg in messages {
+            if let Some(id) = msg.tool_call_id {
+                let block = serde_json::json!({
+                    "type": "tool_result",
+                    "tool_use_id": id,
+                    "content": msg.content,
+                });
+                match converted.last_mut() {
+                    Some(last) if in_tool_results => {
+                        if let Some(blocks) = last["content"].as_array_mut() {
+                            blocks.push(block);
+                        }
+                    }
+                    _ => converted.push(serde_json::json!({"role": "user", "content": [block]})),
+                }
+                in_tool_results = true;
+                continue;
+            }
+            in_tool_results = false;
+
+            let tool_calls = msg.tool_calls.unwrap_or_default();
+            if msg.parts.is_empty() && tool_calls.is_empty() {
+                converted.push(serde_json::json!({"role": msg.role, "content": msg.content}));
+                continue;
+            }
+            let mut content = Vec::new();
+            for part in msg.parts {
+                let ContentPart::File(file) = part;
+                file.check_provider("anthropic")?;
+                let block_type = if file.mime_type.starts_with("image/") {
+                    "image"
+                } else {
+                    "document"
+                };
+                content.push(serde_json::json!({
+                    "type": block_type,
+                    "source": {"type": "file", "file_id": file.id},
+                }));
+            }
+            if !msg.content.is_empty() {
+                content.push(serde_json::json!({"type": "text", "text": msg.content}));
+            }
+            for call in tool_calls {
+                content.push(serde_json::json!({
+                    "type": "tool_use",
+                    "id": call.id,
+                    "name": call.function.name,
+                    "input": call.arguments_value()?,
+                }));
+            }
+            converted.push(serde_json::json!({"role": msg.role, "content": content}));
+        }
+        Ok(converted)
+    }
+
+    fn convert_file(file: AnthropicFile) -> FileHandle {
+        FileHandle {
+            provider: "anthropic".to_string(),
+            id: file.id,
+            filename: file.filename,
+            mime_type: file.mime_type,
+            size_bytes: file.size_bytes,
+            uri: None,
+        }
+    }
+
+    // One choice per text block. `tool_use` blocks go on the first choice, which is
+    // left without text if the reply has none.
+    fn convert_response(res: AnthropicChatCompletionResponse) -> ChatCompletionResponse {
+        let mut choices = Vec::new();
+        let mut tool_calls = Vec::new();
+        for content in res.content {
+            match content {
+                AnthropicContent::Text { text } => choices.push(ChatChoice {
                     message: ChatMessage {
                         role: res.role.clone(),
-                        content: content.text,
+                        content: text,
+                        ..Default::default()
                     },
                     finish_reason: res.stop_reason.clone(),
-                })
-                .collect(),
+                }),
+                AnthropicContent::ToolUse { id, name, input } => tool_calls.push(ToolCall {
+                    id,
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name,
+                        arguments: input.to_string(),
+                    },
+                }),
+                AnthropicContent::Other => {}
+            }
+        }
+        if !tool_calls.is_empty() {
+            if choices.is_empty() {
+                choices.push(ChatChoice {
+                    message: ChatMessage {
+                        role: res.role.clone(),
+                        ..Default::default()
+                    },
+                    finish_reason: res.stop_reason.clone(),
+                });
+            }
+            choices[0].message.tool_calls = Some(tool_calls);
+        }
+
+        ChatCompletionResponse {
+            id: res.id,
+            model: res.model,
+            choices,
+            // Anthropic reports cache reads and writes separately from `input_tokens`.
             usage: Some(crate::core::ChatUsage {
-                input_tokens: Some(res.usage.input_tokens),
+                input_tokens: Some(
+                    res.usage.input_tokens
+                        + res.usage.cache_creation_input_tokens.unwrap_or(0)
+                        + res.usage.cache_read_input_tokens.unwrap_or(0),
+                ),
                 output_tokens: Some(res.usage.output_tokens),
+                cached_input_tokens: res.usage.cache_read_input_tokens,
+                cache_write_tokens: res.usage.cache_creation_input_tokens,
+                ..Default::default()
             }),
+            ..Default::default()
+        }
+    }
+
+    async fn chat(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        let url = format!("{}/v1/messages", self.domain);
+        let headers = if request.messages.iter().any(|msg| !msg.parts.is_empty()) {
+            self.files_headers()
+        } else {
+            self.headers()
+        };
+        let req = Self::chat_body(request)?;
+
+        let res: AnthropicChatCompletionResponse =
+            self.client.send_request(url, headers, &req).await?;
+
+        Ok(Self::convert_response(res))
+    }
+
+    fn convert_batch(batch: AnthropicBatch) -> BatchJob {
+        let counts = batch.request_counts;
+        let status = match batch.processing_status.as_str() {
+            "ended" if batch.cancel_initiated_at.is_some() => BatchStatus::Cancelled,
+            "ended" => BatchStatus::Completed,
+            // in_progress and canceling
+            _ => BatchStatus::InProgress,
+        };
+        BatchJob {
+            id: batch.id,
+            status,
+            counts: BatchCounts {
+                total: counts.processing
+                    + counts.succeeded
+                    + counts.errored
+                    + counts.canceled
+                    + counts.expired,
+                succeeded: counts.succeeded,
+                failed: counts.errored + counts.canceled + counts.expired,
+            },
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl super::LlmProvider for Anthropic {
+    async fn chat_completion<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.capabilities.validate("anthropic", &request)?;
+        ChatSpan::new("anthropic", &request)
+            .instrument(self.chat(request))
+            .await
+    }
+
+    /// Counted by the `/v1/messages/count_tokens` endpoint.
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        let url = format!("{}/v1/messages/count_tokens", self.domain);
+        let headers = if request.messages.iter().any(|msg| !msg.parts.is_empty()) {
+            self.files_headers()
+        } else {
+            self.headers()
+        };
+        let mut messages = request.messages.clone();
+        let req = AnthropicCountTokensRequest {
+            system: take_system_prompt("anthropic", &mut messages)?,
+            messages: Self::convert_messages(messages)?,
+            model: &request.model,
+            tools: Self::convert_tools(request.tools.clone()),
+        };
+        let res: AnthropicCountTokensResponse =
+            self.client.send_request(url, headers, &req).await?;
+        Ok(TokenCount {
+            input_tokens: res.input_tokens,
+            source: TokenCountSource::Provider,
         })
     }
+
+    /// Follows `has_more` until every page is read.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        let mut models = Vec::new();
+        let mut after_id: Option<String> = None;
+        loop {
+            let mut url = reqwest::Url::parse(&format!("{}/v1/models", self.domain))
+                .map_err(|e| LlmApiError::ConfigError(format!("invalid domain: {}", e)))?;
+            url.query_pairs_mut().append_pair("limit", "1000");
+            if let Some(after_id) = &after_id {
+                url.query_pairs_mut().append_pair("after_id", after_id);
+            }
+            let list: AnthropicModelList =
+                self.client.get_request(url.into(), self.headers()).await?;
+            models.extend(list.data.into_iter().map(|model| ModelInfo {
+                id: model.id,
+                provider: "anthropic".to_string(),
+                display_name: model.display_name,
+                owned_by: Some("anthropic".to_string()),
+                created: model.created_at.as_deref().and_then(unix_timestamp),
+                ..Default::default()
+            }));
+            match list.last_id {
+                Some(last_id) if list.has_more => after_id = Some(last_id),
+                _ => return Ok(models),
+            }
+        }
+    }
+}
+
+#[async_trait::async_trait]
+impl BatchProvider for Anthropic {
+    async fn create_batch(&self, requests: Vec<BatchRequest>) -> Result<BatchJob, LlmApiError> {
+        let url = format!("{}/v1/messages/batches", self.domain);
+        let headers = if requests
+            .iter()
+            .any(|r| r.request.messages.iter().any(|msg| !msg.parts.is_empty()))
+        {
+            self.files_headers()
+        } else {
+            self.headers()
+        };
+        let req = AnthropicCreateBatchRequest {
+            requests: requests
+                .into_iter()
+                .map(|request| {
+                    Ok(AnthropicBatchItem {
+                        custom_id: request.custom_id,
+                        params: Self::chat_body(request.request)?,
+                    })
+                })
+                .collect::<Result<_, LlmApiError>>()?,
+        };
+        let batch: AnthropicBatch = self.client.send_request(url, headers, &req).await?;
+        Ok(Self::convert_batch(batch))
+    }
+
+    async fn get_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        let url = format!("{}/v1/messages/batches/{}", self.domain, id);
+        let batch: AnthropicBatch = self.client.get_request(url, self.headers()).await?;
+        Ok(Self::convert_batch(batch))
+    }
+
+    async fn cancel_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        let url = format!("{}/v1/messages/batches/{}/cancel", self.domain, id);
+        let batch: AnthropicBatch = self
+            .client
+            .send_request(url, self.headers(), &serde_json::json!({}))
+            .await?;
+        Ok(Self::convert_batch(batch))
+    }
+
+    /// Read from the batch's `results_url`, which is only set once it has ended.
+    async fn batch_results(&self, id: &str) -> Result<Vec<BatchResult>, LlmApiError> {
+        let url = format!("{}/v1/messages/batches/{}", self.domain, id);
+        let batch: AnthropicBatch = self.client.get_request(url, self.headers()).await?;
+        let Some(results_url) = batch.results_url else {
+            return Err(LlmApiError::ProviderError(format!(
+                "batch {} has not ended",
+                id
+            )));
+        };
+
+        let content = self.client.get_text(results_url, self.headers()).await?;
+        let mut results = Vec::new();
+        for line in content.lines().filter(|line| !line.trim().is_empty()) {
+            let line: AnthropicBatchResultLine = serde_json::from_str(line)
+                .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?;
+            let response = match line.result {
+                AnthropicBatchOutcome::Succeeded { message } => Ok(Self::convert_response(message)),
+                AnthropicBatchOutcome::Errored { error } => {
+                    // The API error is nested: {"type": "error", "error": {"message": ...}}.
+                    let message = error["error"]["message"]
+                        .as_str()
+                        .map(str::to_string)
+                        .unwrap_or_else(|| error.to_string());
+                    Err(LlmApiError::ProviderError(message))
+                }
+                AnthropicBatchOutcome::Canceled => Err(LlmApiError::ProviderError(
+                    "request was cancelled".to_string(),
+                )),
+                AnthropicBatchOutcome::Expired => {
+                    Err(LlmApiError::ProviderError("request expired".to_string()))
+                }
+            };
+            results.push(BatchResult {
+                custom_id: line.custom_id,
+                response,
+            });
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait::async_trait]
+impl FileProvider for Anthropic {
+    async fn upload_file(&self, upload: FileUpload) -> Result<FileHandle, LlmApiError> {
+        let url = format!("{}/v1/files", self.domain);
+        let form = Multipart::new().file("file", &upload.filename, &upload.mime_type, upload.data);
+        let file: AnthropicFile = self
+            .client
+            .send_multipart(url, self.files_headers(), form)
+            .await?;
+        Ok(Self::convert_file(file))
+    }
+
+    async fn list_files(&self) -> Result<Vec<FileHandle>, LlmApiError> {
+        let url = format!("{}/v1/files", self.domain);
+        let list: AnthropicFileList = self.client.get_request(url, self.files_headers()).await?;
+        Ok(list.data.into_iter().map(Self::convert_file).collect())
+    }
+
+    async fn get_file(&self, id: &str) -> Result<FileHandle, LlmApiError> {
+        let url = format!("{}/v1/files/{}", self.domain, id);
+        let file: AnthropicFile = self.client.get_request(url, self.files_headers()).await?;
+        Ok(Self::convert_file(file))
+    }
+
+    async fn delete_file(&self, id: &str) -> Result<(), LlmApiError> {
+        let url = format!("{}/v1/files/{}", self.domain, id);
+        let _: serde_json::Value = self
+            .client
+            .delete_request(url, self.files_headers())
+            .await?;
+        Ok(())
+    }
 }
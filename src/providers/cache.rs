@@ -0,0 +1,273 @@
+// Response caching around any provider.
+// Requests are keyed by a SHA-256 of their canonical JSON, so identical requests hit
+// the same entry across runs when stored on disk.
+
+use crate::core::runtime::{Instant, SystemTime, UNIX_EPOCH};
+use crate::core::tokens::TokenCount;
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse, ModelInfo};
+use crate::error::LlmApiError;
+use crate::providers::LlmProvider;
+use async_trait::async_trait;
+use serde::{Deserialize, Serialize};
+use sha2::{Digest, Sha256};
+use std::collections::{BTreeMap, HashMap};
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Storage for cached responses.
+pub trait CacheStore: Send + Sync {
+    /// A fresh entry for `key`, if there is one.
+    fn get(&self, key: &str) -> Option<ChatCompletionResponse>;
+
+    fn put(&self, key: &str, response: &ChatCompletionResponse) -> Result<(), LlmApiError>;
+}
+
+/// In-memory store that evicts the least recently used entry beyond `capacity`.
+pub struct MemoryCache {
+    capacity: usize,
+    ttl: Option<Duration>,
+    state: Mutex<LruState>,
+}
+
+#[derive(Default)]
+struct LruState {
+    entries: HashMap<String, MemoryEntry>,
+    // Last use tick to key, oldest first.
+    recency: BTreeMap<u64, String>,
+    tick: u64,
+}
+
+struct MemoryEntry {
+    response: ChatCompletionResponse,
+    stored_at: Instant,
+    last_used: u64,
+}
+
+impl MemoryCache {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            capacity,
+            ttl: None,
+            state: Mutex::new(LruState::default()),
+        }
+    }
+
+    /// Treat entries older than `ttl` as missing.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.state.lock().unwrap().entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl LruState {
+    fn touch(&mut self, key: &str) {
+        self.tick += 1;
+        if let Some(entry) = self.entries.get_mut(key) {
+            self.recency.remove(&entry.last_used);
+            entry.last_used = self.tick;
+            self.recency.insert(self.tick, key.to_string());
+        }
+    }
+
+    fn remove(&mut self, key: &str) {
+        if let Some(entry) = self.entries.remove(key) {
+            self.recency.remove(&entry.last_used);
+        }
+    }
+}
+
+impl CacheStore for MemoryCache {
+    fn get(&self, key: &str) -> Option<ChatCompletionResponse> {
+        let mut state = self.state.lock().unwrap();
+        let stored_at = state.entries.get(key)?.stored_at;
+        if self.ttl.is_some_and(|ttl| stored_at.elapsed() >= ttl) {
+            state.remove(key);
+            return None;
+        }
+        state.touch(key);
+        state.entries.get(key).map(|entry| entry.response.clone())
+    }
+
+    fn put(&self, key: &str, response: &ChatCompletionResponse) -> Result<(), LlmApiError> {
+        if self.capacity == 0 {
+            return Ok(());
+        }
+        let mut state = self.state.lock().unwrap();
+        state.remove(key);
+        state.entries.insert(
+            key.to_string(),
+            MemoryEntry {
+                response: response.clone(),
+                stored_at: Instant::now(),
+                last_used: 0,
+            },
+        );
+        state.touch(key);
+        while state.entries.len() > self.capacity {
+            let Some((_, oldest)) = state.recency.pop_first() else {
+                break;
+            };
+            state.entries.remove(&oldest);
+        }
+        Ok(())
+    }
+}
+
+/// One JSON file per entry in a directory, so the cache survives restarts.
+pub struct DiskCache {
+    dir: PathBuf,
+    ttl: Option<Duration>,
+}
+
+#[derive(Serialize, Deserialize)]
+struct DiskEntry {
+    // Seconds since the Unix epoch.
+    stored_at: u64,
+    response: ChatCompletionResponse,
+}
+
+impl DiskCache {
+    /// The directory is created on the first write.
+    pub fn new(dir: impl Into<PathBuf>) -> Self {
+        Self {
+            dir: dir.into(),
+            ttl: None,
+        }
+    }
+
+    /// Treat entries older than `ttl` as missing.
+    pub fn with_ttl(mut self, ttl: Duration) -> Self {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(format!("{}.json", key))
+    }
+}
+
+fn unix_now() -> u64 {
+    SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or(0)
+}
+
+impl CacheStore for DiskCache {
+    fn get(&self, key: &str) -> Option<ChatCompletionResponse> {
+        // Unreadable or corrupt entries are treated as misses and overwritten later.
+        let data = std::fs::read_to_string(self.path(key)).ok()?;
+        let entry: DiskEntry = serde_json::from_str(&data).ok()?;
+        if let Some(ttl) = self.ttl {
+            if unix_now().saturating_sub(entry.stored_at) >= ttl.as_secs() {
+                return None;
+            }
+        }
+        Some(entry.response)
+    }
+
+    fn put(&self, key: &str, response: &ChatCompletionResponse) -> Result<(), LlmApiError> {
+        let entry = DiskEntry {
+            stored_at: unix_now(),
+            response: response.clone(),
+        };
+        let data = serde_json::to_string(&entry)
+            .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+        std::fs::create_dir_all(&self.dir).map_err(|e| {
+            LlmApiError::ConfigError(format!("cannot create {}: {}", self.dir.display(), e))
+        })?;
+        let path = self.path(key);
+        std::fs::write(&path, data).map_err(|e| {
+            LlmApiError::ConfigError(format!("cannot write {}: {}", path.display(), e))
+        })
+    }
+}
+
+/// Wraps a provider and serves repeated requests from a `CacheStore`.
+///
+/// Only deterministic requests are cached, i.e. those with `temperature` set to 0,
+/// unless `with_force(true)` is used. Responses served from the cache have
+/// `metadata.cache_hit` set.
+pub struct CachedProvider<P> {
+    inner: P,
+    store: Arc<dyn CacheStore>,
+    force: bool,
+}
+
+impl<P: LlmProvider> CachedProvider<P> {
+    pub fn new(inner: P, store: Arc<dyn CacheStore>) -> Self {
+        Self {
+            inner,
+            store,
+            force: false,
+        }
+    }
+
+    /// Cache requests with a non-zero or default temperature as well.
+    pub fn with_force(mut self, force: bool) -> Self {
+        self.force = force;
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn is_cacheable(&self, request: &ChatCompletionRequest) -> bool {
+        self.force || request.temperature == Some(0.0)
+    }
+}
+
+/// Cache key for a request: the hex SHA-256 of its canonical JSON (model, messages,
+/// sampling parameters and tools, with object keys sorted).
+pub fn cache_key(request: &ChatCompletionRequest) -> Result<String, LlmApiError> {
+    // Going through `Value` sorts object keys, so field order never changes the key.
+    let canonical = serde_json::to_value(request)
+        .and_then(|value| serde_json::to_vec(&value))
+        .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+    let digest = Sha256::digest(&canonical);
+    Ok(digest.iter().map(|byte| format!("{:02x}", byte)).collect())
+}
+
+#[async_trait]
+impl<P: LlmProvider + Send + Sync> LlmProvider for CachedProvider<P> {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        if !self.is_cacheable(&request) {
+            return self.inner.chat_completion(request).await;
+        }
+
+        let key = cache_key(&request)?;
+        if let Some(mut response) = self.store.get(&key) {
+            response.metadata.cache_hit = true;
+            return Ok(response);
+        }
+
+        let response = self.inner.chat_completion(request).await?;
+        // A failed write only costs a miss next time, so it does not fail the call.
+        let _ = self.store.put(&key, &response);
+        Ok(response)
+    }
+
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        self.inner.count_tokens(request).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        self.inner.list_models().await
+    }
+}
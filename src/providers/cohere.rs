@@ -0,0 +1,263 @@
+// Cohere API provider (v2 chat)
+// https://docs.cohere.com/reference/chat
+// https://dashboard.cohere.com/api-keys
+
+use crate::capabilities::CapabilityCatalog;
+use crate::core::client::APIClient;
+use crate::core::telemetry::ChatSpan;
+use crate::core::{
+    reject_parts, ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage,
+    ChatUsage, Tool, ToolCall,
+};
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize)]
+struct CohereChatRequest {
+    messages: Vec<CohereMessage>,
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    documents: Vec<CohereDocument>,
+}
+
+#[derive(Debug, Serialize)]
+struct CohereMessage {
+    role: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<ToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+/// A document the model can ground its answer on and cite.
+#[derive(Debug, Clone, Serialize)]
+pub struct CohereDocument {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<String>,
+    pub data: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereChatResponse {
+    id: String,
+    finish_reason: String,
+    message: CohereResponseMessage,
+    usage: Option<CohereUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereResponseMessage {
+    role: String,
+    #[serde(default)]
+    content: Vec<CohereContent>,
+    tool_calls: Option<Vec<ToolCall>>,
+    #[serde(default)]
+    citations: Vec<CohereCitation>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereContent {
+    #[serde(rename = "type")]
+    content_type: String,
+    #[serde(default)]
+    text: String,
+}
+
+/// A span of the reply that is supported by one or more sources.
+#[derive(Debug, Clone, Deserialize)]
+pub struct CohereCitation {
+    pub start: u32,
+    pub end: u32,
+    pub text: String,
+    #[serde(default)]
+    pub sources: Vec<CohereCitationSource>,
+}
+
+#[derive(Debug, Clone, Deserialize)]
+pub struct CohereCitationSource {
+    #[serde(rename = "type")]
+    pub source_type: String,
+    pub id: Option<String>,
+    pub document: Option<serde_json::Value>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereUsage {
+    billed_units: Option<CohereTokens>,
+    tokens: Option<CohereTokens>,
+}
+
+#[derive(Debug, Deserialize)]
+struct CohereTokens {
+    input_tokens: Option<f64>,
+    output_tokens: Option<f64>,
+}
+
+/// The normalized response together with the citations Cohere returned for it.
+#[derive(Debug)]
+pub struct CohereChatCompletion {
+    pub response: ChatCompletionResponse,
+    pub citations: Vec<CohereCitation>,
+}
+
+pub struct Cohere {
+    domain: String,
+    api_key: String,
+    client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
+}
+
+impl Cohere {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            domain: "https://api.cohere.com".to_string(),
+            api_key,
+            client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
+        }
+    }
+
+    /// Override the API base URL, e.g. for a proxy or a local test server.
+    pub fn with_domain(mut self, domain: String) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
+    /// Chat grounded on `documents`; the citations are returned alongside the response.
+    pub async fn chat_with_documents(
+        &self,
+        request: ChatCompletionRequest,
+        documents: Vec<CohereDocument>,
+    ) -> Result<CohereChatCompletion, LlmApiError> {
+        self.capabilities.validate("cohere", &request)?;
+        let mut citations = Vec::new();
+        let response = ChatSpan::new("cohere", &request)
+            .instrument(async {
+                let completion = self.chat(request, documents).await?;
+                citations = completion.citations;
+                Ok(completion.response)
+            })
+            .await?;
+        Ok(CohereChatCompletion {
+            response,
+            citations,
+        })
+    }
+
+    async fn chat(
+        &self,
+        request: ChatCompletionRequest,
+        documents: Vec<CohereDocument>,
+    ) -> Result<CohereChatCompletion, LlmApiError> {
+        reject_parts("cohere", &request.messages)?;
+        let url = format!("{}/v2/chat", self.domain);
+
+        let req = CohereChatRequest {
+            messages: Self::convert_messages(request.messages),
+            model: request.model.clone(),
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            tools: request.tools,
+            documents,
+        };
+        let headers = vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))];
+        let res: CohereChatResponse = self.client.send_request(url, headers, &req).await?;
+
+        let content = res
+            .message
+            .content
+            .into_iter()
+            .filter(|content| content.content_type == "text")
+            .map(|content| content.text)
+            .collect::<Vec<_>>()
+            .join("");
+
+        Ok(CohereChatCompletion {
+            response: ChatCompletionResponse {
+                id: res.id,
+                model: request.model,
+                choices: vec![ChatChoice {
+                    message: ChatMessage {
+                        role: res.message.role,
+                        content,
+                        tool_calls: res.message.tool_calls,
+                        ..Default::default()
+                    },
+                    finish_reason: Self::normalize_finish_reason(&res.finish_reason),
+                }],
+                usage: res
+                    .usage
+                    .and_then(|usage| usage.billed_units.or(usage.tokens))
+                    .map(|tokens| ChatUsage {
+                        input_tokens: tokens.input_tokens.map(|n| n as u32),
+                        output_tokens: tokens.output_tokens.map(|n| n as u32),
+                        ..Default::default()
+                    }),
+                ..Default::default()
+            },
+            citations: res.message.citations,
+        })
+    }
+
+    fn convert_messages(messages: Vec<ChatMessage>) -> Vec<CohereMessage> {
+        messages
+            .into_iter()
+            .map(|msg| CohereMessage {
+                // Cohere rejects empty assistant content alongside tool calls.
+                content: if msg.content.is_empty() && msg.tool_calls.is_some() {
+                    None
+                } else {
+                    Some(msg.content)
+                },
+                role: msg.role,
+                tool_calls: msg.tool_calls,
+                tool_call_id: msg.tool_call_id,
+            })
+            .collect()
+    }
+
+    fn normalize_finish_reason(reason: &str) -> String {
+        match reason {
+            "COMPLETE" | "STOP_SEQUENCE" => "stop".to_string(),
+            "MAX_TOKENS" => "length".to_string(),
+            "TOOL_CALL" => "tool_calls".to_string(),
+            other => other.to_lowercase(),
+        }
+    }
+}
+
+#[async_trait]
+impl crate::providers::LlmProvider for Cohere {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.chat_with_documents(request, vec![])
+            .await
+            .map(|completion| completion.response)
+    }
+}
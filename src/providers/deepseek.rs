@@ -2,12 +2,18 @@
 // https://api-docs.deepseek.com/
 // https://platform.deepseek.com
 
+use crate::capabilities::CapabilityCatalog;
 use crate::core::client::APIClient;
-use crate::core::{ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage};
+use crate::core::telemetry::ChatSpan;
+use crate::core::{
+    reject_parts, ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage,
+    ChatUsage, ModelInfo, Tool, ToolCall,
+};
 use crate::error::LlmApiError;
 use async_trait::async_trait;
 use reqwest::header;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Serialize)]
 struct DeepSeekChatRequest {
@@ -17,12 +23,18 @@ struct DeepSeekChatRequest {
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct DeepSeekMessage {
     role: String,
-    content: String,
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<ToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -30,6 +42,7 @@ struct DeepSeekChatResponse {
     id: String,
     model: String,
     choices: Vec<DeepSeekChoice>,
+    usage: Option<DeepSeekUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -38,10 +51,36 @@ struct DeepSeekChoice {
     finish_reason: String,
 }
 
+#[derive(Debug, Deserialize)]
+struct DeepSeekUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    prompt_cache_hit_tokens: Option<u32>,
+    completion_tokens_details: Option<DeepSeekTokenDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepSeekTokenDetails {
+    reasoning_tokens: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepSeekModelList {
+    data: Vec<DeepSeekModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct DeepSeekModel {
+    id: String,
+    created: Option<u64>,
+    owned_by: Option<String>,
+}
+
 pub struct DeepSeek {
     domain: String,
     api_key: String,
     client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
 }
 
 impl DeepSeek {
@@ -50,26 +89,46 @@ impl DeepSeek {
             domain: "https://api.deepseek.com".to_string(),
             api_key,
             client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
         }
     }
 
+    /// Override the API base URL, e.g. for a proxy or a local test server.
+    pub fn with_domain(mut self, domain: String) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
     fn convert_messages(messages: Vec<ChatMessage>) -> Vec<DeepSeekMessage> {
         messages
             .into_iter()
             .map(|msg| DeepSeekMessage {
                 role: msg.role,
-                content: msg.content,
+                content: Some(msg.content),
+                tool_calls: msg.tool_calls,
+                tool_call_id: msg.tool_call_id,
             })
             .collect()
     }
-}
 
-#[async_trait]
-impl crate::providers::LlmProvider for DeepSeek {
-    async fn chat_completion(
+    async fn chat(
         &self,
         request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, LlmApiError> {
+        reject_parts("deepseek", &request.messages)?;
         let url = format!("{}/v1/chat/completions", self.domain);
 
         let headers = vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))];
@@ -79,6 +138,7 @@ impl crate::providers::LlmProvider for DeepSeek {
             model: request.model,
             temperature: request.temperature,
             max_tokens: request.max_tokens,
+            tools: request.tools,
         };
 
         let res: DeepSeekChatResponse = self.client.send_request(url, headers, &req).await?;
@@ -91,13 +151,59 @@ impl crate::providers::LlmProvider for DeepSeek {
                 .map(|choice| ChatChoice {
                     message: ChatMessage {
                         role: choice.message.role,
-                        content: choice.message.content,
+                        content: choice.message.content.unwrap_or_default(),
+                        tool_calls: choice.message.tool_calls,
+                        ..Default::default()
                     },
                     finish_reason: choice.finish_reason,
                 })
                 .collect(),
             model: res.model,
-            usage: None,
+            usage: res.usage.map(|usage| ChatUsage {
+                input_tokens: Some(usage.prompt_tokens),
+                output_tokens: Some(usage.completion_tokens),
+                cached_input_tokens: usage.prompt_cache_hit_tokens,
+                reasoning_tokens: usage
+                    .completion_tokens_details
+                    .and_then(|d| d.reasoning_tokens),
+                ..Default::default()
+            }),
+            ..Default::default()
         })
     }
 }
+
+#[async_trait]
+impl crate::providers::LlmProvider for DeepSeek {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.capabilities.validate("deepseek", &request)?;
+        ChatSpan::new("deepseek", &request)
+            .instrument(self.chat(request))
+            .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        let url = format!("{}/v1/models", self.domain);
+        let list: DeepSeekModelList = self
+            .client
+            .get_request(
+                url,
+                vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))],
+            )
+            .await?;
+        Ok(list
+            .data
+            .into_iter()
+            .map(|model| ModelInfo {
+                id: model.id,
+                provider: "deepseek".to_string(),
+                owned_by: model.owned_by,
+                created: model.created,
+                ..Default::default()
+            })
+            .collect())
+    }
+}
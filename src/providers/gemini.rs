@@ -1,33 +1,180 @@
 // Gemini API provider
 // https://ai.google.dev/api/generate-content?hl=en
+// https://ai.google.dev/api/batch-mode
+// https://ai.google.dev/api/files
+// https://ai.google.dev/gemini-api/docs/imagen
+// https://ai.google.dev/api/models
 // https://aistudio.google.com/app/apikey
 
+use crate::batch::{BatchCounts, BatchJob, BatchProvider, BatchRequest, BatchResult, BatchStatus};
+use crate::capabilities::CapabilityCatalog;
 use crate::core::client::APIClient;
-use crate::core::{ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage};
+use crate::core::telemetry::ChatSpan;
+use crate::core::tokens::{TokenCount, TokenCountSource};
+use crate::core::{
+    take_system_prompt, tool_call_name, ChatChoice, ChatCompletionRequest, ChatCompletionResponse,
+    ChatMessage, ChatUsage, ContentPart, FileHandle, FunctionCall, ModelInfo, Tool, ToolCall,
+};
 use crate::error::LlmApiError;
+use crate::files::{FileProvider, FileUpload};
+use crate::images::{
+    GeneratedImage, ImageData, ImageFormat, ImageProvider, ImageRequest, ImageResponse,
+};
+use reqwest::header::HeaderName;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Serialize, Deserialize, Default)]
 struct GeminiChatCompletionRequest {
+    // Leading system messages, which Gemini takes apart from `contents`.
+    #[serde(rename = "systemInstruction", skip_serializing_if = "Option::is_none")]
+    system_instruction: Option<GeminiSystemInstruction>,
     contents: Vec<GeminiChatCompletionContent>,
     #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<GeminiTool>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
     generation_config: Option<GeminiGenerationConfig>,
 }
 
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiSystemInstruction {
+    parts: Vec<GeminiPart>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiTool {
+    #[serde(rename = "functionDeclarations")]
+    function_declarations: Vec<GeminiFunctionDeclaration>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionDeclaration {
+    name: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    description: Option<String>,
+    parameters: serde_json::Value,
+}
+
 #[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
 struct GeminiChatCompletionResponse {
+    // Missing when the prompt itself was blocked.
+    #[serde(default)]
     candidates: Vec<GeminiCandidate>,
+    prompt_feedback: Option<GeminiPromptFeedback>,
+    usage_metadata: Option<GeminiUsageMetadata>,
+    response_id: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiPromptFeedback {
+    block_reason: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiUsageMetadata {
+    prompt_token_count: Option<u32>,
+    candidates_token_count: Option<u32>,
+    cached_content_token_count: Option<u32>,
+    thoughts_token_count: Option<u32>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GeminiChatCompletionContent {
     role: String,
+    #[serde(default)]
     parts: Vec<GeminiPart>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct GeminiPart {
+    // A part holds one of text, a file, a function call or a function response;
+    // Gemini rejects parts that set more than one.
+    #[serde(default, skip_serializing_if = "String::is_empty")]
     text: String,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    file_data: Option<GeminiFileData>,
+    #[serde(
+        default,
+        rename = "functionCall",
+        skip_serializing_if = "Option::is_none"
+    )]
+    function_call: Option<GeminiFunctionCall>,
+    #[serde(
+        default,
+        rename = "functionResponse",
+        skip_serializing_if = "Option::is_none"
+    )]
+    function_response: Option<GeminiFunctionResponse>,
+}
+
+impl GeminiPart {
+    fn text(text: String) -> Self {
+        Self {
+            text,
+            file_data: None,
+            function_call: None,
+            function_response: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionCall {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    name: String,
+    #[serde(default)]
+    args: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFunctionResponse {
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    id: Option<String>,
+    name: String,
+    response: serde_json::Value,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiFileData {
+    mime_type: String,
+    file_uri: String,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiStartUploadRequest {
+    file: GeminiUploadMetadata,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiUploadMetadata {
+    display_name: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiUploadResponse {
+    file: GeminiFile,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiFile {
+    name: String,
+    #[serde(default)]
+    display_name: String,
+    mime_type: String,
+    #[serde(default, deserialize_with = "count_from_string")]
+    size_bytes: u64,
+    uri: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiFileList {
+    #[serde(default)]
+    files: Vec<GeminiFile>,
 }
 
 #[derive(Debug, Serialize, Deserialize, Default)]
@@ -38,17 +185,182 @@ struct GeminiGenerationConfig {
     max_output_tokens: Option<u32>,
 }
 
+// With a system instruction, the whole request is counted as `generateContentRequest`.
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCountTokensRequest {
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    contents: Vec<GeminiChatCompletionContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    generate_content_request: Option<GeminiCountedRequest>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiCountedRequest {
+    model: String,
+    system_instruction: GeminiSystemInstruction,
+    contents: Vec<GeminiChatCompletionContent>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiCountTokensResponse {
+    #[serde(rename = "totalTokens")]
+    total_tokens: u32,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiCreateBatchRequest {
+    batch: GeminiBatchConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiBatchConfig {
+    display_name: String,
+    input_config: GeminiBatchInputConfig,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiBatchInputConfig {
+    requests: GeminiBatchRequests,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiBatchRequests {
+    requests: Vec<GeminiBatchItem>,
+}
+
+#[derive(Debug, Serialize)]
+struct GeminiBatchItem {
+    request: GeminiChatCompletionRequest,
+    metadata: GeminiBatchItemMetadata,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct GeminiBatchItemMetadata {
+    key: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiBatchOperation {
+    name: String,
+    metadata: GeminiBatch,
+    response: Option<GeminiBatchOutput>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiBatch {
+    #[serde(default)]
+    model: String,
+    state: String,
+    batch_stats: Option<GeminiBatchStats>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiBatchStats {
+    #[serde(default, deserialize_with = "count_from_string")]
+    request_count: u32,
+    #[serde(default, deserialize_with = "count_from_string")]
+    successful_request_count: u32,
+    #[serde(default, deserialize_with = "count_from_string")]
+    failed_request_count: u32,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiBatchOutput {
+    inlined_responses: GeminiInlinedResponses,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiInlinedResponses {
+    #[serde(default)]
+    inlined_responses: Vec<GeminiInlinedResponse>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiInlinedResponse {
+    response: Option<GeminiChatCompletionResponse>,
+    error: Option<GeminiStatus>,
+    metadata: Option<GeminiBatchItemMetadata>,
+}
+
+#[derive(Debug, Deserialize)]
+struct GeminiStatus {
+    #[serde(default)]
+    message: String,
+}
+
 #[derive(Debug, Deserialize)]
 struct GeminiCandidate {
-    content: GeminiChatCompletionContent,
-    #[serde(skip_serializing_if = "Option::is_none")]
+    // Missing when the candidate was blocked, e.g. for safety.
+    content: Option<GeminiChatCompletionContent>,
+    #[serde(rename = "finishReason")]
     finish_reason: Option<String>,
 }
 
+#[derive(Debug, Serialize)]
+struct ImagenRequest {
+    instances: Vec<ImagenInstance>,
+    parameters: ImagenParameters,
+}
+
+#[derive(Debug, Serialize)]
+struct ImagenInstance {
+    prompt: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+struct ImagenParameters {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_count: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    aspect_ratio: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    sample_image_size: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ImagenResponse {
+    #[serde(default)]
+    predictions: Vec<ImagenPrediction>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct ImagenPrediction {
+    bytes_base64_encoded: Option<String>,
+    mime_type: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiModelList {
+    #[serde(default)]
+    models: Vec<GeminiModel>,
+    next_page_token: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct GeminiModel {
+    name: String,
+    display_name: Option<String>,
+    input_token_limit: Option<u32>,
+    output_token_limit: Option<u32>,
+    #[serde(default)]
+    supported_generation_methods: Vec<String>,
+}
+
 pub struct Gemini {
     domain: String,
     api_key: String,
     client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
 }
 
 impl Gemini {
@@ -57,8 +369,302 @@ impl Gemini {
             domain: "https://generativelanguage.googleapis.com".to_string(),
             api_key,
             client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
         }
     }
+
+    /// Override the API base URL, e.g. for a proxy or a local test server.
+    pub fn with_domain(mut self, domain: String) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
+    // Files go before the text, as Gemini recommends for prompts about a file.
+    // Assistant turns are Gemini's `model` turns, and tool results are sent back as
+    // `user` turns holding a `functionResponse`.
+    fn convert_messages(
+        messages: Vec<ChatMessage>,
+    ) -> Result<Vec<GeminiChatCompletionContent>, LlmApiError> {
+        let mut contents: Vec<GeminiChatCompletionContent> = Vec::with_capacity(messages.len());
+        let mut in_tool_results = false;
+        for (index, msg) in messages.iter().enumerate() {
+            if let Some(id) = &msg.tool_call_id {
+                let name = tool_call_name(&messages[..index], id).unwrap_or_else(|| id.clone());
+                let part = GeminiPart {
+                    function_response: Some(GeminiFunctionResponse {
+                        id: Self::sent_id(id, &name),
+                        name,
+                        response: Self::tool_output(&msg.content),
+                    }),
+                    ..GeminiPart::text(String::new())
+                };
+                // The responses to one model turn share one content, one part per call.
+                match contents.last_mut() {
+                    Some(last) if in_tool_results => last.parts.push(part),
+                    _ => contents.push(GeminiChatCompletionContent {
+                        role: "user".to_string(),
+                        parts: vec![part],
+                    }),
+                }
+                in_tool_results = true;
+                continue;
+            }
+            in_tool_results = false;
+
+            let mut parts = Vec::new();
+            for part in &msg.parts {
+                let ContentPart::File(file) = part;
+                file.check_provider("gemini")?;
+                parts.push(GeminiPart {
+                    file_data: Some(GeminiFileData {
+                        mime_type: file.mime_type.clone(),
+                        file_uri: file.uri.clone().unwrap_or_else(|| file.id.clone()),
+                    }),
+                    ..GeminiPart::text(String::new())
+                });
+            }
+            let tool_calls = msg.tool_calls.as_deref().unwrap_or_default();
+            if (parts.is_empty() && tool_calls.is_empty()) || !msg.content.is_empty() {
+                parts.push(GeminiPart::text(msg.content.clone()));
+            }
+            for call in tool_calls {
+                parts.push(GeminiPart {
+                    function_call: Some(GeminiFunctionCall {
+                        id: Self::sent_id(&call.id, &call.function.name),
+                        name: call.function.name.clone(),
+                        args: call.arguments_value()?,
+                    }),
+                    ..GeminiPart::text(String::new())
+                });
+            }
+            let role = match msg.role.as_str() {
+                "assistant" => "model".to_string(),
+                _ => msg.role.clone(),
+            };
+            contents.push(GeminiChatCompletionContent { role, parts });
+        }
+        Ok(contents)
+    }
+
+    fn system_instruction(
+        messages: &mut Vec<ChatMessage>,
+    ) -> Result<Option<GeminiSystemInstruction>, LlmApiError> {
+        Ok(
+            take_system_prompt("gemini", messages)?.map(|text| GeminiSystemInstruction {
+                parts: vec![GeminiPart::text(text)],
+            }),
+        )
+    }
+
+    // The id to send back for a call to `name`: none for the ids made up by
+    // `convert_tool_calls`, or a bare function name, which Gemini never issued.
+    fn sent_id(id: &str, name: &str) -> Option<String> {
+        let generated = id == name
+            || id
+                .strip_prefix(name)
+                .and_then(|rest| rest.strip_prefix('-'))
+                .is_some_and(|index| index.parse::<usize>().is_ok());
+        (!generated).then(|| id.to_string())
+    }
+
+    // `functionResponse.response` must be an object; other output is wrapped in one.
+    fn tool_output(content: &str) -> serde_json::Value {
+        match serde_json::from_str(content) {
+            Ok(serde_json::Value::Object(object)) => serde_json::Value::Object(object),
+            _ => serde_json::json!({ "content": content }),
+        }
+    }
+
+    fn convert_tools(tools: Option<Vec<Tool>>) -> Option<Vec<GeminiTool>> {
+        let tools = tools.filter(|tools| !tools.is_empty())?;
+        Some(vec![GeminiTool {
+            function_declarations: tools
+                .into_iter()
+                .map(|tool| GeminiFunctionDeclaration {
+                    name: tool.function.name,
+                    description: tool.function.description,
+                    parameters: tool.function.parameters,
+                })
+                .collect(),
+        }])
+    }
+
+    // Gemini only ids function calls in some APIs; without one, `<name>-<index>`
+    // stands in for it, so parallel calls to one function stay distinct.
+    fn convert_tool_calls(parts: &[GeminiPart]) -> Option<Vec<ToolCall>> {
+        let calls: Vec<ToolCall> = parts
+            .iter()
+            .filter_map(|part| part.function_call.as_ref())
+            .enumerate()
+            .map(|(index, call)| ToolCall {
+                id: call
+                    .id
+                    .clone()
+                    .unwrap_or_else(|| format!("{}-{}", call.name, index)),
+                call_type: "function".to_string(),
+                function: FunctionCall {
+                    name: call.name.clone(),
+                    arguments: call.args.to_string(),
+                },
+            })
+            .collect();
+        (!calls.is_empty()).then_some(calls)
+    }
+
+    fn chat_body(
+        request: ChatCompletionRequest,
+    ) -> Result<GeminiChatCompletionRequest, LlmApiError> {
+        let mut messages = request.messages;
+        Ok(GeminiChatCompletionRequest {
+            system_instruction: Self::system_instruction(&mut messages)?,
+            contents: Self::convert_messages(messages)?,
+            tools: Self::convert_tools(request.tools),
+            generation_config: Some(GeminiGenerationConfig {
+                temperature: request.temperature,
+                max_output_tokens: request.max_tokens,
+            }),
+        })
+    }
+
+    fn convert_file(file: GeminiFile) -> FileHandle {
+        FileHandle {
+            provider: "gemini".to_string(),
+            id: file.name,
+            filename: file.display_name,
+            mime_type: file.mime_type,
+            size_bytes: file.size_bytes,
+            uri: Some(file.uri),
+        }
+    }
+
+    // A blocked prompt or candidate has no content and becomes an error.
+    fn convert_response(
+        res: GeminiChatCompletionResponse,
+        model: String,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        if res.candidates.is_empty() {
+            let reason = res
+                .prompt_feedback
+                .and_then(|feedback| feedback.block_reason);
+            return Err(LlmApiError::ProviderError(match reason {
+                Some(reason) => format!("prompt blocked: {}", reason),
+                None => "response has no candidates".to_string(),
+            }));
+        }
+        let choices = res
+            .candidates
+            .into_iter()
+            .map(|candidate| {
+                let finish_reason = candidate.finish_reason.unwrap_or_default();
+                let content = candidate.content.ok_or_else(|| {
+                    LlmApiError::ProviderError(format!(
+                        "candidate has no content (finish reason {})",
+                        finish_reason
+                    ))
+                })?;
+                Ok(ChatChoice {
+                    message: ChatMessage {
+                        content: content
+                            .parts
+                            .iter()
+                            .map(|part| part.text.as_str())
+                            .collect(),
+                        tool_calls: Self::convert_tool_calls(&content.parts),
+                        role: content.role,
+                        ..Default::default()
+                    },
+                    finish_reason,
+                })
+            })
+            .collect::<Result<_, LlmApiError>>()?;
+
+        Ok(ChatCompletionResponse {
+            id: res.response_id.unwrap_or_default(),
+            choices,
+            model,
+            // Gemini counts thinking tokens separately from the candidates.
+            usage: res.usage_metadata.map(|usage| ChatUsage {
+                input_tokens: usage.prompt_token_count,
+                output_tokens: Some(
+                    usage.candidates_token_count.unwrap_or(0)
+                        + usage.thoughts_token_count.unwrap_or(0),
+                ),
+                cached_input_tokens: usage.cached_content_token_count,
+                reasoning_tokens: usage.thoughts_token_count,
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    async fn chat(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        let url = format!(
+            "{}/v1beta/models/{}:generateContent?key={}",
+            self.domain, request.model, self.api_key
+        );
+        let model = request.model.clone();
+        let req = Self::chat_body(request)?;
+
+        let res: GeminiChatCompletionResponse = self.client.send_request(url, vec![], &req).await?;
+
+        Self::convert_response(res, model)
+    }
+
+    fn convert_batch(operation: &GeminiBatchOperation) -> BatchJob {
+        let batch = &operation.metadata;
+        // States are reported as BATCH_STATE_* or JOB_STATE_*.
+        let state = batch.state.rsplit('_').next().unwrap_or_default();
+        let status = match state {
+            "SUCCEEDED" => BatchStatus::Completed,
+            "FAILED" => BatchStatus::Failed,
+            "CANCELLED" => BatchStatus::Cancelled,
+            "EXPIRED" => BatchStatus::Expired,
+            _ => BatchStatus::InProgress,
+        };
+        let stats = batch.batch_stats.as_ref();
+        BatchJob {
+            id: operation.name.clone(),
+            status,
+            counts: BatchCounts {
+                total: stats.map_or(0, |stats| stats.request_count),
+                succeeded: stats.map_or(0, |stats| stats.successful_request_count),
+                failed: stats.map_or(0, |stats| stats.failed_request_count),
+            },
+        }
+    }
+}
+
+// Gemini encodes int64 counts as JSON strings.
+fn count_from_string<'de, D, T>(deserializer: D) -> Result<T, D::Error>
+where
+    D: serde::Deserializer<'de>,
+    T: std::str::FromStr + TryFrom<u64> + Default,
+    <T as std::str::FromStr>::Err: std::fmt::Display,
+{
+    match serde_json::Value::deserialize(deserializer)? {
+        serde_json::Value::String(count) => count.parse().map_err(serde::de::Error::custom),
+        serde_json::Value::Number(count) => Ok(count
+            .as_u64()
+            .and_then(|count| T::try_from(count).ok())
+            .unwrap_or_default()),
+        _ => Ok(T::default()),
+    }
 }
 
 #[async_trait::async_trait]
@@ -67,43 +673,299 @@ impl super::LlmProvider for Gemini {
         &'a self,
         request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.capabilities.validate("gemini", &request)?;
+        ChatSpan::new("gcp.gemini", &request)
+            .instrument(self.chat(request))
+            .await
+    }
+
+    /// Counted by the `countTokens` endpoint.
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
         let url = format!(
-            "{}/v1beta/models/{}:generateContent?key={}",
+            "{}/v1beta/models/{}:countTokens?key={}",
             self.domain, request.model, self.api_key
         );
+        let mut messages = request.messages.clone();
+        let system_instruction = Self::system_instruction(&mut messages)?;
+        let contents = Self::convert_messages(messages)?;
+        let req = match system_instruction {
+            Some(system_instruction) => GeminiCountTokensRequest {
+                contents: Vec::new(),
+                generate_content_request: Some(GeminiCountedRequest {
+                    model: format!("models/{}", request.model),
+                    system_instruction,
+                    contents,
+                }),
+            },
+            None => GeminiCountTokensRequest {
+                contents,
+                generate_content_request: None,
+            },
+        };
+        let res: GeminiCountTokensResponse = self.client.send_request(url, vec![], &req).await?;
+        Ok(TokenCount {
+            input_tokens: res.total_tokens,
+            source: TokenCountSource::Provider,
+        })
+    }
 
-        let req = GeminiChatCompletionRequest {
-            contents: request
-                .messages
-                .into_iter()
-                .map(|msg| GeminiChatCompletionContent {
-                    role: msg.role,
-                    parts: vec![GeminiPart { text: msg.content }],
+    /// Every page of `models.list`. Ids have the `models/` prefix removed.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        let mut models = Vec::new();
+        let mut page_token: Option<String> = None;
+        loop {
+            let mut url = reqwest::Url::parse(&format!("{}/v1beta/models", self.domain))
+                .map_err(|e| LlmApiError::ConfigError(format!("invalid domain: {}", e)))?;
+            url.query_pairs_mut()
+                .append_pair("key", &self.api_key)
+                .append_pair("pageSize", "1000");
+            if let Some(page_token) = &page_token {
+                url.query_pairs_mut().append_pair("pageToken", page_token);
+            }
+            let list: GeminiModelList = self.client.get_request(url.into(), vec![]).await?;
+            models.extend(list.models.into_iter().map(|model| {
+                ModelInfo {
+                    id: model
+                        .name
+                        .strip_prefix("models/")
+                        .unwrap_or(&model.name)
+                        .to_string(),
+                    provider: "gemini".to_string(),
+                    display_name: model.display_name,
+                    owned_by: Some("google".to_string()),
+                    input_token_limit: model.input_token_limit,
+                    output_token_limit: model.output_token_limit,
+                    generation_methods: model.supported_generation_methods,
+                    ..Default::default()
+                }
+            }));
+            match list.next_page_token {
+                Some(token) if !token.is_empty() => page_token = Some(token),
+                _ => return Ok(models),
+            }
+        }
+    }
+}
+
+/// Requests are sent inline, so a batch is limited by Gemini's request size limit.
+/// All requests of a batch must use the same model.
+#[async_trait::async_trait]
+impl BatchProvider for Gemini {
+    async fn create_batch(&self, requests: Vec<BatchRequest>) -> Result<BatchJob, LlmApiError> {
+        let Some(model) = requests.first().map(|r| r.request.model.clone()) else {
+            return Err(LlmApiError::ConfigError(
+                "batch has no requests".to_string(),
+            ));
+        };
+        if requests.iter().any(|r| r.request.model != model) {
+            return Err(LlmApiError::ConfigError(
+                "all requests of a Gemini batch must use the same model".to_string(),
+            ));
+        }
+
+        let url = format!(
+            "{}/v1beta/models/{}:batchGenerateContent?key={}",
+            self.domain, model, self.api_key
+        );
+        let req = GeminiCreateBatchRequest {
+            batch: GeminiBatchConfig {
+                display_name: "llm-api-rs".to_string(),
+                input_config: GeminiBatchInputConfig {
+                    requests: GeminiBatchRequests {
+                        requests: requests
+                            .into_iter()
+                            .map(|request| {
+                                Ok(GeminiBatchItem {
+                                    metadata: GeminiBatchItemMetadata {
+                                        key: request.custom_id,
+                                    },
+                                    request: Self::chat_body(request.request)?,
+                                })
+                            })
+                            .collect::<Result<_, LlmApiError>>()?,
+                    },
+                },
+            },
+        };
+        let operation: GeminiBatchOperation = self.client.send_request(url, vec![], &req).await?;
+        Ok(Self::convert_batch(&operation))
+    }
+
+    /// `id` is the batch name, e.g. `batches/123`.
+    async fn get_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        let url = format!("{}/v1beta/{}?key={}", self.domain, id, self.api_key);
+        let operation: GeminiBatchOperation = self.client.get_request(url, vec![]).await?;
+        Ok(Self::convert_batch(&operation))
+    }
+
+    async fn cancel_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        let url = format!("{}/v1beta/{}:cancel?key={}", self.domain, id, self.api_key);
+        let _: serde_json::Value = self
+            .client
+            .send_request(url, vec![], &serde_json::json!({}))
+            .await?;
+        self.get_batch(id).await
+    }
+
+    async fn batch_results(&self, id: &str) -> Result<Vec<BatchResult>, LlmApiError> {
+        let url = format!("{}/v1beta/{}?key={}", self.domain, id, self.api_key);
+        let operation: GeminiBatchOperation = self.client.get_request(url, vec![]).await?;
+        let model = operation
+            .metadata
+            .model
+            .trim_start_matches("models/")
+            .to_string();
+        let Some(output) = operation.response else {
+            return Ok(Vec::new());
+        };
+
+        Ok(output
+            .inlined_responses
+            .inlined_responses
+            .into_iter()
+            .filter_map(|item| {
+                let custom_id = item.metadata?.key;
+                let response = match (item.response, item.error) {
+                    (_, Some(error)) => Err(LlmApiError::ProviderError(error.message)),
+                    (Some(response), None) => Self::convert_response(response, model.clone()),
+                    (None, None) => Err(LlmApiError::ProviderError(
+                        "response has no candidates".to_string(),
+                    )),
+                };
+                Some(BatchResult {
+                    custom_id,
+                    response,
                 })
-                .collect(),
-            generation_config: Some(GeminiGenerationConfig {
-                temperature: request.temperature,
-                max_output_tokens: request.max_tokens,
-            }),
+            })
+            .collect())
+    }
+}
+
+/// Uploads use Gemini's resumable protocol: one call to start the upload and one to
+/// send the data. Files are deleted by Gemini after 48 hours.
+#[async_trait::async_trait]
+impl FileProvider for Gemini {
+    async fn upload_file(&self, upload: FileUpload) -> Result<FileHandle, LlmApiError> {
+        let url = format!("{}/upload/v1beta/files?key={}", self.domain, self.api_key);
+        let headers = vec![
+            (
+                HeaderName::from_static("x-goog-upload-protocol"),
+                "resumable".to_string(),
+            ),
+            (
+                HeaderName::from_static("x-goog-upload-command"),
+                "start".to_string(),
+            ),
+            (
+                HeaderName::from_static("x-goog-upload-header-content-length"),
+                upload.data.len().to_string(),
+            ),
+            (
+                HeaderName::from_static("x-goog-upload-header-content-type"),
+                upload.mime_type.clone(),
+            ),
+        ];
+        let req = GeminiStartUploadRequest {
+            file: GeminiUploadMetadata {
+                display_name: upload.filename,
+            },
         };
+        let started = self.client.send_request_raw(url, headers, &req).await?;
+        let upload_url = started
+            .headers
+            .get("x-goog-upload-url")
+            .and_then(|url| url.to_str().ok())
+            .ok_or_else(|| {
+                LlmApiError::ProviderError("upload start returned no upload URL".to_string())
+            })?
+            .to_string();
 
-        let res: GeminiChatCompletionResponse = self.client.send_request(url, vec![], &req).await?;
+        let headers = vec![
+            (
+                HeaderName::from_static("x-goog-upload-offset"),
+                "0".to_string(),
+            ),
+            (
+                HeaderName::from_static("x-goog-upload-command"),
+                "upload, finalize".to_string(),
+            ),
+        ];
+        let res: GeminiUploadResponse = self
+            .client
+            .send_bytes(upload_url, headers, &upload.mime_type, upload.data)
+            .await?;
+        Ok(Self::convert_file(res.file))
+    }
 
-        Ok(ChatCompletionResponse {
-            id: res.candidates[0].content.parts[0].text.clone(),
-            choices: res
-                .candidates
-                .into_iter()
-                .map(|candidate| ChatChoice {
-                    message: ChatMessage {
-                        role: candidate.content.role.clone(),
-                        content: candidate.content.parts[0].text.clone(),
-                    },
-                    finish_reason: candidate.finish_reason.unwrap_or_default(),
+    async fn list_files(&self) -> Result<Vec<FileHandle>, LlmApiError> {
+        let url = format!("{}/v1beta/files?key={}", self.domain, self.api_key);
+        let list: GeminiFileList = self.client.get_request(url, vec![]).await?;
+        Ok(list.files.into_iter().map(Self::convert_file).collect())
+    }
+
+    /// `id` is the file name, e.g. `files/abc`.
+    async fn get_file(&self, id: &str) -> Result<FileHandle, LlmApiError> {
+        let url = format!("{}/v1beta/{}?key={}", self.domain, id, self.api_key);
+        let file: GeminiFile = self.client.get_request(url, vec![]).await?;
+        Ok(Self::convert_file(file))
+    }
+
+    async fn delete_file(&self, id: &str) -> Result<(), LlmApiError> {
+        let url = format!("{}/v1beta/{}?key={}", self.domain, id, self.api_key);
+        let _: serde_json::Value = self.client.delete_request(url, vec![]).await?;
+        Ok(())
+    }
+}
+
+/// Images come from Imagen models through `:predict`, always base64-encoded.
+///
+/// `size` is an aspect ratio such as `16:9`, or an image size such as `2K`.
+/// `quality` is ignored.
+#[async_trait::async_trait]
+impl ImageProvider for Gemini {
+    async fn generate_image(&self, request: ImageRequest) -> Result<ImageResponse, LlmApiError> {
+        if request.format == Some(ImageFormat::Url) {
+            return Err(LlmApiError::ConfigError(
+                "Imagen returns images as base64 only".to_string(),
+            ));
+        }
+        let url = format!(
+            "{}/v1beta/models/{}:predict?key={}",
+            self.domain, request.model, self.api_key
+        );
+        let (aspect_ratio, sample_image_size) = match request.size {
+            Some(size) if size.contains(':') => (Some(size), None),
+            size => (None, size),
+        };
+        let req = ImagenRequest {
+            instances: vec![ImagenInstance {
+                prompt: request.prompt,
+            }],
+            parameters: ImagenParameters {
+                sample_count: request.count,
+                aspect_ratio,
+                sample_image_size,
+            },
+        };
+        let res: ImagenResponse = self.client.send_request(url, vec![], &req).await?;
+        // Predictions blocked by safety filters carry no image and are left out.
+        let images = res
+            .predictions
+            .into_iter()
+            .filter_map(|prediction| {
+                Some(GeneratedImage {
+                    data: ImageData::Base64(prediction.bytes_base64_encoded?),
+                    mime_type: prediction.mime_type,
+                    revised_prompt: None,
                 })
-                .collect(),
+            })
+            .collect();
+        Ok(ImageResponse {
             model: request.model,
-            usage: None,
+            images,
         })
     }
 }
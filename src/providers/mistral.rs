@@ -0,0 +1,207 @@
+// Mistral API provider
+// https://docs.mistral.ai/api/#tag/chat
+// https://console.mistral.ai/api-keys
+
+use crate::capabilities::CapabilityCatalog;
+use crate::core::client::APIClient;
+use crate::core::telemetry::ChatSpan;
+use crate::core::{
+    reject_parts, ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage,
+    ChatUsage, FunctionCall, Tool, ToolCall,
+};
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use reqwest::header;
+use serde::{Deserialize, Serialize};
+use std::sync::Arc;
+
+#[derive(Debug, Serialize)]
+struct MistralChatRequest {
+    messages: Vec<MistralMessage>,
+    model: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
+    safe_prompt: bool,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct MistralMessage {
+    role: String,
+    content: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<MistralToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
+}
+
+// Mistral often leaves `type` out of the tool calls it returns, or sets it to null.
+#[derive(Debug, Serialize, Deserialize)]
+struct MistralToolCall {
+    id: String,
+    #[serde(rename = "type", default)]
+    call_type: Option<String>,
+    function: FunctionCall,
+}
+
+#[derive(Debug, Deserialize)]
+struct MistralChatResponse {
+    id: String,
+    model: String,
+    choices: Vec<MistralChoice>,
+    usage: Option<MistralUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct MistralChoice {
+    message: MistralMessage,
+    finish_reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct MistralUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+}
+
+pub struct Mistral {
+    domain: String,
+    api_key: String,
+    safe_prompt: bool,
+    client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
+}
+
+impl Mistral {
+    pub fn new(api_key: String) -> Self {
+        Self {
+            domain: "https://api.mistral.ai".to_string(),
+            api_key,
+            safe_prompt: false,
+            client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
+        }
+    }
+
+    /// Override the API base URL, e.g. for a proxy or a local test server.
+    pub fn with_domain(mut self, domain: String) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
+    /// Prepend Mistral's safety system prompt to every conversation.
+    pub fn with_safe_prompt(mut self, safe_prompt: bool) -> Self {
+        self.safe_prompt = safe_prompt;
+        self
+    }
+
+    fn convert_messages(messages: Vec<ChatMessage>) -> Vec<MistralMessage> {
+        messages
+            .into_iter()
+            .map(|msg| MistralMessage {
+                role: msg.role,
+                content: Some(msg.content),
+                tool_calls: msg.tool_calls.map(|calls| {
+                    calls
+                        .into_iter()
+                        .map(|call| MistralToolCall {
+                            id: call.id,
+                            call_type: Some(call.call_type),
+                            function: call.function,
+                        })
+                        .collect()
+                }),
+                tool_call_id: msg.tool_call_id,
+            })
+            .collect()
+    }
+
+    fn normalize_finish_reason(reason: String) -> String {
+        match reason.as_str() {
+            "model_length" => "length".to_string(),
+            _ => reason,
+        }
+    }
+
+    async fn chat(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        reject_parts("mistral", &request.messages)?;
+        let url = format!("{}/v1/chat/completions", self.domain);
+
+        let req = MistralChatRequest {
+            messages: Self::convert_messages(request.messages),
+            model: request.model,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            tools: request.tools,
+            safe_prompt: self.safe_prompt,
+        };
+        let headers = vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))];
+        let res: MistralChatResponse = self.client.send_request(url, headers, &req).await?;
+        Ok(ChatCompletionResponse {
+            id: res.id,
+            choices: res
+                .choices
+                .into_iter()
+                .map(|choice| ChatChoice {
+                    message: ChatMessage {
+                        role: choice.message.role,
+                        content: choice.message.content.unwrap_or_default(),
+                        tool_calls: choice.message.tool_calls.map(|calls| {
+                            calls
+                                .into_iter()
+                                .map(|call| ToolCall {
+                                    id: call.id,
+                                    call_type: call
+                                        .call_type
+                                        .unwrap_or_else(|| "function".to_string()),
+                                    function: call.function,
+                                })
+                                .collect()
+                        }),
+                        ..Default::default()
+                    },
+                    finish_reason: Self::normalize_finish_reason(choice.finish_reason),
+                })
+                .collect(),
+            model: res.model,
+            usage: res.usage.map(|usage| ChatUsage {
+                input_tokens: Some(usage.prompt_tokens),
+                output_tokens: Some(usage.completion_tokens),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+#[async_trait]
+impl crate::providers::LlmProvider for Mistral {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.capabilities.validate("mistral", &request)?;
+        ChatSpan::new("mistral_ai", &request)
+            .instrument(self.chat(request))
+            .await
+    }
+}
@@ -0,0 +1,85 @@
+// Mock provider for offline tests.
+// Returns scripted responses in order and records every request it receives.
+
+use crate::core::{ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage};
+use crate::error::LlmApiError;
+use async_trait::async_trait;
+use std::collections::VecDeque;
+use std::sync::Mutex;
+
+#[derive(Default)]
+pub struct MockProvider {
+    responses: Mutex<VecDeque<Result<ChatCompletionResponse, LlmApiError>>>,
+    requests: Mutex<Vec<ChatCompletionRequest>>,
+}
+
+impl MockProvider {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Queue a full response.
+    pub fn with_response(self, response: ChatCompletionResponse) -> Self {
+        self.push(Ok(response));
+        self
+    }
+
+    /// Queue a single assistant reply with the given text.
+    pub fn with_reply(self, content: &str) -> Self {
+        self.push(Ok(Self::reply(content)));
+        self
+    }
+
+    /// Queue an error.
+    pub fn with_error(self, error: LlmApiError) -> Self {
+        self.push(Err(error));
+        self
+    }
+
+    /// Queue another result after construction, e.g. from inside a test.
+    pub fn push(&self, result: Result<ChatCompletionResponse, LlmApiError>) {
+        self.responses.lock().unwrap().push_back(result);
+    }
+
+    /// Requests received so far, oldest first.
+    pub fn requests(&self) -> Vec<ChatCompletionRequest> {
+        self.requests.lock().unwrap().clone()
+    }
+
+    fn reply(content: &str) -> ChatCompletionResponse {
+        ChatCompletionResponse {
+            id: "mock".to_string(),
+            model: "mock".to_string(),
+            choices: vec![ChatChoice {
+                message: ChatMessage {
+                    role: "assistant".to_string(),
+                    content: content.to_string(),
+                    ..Default::default()
+                },
+                finish_reason: "stop".to_string(),
+            }],
+            usage: None,
+            ..Default::default()
+        }
+    }
+}
+
+#[async_trait]
+impl crate::providers::LlmProvider for MockProvider {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.requests.lock().unwrap().push(request);
+
+        self.responses
+            .lock()
+            .unwrap()
+            .pop_front()
+            .unwrap_or_else(|| {
+                Err(LlmApiError::ProviderError(
+                    "MockProvider has no scripted response left".to_string(),
+                ))
+            })
+    }
+}
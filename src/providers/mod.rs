@@ -1,11 +1,26 @@
+#[cfg(feature = "anthropic")]
 pub mod anthropic;
+pub mod cache;
+#[cfg(feature = "cohere")]
+pub mod cohere;
+#[cfg(feature = "deepseek")]
 pub mod deepseek;
+#[cfg(feature = "gemini")]
 pub mod gemini;
+#[cfg(feature = "mistral")]
+pub mod mistral;
+pub mod mock;
+pub mod moderated;
+#[cfg(feature = "ollama")]
 pub mod ollama;
+#[cfg(feature = "openai")]
 pub mod openai;
+pub mod router;
+#[cfg(feature = "xai")]
 pub mod xai;
 
-use crate::core::{ChatCompletionRequest, ChatCompletionResponse};
+use crate::core::tokens::TokenCount;
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse, ModelInfo};
 use crate::error::LlmApiError;
 use async_trait::async_trait;
 
@@ -15,10 +30,39 @@ pub trait LlmProvider {
         &self,
         request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, LlmApiError>;
+
+    /// Input tokens `request` would use. Providers with a counting endpoint or a local
+    /// tokenizer override this; the default is an estimate.
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        Ok(TokenCount::estimate(request))
+    }
+
+    /// Models available to this provider's account, e.g. for a model picker.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        Err(LlmApiError::ProviderError(
+            "this provider cannot list its models".to_string(),
+        ))
+    }
 }
 
+#[cfg(feature = "anthropic")]
 pub use anthropic::Anthropic;
+pub use cache::{CacheStore, CachedProvider, DiskCache, MemoryCache};
+#[cfg(feature = "cohere")]
+pub use cohere::Cohere;
+#[cfg(feature = "deepseek")]
 pub use deepseek::DeepSeek;
+#[cfg(feature = "gemini")]
 pub use gemini::Gemini;
+#[cfg(feature = "mistral")]
+pub use mistral::Mistral;
+pub use mock::MockProvider;
+pub use moderated::ModeratedProvider;
+#[cfg(feature = "openai")]
 pub use openai::OpenAI;
+pub use router::{CircuitBreaker, Route, Router, RoutingStrategy};
+#[cfg(feature = "xai")]
 pub use xai::XAI;
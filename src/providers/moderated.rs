@@ -0,0 +1,109 @@
+use crate::core::tokens::TokenCount;
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse, ModelInfo};
+use crate::error::LlmApiError;
+use crate::moderation::{ModerationProvider, ModerationRequest};
+use crate::providers::LlmProvider;
+use async_trait::async_trait;
+
+/// Wraps a provider and runs user input through a moderation provider first.
+///
+/// By default only the last user message is checked, since earlier ones were checked
+/// when they were sent. Flagged requests fail with `LlmApiError::ContentFlagged` and
+/// never reach the inner provider. Only message text is moderated; file parts are not.
+pub struct ModeratedProvider<P, M> {
+    inner: P,
+    moderator: M,
+    model: Option<String>,
+    all_messages: bool,
+}
+
+impl<P: LlmProvider, M: ModerationProvider> ModeratedProvider<P, M> {
+    pub fn new(inner: P, moderator: M) -> Self {
+        Self {
+            inner,
+            moderator,
+            model: None,
+            all_messages: false,
+        }
+    }
+
+    /// Moderation model to use instead of the moderator's default.
+    pub fn with_model(mut self, model: &str) -> Self {
+        self.model = Some(model.to_string());
+        self
+    }
+
+    /// Check every user message, e.g. when the history comes from the client.
+    pub fn with_all_messages(mut self, all_messages: bool) -> Self {
+        self.all_messages = all_messages;
+        self
+    }
+
+    pub fn inner(&self) -> &P {
+        &self.inner
+    }
+
+    fn moderation_request(&self, request: &ChatCompletionRequest) -> ModerationRequest {
+        let mut user_messages = request
+            .messages
+            .iter()
+            .filter(|message| message.role == "user" && !message.content.is_empty());
+        let texts: Vec<&str> = if self.all_messages {
+            user_messages
+                .map(|message| message.content.as_str())
+                .collect()
+        } else {
+            user_messages
+                .next_back()
+                .map(|message| message.content.as_str())
+                .into_iter()
+                .collect()
+        };
+
+        let mut moderation = ModerationRequest::new();
+        moderation.model = self.model.clone();
+        texts
+            .into_iter()
+            .fold(moderation, |moderation, text| moderation.with_text(text))
+    }
+}
+
+#[async_trait]
+impl<P, M> LlmProvider for ModeratedProvider<P, M>
+where
+    P: LlmProvider + Send + Sync,
+    M: ModerationProvider + Send + Sync,
+{
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        let moderation = self.moderation_request(&request);
+        if !moderation.inputs.is_empty() {
+            let response = self.moderator.moderate(moderation).await?;
+            if response.flagged() {
+                let mut categories: Vec<String> = Vec::new();
+                for result in &response.results {
+                    for name in result.flagged_categories() {
+                        if !categories.iter().any(|category| category == name) {
+                            categories.push(name.to_string());
+                        }
+                    }
+                }
+                return Err(LlmApiError::ContentFlagged { categories });
+            }
+        }
+        self.inner.chat_completion(request).await
+    }
+
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        self.inner.count_tokens(request).await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        self.inner.list_models().await
+    }
+}
@@ -1,31 +1,71 @@
 // Ollama API provider
+// https://github.com/ollama/ollama/blob/main/docs/api.md
 
+use crate::capabilities::CapabilityCatalog;
 use crate::core::client::APIClient;
-use crate::core::{ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage};
+use crate::core::telemetry::ChatSpan;
+use crate::core::{
+    reject_parts, tool_call_name, unix_timestamp, ChatChoice, ChatCompletionRequest,
+    ChatCompletionResponse, ChatMessage, FunctionCall, ModelInfo, Tool, ToolCall,
+};
 use crate::error::LlmApiError;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Serialize)]
 struct OllamaChatCompletionRequest {
-    messages: Vec<ChatMessage>,
+    messages: Vec<OllamaMessage>,
     model: String,
     stream: bool,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaMessage {
+    role: String,
+    #[serde(default)]
+    content: String,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    tool_calls: Vec<OllamaToolCall>,
+    // The function a `tool` message answers; Ollama has no tool call ids.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_name: Option<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaToolCall {
+    function: OllamaFunctionCall,
+}
+
+#[derive(Debug, Serialize, Deserialize)]
+struct OllamaFunctionCall {
+    name: String,
+    #[serde(default)]
+    arguments: serde_json::Value,
 }
 
 #[derive(Debug, Deserialize)]
 struct OllamaChatCompletionResponse {
-    message: MessageContent,
+    message: OllamaMessage,
 }
 
 #[derive(Debug, Deserialize)]
-struct MessageContent {
-    role: String,
-    content: String,
+struct OllamaModelList {
+    #[serde(default)]
+    models: Vec<OllamaModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OllamaModel {
+    name: String,
+    modified_at: Option<String>,
 }
 
 pub struct Ollama {
     domain: String,
     client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
 }
 
 impl Ollama {
@@ -33,22 +73,89 @@ impl Ollama {
         Self {
             domain,
             client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
         }
     }
-}
 
-#[async_trait::async_trait]
-impl super::LlmProvider for Ollama {
-    async fn chat_completion<'a>(
-        &'a self,
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
+    // Tool call arguments are sent as JSON objects, and tool results name the
+    // function they answer.
+    fn convert_messages(messages: &[ChatMessage]) -> Result<Vec<OllamaMessage>, LlmApiError> {
+        messages
+            .iter()
+            .enumerate()
+            .map(|(index, msg)| {
+                let tool_calls = msg
+                    .tool_calls
+                    .iter()
+                    .flatten()
+                    .map(|call| {
+                        Ok(OllamaToolCall {
+                            function: OllamaFunctionCall {
+                                name: call.function.name.clone(),
+                                arguments: call.arguments_value()?,
+                            },
+                        })
+                    })
+                    .collect::<Result<_, LlmApiError>>()?;
+                Ok(OllamaMessage {
+                    role: msg.role.clone(),
+                    content: msg.content.clone(),
+                    tool_calls,
+                    tool_name: msg
+                        .tool_call_id
+                        .as_deref()
+                        .and_then(|id| tool_call_name(&messages[..index], id)),
+                })
+            })
+            .collect()
+    }
+
+    // Ollama does not give tool calls ids, so they are numbered within the response.
+    fn convert_tool_calls(calls: Vec<OllamaToolCall>) -> Option<Vec<ToolCall>> {
+        if calls.is_empty() {
+            return None;
+        }
+        Some(
+            calls
+                .into_iter()
+                .enumerate()
+                .map(|(index, call)| ToolCall {
+                    id: format!("call_{}", index),
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: call.function.name,
+                        arguments: call.function.arguments.to_string(),
+                    },
+                })
+                .collect(),
+        )
+    }
+
+    async fn chat(
+        &self,
         request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, LlmApiError> {
+        reject_parts("ollama", &request.messages)?;
         let url = format!("{}/api/chat", self.domain);
 
         let req = OllamaChatCompletionRequest {
-            messages: request.messages,
+            messages: Self::convert_messages(&request.messages)?,
             model: request.model.clone(),
             stream: false,
+            tools: request.tools,
         };
 
         let res: OllamaChatCompletionResponse =
@@ -61,10 +168,42 @@ impl super::LlmProvider for Ollama {
                 message: ChatMessage {
                     role: res.message.role,
                     content: res.message.content,
+                    tool_calls: Self::convert_tool_calls(res.message.tool_calls),
+                    ..Default::default()
                 },
                 finish_reason: "completed".to_string(),
             }],
             usage: None,
+            ..Default::default()
         })
     }
 }
+
+#[async_trait::async_trait]
+impl super::LlmProvider for Ollama {
+    async fn chat_completion<'a>(
+        &'a self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.capabilities.validate("ollama", &request)?;
+        ChatSpan::new("ollama", &request)
+            .instrument(self.chat(request))
+            .await
+    }
+
+    /// Models pulled to the local server, from `/api/tags`.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        let url = format!("{}/api/tags", self.domain);
+        let list: OllamaModelList = self.client.get_request(url, vec![]).await?;
+        Ok(list
+            .models
+            .into_iter()
+            .map(|model| ModelInfo {
+                id: model.name,
+                provider: "ollama".to_string(),
+                created: model.modified_at.as_deref().and_then(unix_timestamp),
+                ..Default::default()
+            })
+            .collect())
+    }
+}
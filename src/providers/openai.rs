@@ -1,22 +1,50 @@
 // src/providers/openai.rs
 // https://platform.openai.com/docs/api-reference/chat/create
+// https://platform.openai.com/docs/api-reference/responses/create
+// https://platform.openai.com/docs/api-reference/batch
+// https://platform.openai.com/docs/api-reference/files
+// https://platform.openai.com/docs/api-reference/images
+// https://platform.openai.com/docs/api-reference/audio
+// https://platform.openai.com/docs/api-reference/moderations
 // https://platform.openai.com
 
-use crate::core::client::APIClient;
+use crate::audio::{
+    ByteStream, SpeechAudio, SpeechProvider, SpeechRequest, TimestampGranularity,
+    TranscriptSegment, Transcription, TranscriptionProvider, TranscriptionRequest,
+};
+use crate::batch::{BatchCounts, BatchJob, BatchProvider, BatchRequest, BatchResult, BatchStatus};
+use crate::capabilities::CapabilityCatalog;
+use crate::core::client::{APIClient, Multipart};
+use crate::core::telemetry::ChatSpan;
+use crate::core::tokens::TokenCount;
 use crate::core::{
-    ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ChatUsage,
+    ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage, ChatUsage, ContentPart,
+    FileHandle, FunctionCall, ModelInfo, Tool, ToolCall,
 };
 use crate::error::LlmApiError;
+use crate::files::{mime_type_for, FileProvider, FileUpload};
+use crate::images::{
+    GeneratedImage, ImageData, ImageFormat, ImageProvider, ImageRequest, ImageResponse,
+};
+use crate::moderation::{
+    ModerationCategories, ModerationInput, ModerationProvider, ModerationRequest,
+    ModerationResponse, ModerationResult,
+};
 use async_trait::async_trait;
 use reqwest::header;
 use serde::{Deserialize, Serialize};
+use std::collections::HashMap;
+use std::sync::Arc;
 
 #[derive(Debug, Serialize)]
 pub struct OpenAIChatCompletionRequest {
     pub model: String,
-    pub messages: Vec<ChatMessage>,
+    /// `ChatMessage`s, with file parts resolved into a content array.
+    pub messages: Vec<serde_json::Value>,
     pub temperature: Option<f32>,
     pub max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub tools: Option<Vec<Tool>>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -24,13 +52,312 @@ pub struct OpenAIChatCompletionResponse {
     pub id: String,
     pub model: String,
     pub choices: Vec<ChatChoice>,
-    pub usage: Option<ChatUsage>,
+    pub usage: Option<OpenAIUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAIUsage {
+    pub prompt_tokens: u32,
+    pub completion_tokens: u32,
+    pub prompt_tokens_details: Option<OpenAITokenDetails>,
+    pub completion_tokens_details: Option<OpenAITokenDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct OpenAITokenDetails {
+    pub cached_tokens: Option<u32>,
+    pub reasoning_tokens: Option<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIFile {
+    id: String,
+    filename: String,
+    bytes: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIFileList {
+    data: Vec<OpenAIFile>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIBatchLine<'a> {
+    custom_id: &'a str,
+    method: &'static str,
+    url: &'static str,
+    body: OpenAIChatCompletionRequest,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAICreateBatchRequest {
+    input_file_id: String,
+    endpoint: &'static str,
+    completion_window: &'static str,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIBatch {
+    id: String,
+    status: String,
+    request_counts: Option<OpenAIBatchCounts>,
+    output_file_id: Option<String>,
+    error_file_id: Option<String>,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct OpenAIBatchCounts {
+    total: u32,
+    completed: u32,
+    failed: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIBatchOutputLine {
+    custom_id: String,
+    response: Option<OpenAIBatchOutputResponse>,
+    error: Option<OpenAIBatchOutputError>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIBatchOutputResponse {
+    status_code: u16,
+    body: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIBatchOutputError {
+    message: String,
+}
+
+/// Per-call settings that only exist on the Responses API.
+#[derive(Debug, Clone, Default)]
+pub struct ResponsesOptions {
+    /// Continue the server-side conversation stored under this response id.
+    pub previous_response_id: Option<String>,
+    /// Whether OpenAI should store the response so it can be chained later.
+    pub store: Option<bool>,
+    /// `minimal`, `low`, `medium` or `high` for reasoning models.
+    pub reasoning_effort: Option<String>,
+    /// `auto`, `concise` or `detailed`; summaries are returned in `ChatMessage::reasoning`.
+    pub reasoning_summary: Option<String>,
+    /// Built-in tools such as `{"type": "web_search_preview"}`, sent as-is.
+    pub builtin_tools: Vec<serde_json::Value>,
+}
+
+#[derive(Debug, Serialize)]
+struct ResponsesRequest {
+    model: String,
+    input: Vec<ResponsesInputItem>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    temperature: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    max_output_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    tools: Vec<serde_json::Value>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    previous_response_id: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    store: Option<bool>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    reasoning: Option<ResponsesReasoning>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ResponsesInputItem {
+    Message {
+        role: String,
+        // A string, or an array of parts when the message has files.
+        content: serde_json::Value,
+    },
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+    FunctionCallOutput {
+        call_id: String,
+        output: String,
+    },
+}
+
+#[derive(Debug, Serialize)]
+struct ResponsesReasoning {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    effort: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    summary: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesResponse {
+    id: String,
+    model: String,
+    status: Option<String>,
+    incomplete_details: Option<ResponsesIncompleteDetails>,
+    output: Vec<ResponsesOutputItem>,
+    usage: Option<ResponsesUsage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesIncompleteDetails {
+    reason: String,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+enum ResponsesOutputItem {
+    Message {
+        role: String,
+        content: Vec<ResponsesOutputContent>,
+    },
+    Reasoning {
+        #[serde(default)]
+        summary: Vec<ResponsesOutputContent>,
+    },
+    FunctionCall {
+        call_id: String,
+        name: String,
+        arguments: String,
+    },
+    #[serde(other)]
+    Other,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesOutputContent {
+    #[serde(rename = "type")]
+    content_type: String,
+    #[serde(default)]
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct ResponsesUsage {
+    input_tokens: u32,
+    output_tokens: u32,
+    input_tokens_details: Option<OpenAITokenDetails>,
+    output_tokens_details: Option<OpenAITokenDetails>,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIImageRequest {
+    model: String,
+    prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    size: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    quality: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<&'static str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIImageResponse {
+    data: Vec<OpenAIImage>,
+    output_format: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIImage {
+    url: Option<String>,
+    b64_json: Option<String>,
+    revised_prompt: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAITranscription {
+    text: String,
+    language: Option<String>,
+    duration: Option<f32>,
+    #[serde(default)]
+    segments: Vec<OpenAITranscriptSegment>,
+    #[serde(default)]
+    words: Vec<OpenAITranscriptWord>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAITranscriptSegment {
+    start: f32,
+    end: f32,
+    text: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAITranscriptWord {
+    start: f32,
+    end: f32,
+    word: String,
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAISpeechRequest {
+    model: String,
+    input: String,
+    voice: String,
+    response_format: &'static str,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    speed: Option<f32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    instructions: Option<String>,
+}
+
+impl From<SpeechRequest> for OpenAISpeechRequest {
+    fn from(request: SpeechRequest) -> Self {
+        Self {
+            model: request.model,
+            input: request.input,
+            voice: request.voice,
+            response_format: request.format.as_str(),
+            speed: request.speed,
+            instructions: request.instructions,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+struct OpenAIModerationRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    model: Option<String>,
+    input: serde_json::Value,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIModerationResponse {
+    id: String,
+    model: String,
+    results: Vec<OpenAIModerationResult>,
+}
+
+// Categories a model does not classify come back as null.
+#[derive(Debug, Deserialize)]
+struct OpenAIModerationResult {
+    flagged: bool,
+    categories: HashMap<String, Option<bool>>,
+    category_scores: HashMap<String, Option<f64>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIModelList {
+    data: Vec<OpenAIModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct OpenAIModel {
+    id: String,
+    created: Option<u64>,
+    owned_by: Option<String>,
 }
 
 pub struct OpenAI {
     domain: String,
     api_key: String,
+    use_responses_api: bool,
     client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
 }
 
 impl OpenAI {
@@ -38,36 +365,756 @@ impl OpenAI {
         OpenAI {
             domain: "https://api.openai.com".to_string(),
             api_key,
+            use_responses_api: false,
             client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
         }
     }
-}
 
-#[async_trait]
-impl crate::providers::LlmProvider for OpenAI {
-    async fn chat_completion(
+    /// Override the API base URL, e.g. for a proxy or a local test server.
+    pub fn with_domain(mut self, domain: String) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
+    /// Route `chat_completion` through `/v1/responses` instead of `/v1/chat/completions`.
+    pub fn with_responses_api(mut self, use_responses_api: bool) -> Self {
+        self.use_responses_api = use_responses_api;
+        self
+    }
+
+    /// Call the Responses API directly, with options that chat completions cannot express.
+    ///
+    /// The returned `id` can be passed back as `previous_response_id` to chain turns
+    /// on the server, in which case `request.messages` only needs the new input.
+    pub async fn create_response(
         &self,
         request: ChatCompletionRequest,
+        options: ResponsesOptions,
     ) -> Result<ChatCompletionResponse, LlmApiError> {
-        let url = format!("{}/v1/chat/completions", self.domain);
+        self.capabilities.validate("openai", &request)?;
+        ChatSpan::new("openai", &request)
+            .instrument(self.send_response(request, options))
+            .await
+    }
+
+    async fn send_response(
+        &self,
+        request: ChatCompletionRequest,
+        options: ResponsesOptions,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        let url = format!("{}/v1/responses", self.domain);
 
-        let headers = vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))];
+        let mut tools: Vec<serde_json::Value> = request
+            .tools
+            .unwrap_or_default()
+            .into_iter()
+            .map(Self::convert_tool)
+            .collect();
+        tools.extend(options.builtin_tools);
+
+        let reasoning = if options.reasoning_effort.is_some() || options.reasoning_summary.is_some()
+        {
+            Some(ResponsesReasoning {
+                effort: options.reasoning_effort,
+                summary: options.reasoning_summary,
+            })
+        } else {
+            None
+        };
 
-        let req = OpenAIChatCompletionRequest {
+        let req = ResponsesRequest {
             model: request.model,
-            messages: request.messages,
+            input: Self::convert_input(request.messages)?,
             temperature: request.temperature,
-            max_tokens: request.max_tokens,
+            max_output_tokens: request.max_tokens,
+            tools,
+            previous_response_id: options.previous_response_id,
+            store: options.store,
+            reasoning,
         };
 
-        let res: OpenAIChatCompletionResponse =
-            self.client.send_request(url, headers, &req).await?;
+        let res: ResponsesResponse = self.client.send_request(url, self.headers(), &req).await?;
+
+        let mut content = String::new();
+        let mut role = "assistant".to_string();
+        let mut summaries = Vec::new();
+        let mut tool_calls = Vec::new();
+        for item in res.output {
+            match item {
+                ResponsesOutputItem::Message {
+                    role: item_role,
+                    content: parts,
+                } => {
+                    role = item_role;
+                    for part in parts {
+                        if part.content_type == "output_text" {
+                            content.push_str(&part.text);
+                        }
+                    }
+                }
+                ResponsesOutputItem::Reasoning { summary } => {
+                    summaries.extend(summary.into_iter().map(|part| part.text));
+                }
+                ResponsesOutputItem::FunctionCall {
+                    call_id,
+                    name,
+                    arguments,
+                } => tool_calls.push(ToolCall {
+                    id: call_id,
+                    call_type: "function".to_string(),
+                    function: FunctionCall { name, arguments },
+                }),
+                ResponsesOutputItem::Other => {}
+            }
+        }
+
+        let finish_reason = match (res.status.as_deref(), res.incomplete_details) {
+            (Some("incomplete"), Some(details)) if details.reason == "max_output_tokens" => {
+                "length".to_string()
+            }
+            (Some("incomplete"), Some(details)) => details.reason,
+            _ if !tool_calls.is_empty() => "tool_calls".to_string(),
+            _ => "stop".to_string(),
+        };
 
         Ok(ChatCompletionResponse {
+            id: res.id,
+            model: res.model,
+            choices: vec![ChatChoice {
+                message: ChatMessage {
+                    role,
+                    content,
+                    tool_calls: if tool_calls.is_empty() {
+                        None
+                    } else {
+                        Some(tool_calls)
+                    },
+                    reasoning: if summaries.is_empty() {
+                        None
+                    } else {
+                        Some(summaries.join("\n\n"))
+                    },
+                    ..Default::default()
+                },
+                finish_reason,
+            }],
+            usage: res.usage.map(|usage| ChatUsage {
+                input_tokens: Some(usage.input_tokens),
+                output_tokens: Some(usage.output_tokens),
+                cached_input_tokens: usage.input_tokens_details.and_then(|d| d.cached_tokens),
+                reasoning_tokens: usage.output_tokens_details.and_then(|d| d.reasoning_tokens),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+
+    fn convert_input(messages: Vec<ChatMessage>) -> Result<Vec<ResponsesInputItem>, LlmApiError> {
+        let mut input = Vec::new();
+        for msg in messages {
+            if let Some(call_id) = msg.tool_call_id {
+                input.push(ResponsesInputItem::FunctionCallOutput {
+                    call_id,
+                    output: msg.content,
+                });
+                continue;
+            }
+            if !msg.parts.is_empty() {
+                let mut content = Vec::new();
+                if !msg.content.is_empty() {
+                    content.push(serde_json::json!({"type": "input_text", "text": msg.content}));
+                }
+                for part in msg.parts {
+                    let ContentPart::File(file) = part;
+                    file.check_provider("openai")?;
+                    let part_type = if file.mime_type.starts_with("image/") {
+                        "input_image"
+                    } else {
+                        "input_file"
+                    };
+                    content.push(serde_json::json!({"type": part_type, "file_id": file.id}));
+                }
+                input.push(ResponsesInputItem::Message {
+                    role: msg.role,
+                    content: serde_json::Value::Array(content),
+                });
+            } else if !msg.content.is_empty() {
+                input.push(ResponsesInputItem::Message {
+                    role: msg.role,
+                    content: serde_json::Value::String(msg.content),
+                });
+            }
+            for call in msg.tool_calls.unwrap_or_default() {
+                input.push(ResponsesInputItem::FunctionCall {
+                    call_id: call.id,
+                    name: call.function.name,
+                    arguments: call.function.arguments,
+                });
+            }
+        }
+        Ok(input)
+    }
+
+    // The Responses API flattens function tools: no nested `function` object.
+    fn convert_tool(tool: Tool) -> serde_json::Value {
+        let mut value = serde_json::json!({
+            "type": tool.tool_type,
+            "name": tool.function.name,
+            "parameters": tool.function.parameters,
+        });
+        if let Some(description) = tool.function.description {
+            value["description"] = serde_json::Value::String(description);
+        }
+        value
+    }
+
+    fn headers(&self) -> Vec<(header::HeaderName, String)> {
+        vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))]
+    }
+
+    fn chat_body(
+        request: ChatCompletionRequest,
+    ) -> Result<OpenAIChatCompletionRequest, LlmApiError> {
+        Ok(OpenAIChatCompletionRequest {
+            model: request.model,
+            messages: Self::convert_messages(request.messages)?,
+            temperature: request.temperature,
+            max_tokens: request.max_tokens,
+            tools: request.tools,
+        })
+    }
+
+    // Chat completions only take files as `file` parts, which OpenAI supports for PDFs.
+    // Messages are built field by field so that `reasoning` is never sent back.
+    fn convert_messages(messages: Vec<ChatMessage>) -> Result<Vec<serde_json::Value>, LlmApiError> {
+        messages
+            .into_iter()
+            .map(|msg| {
+                let mut value = serde_json::json!({"role": msg.role});
+                if msg.parts.is_empty() {
+                    value["content"] = serde_json::Value::String(msg.content);
+                } else {
+                    let mut content = Vec::new();
+                    if !msg.content.is_empty() {
+                        content.push(serde_json::json!({"type": "text", "text": msg.content}));
+                    }
+                    for part in msg.parts {
+                        let ContentPart::File(file) = part;
+                        file.check_provider("openai")?;
+                        content.push(
+                            serde_json::json!({"type": "file", "file": {"file_id": file.id}}),
+                        );
+                    }
+                    value["content"] = serde_json::Value::Array(content);
+                }
+                if let Some(tool_calls) = msg.tool_calls {
+                    value["tool_calls"] = serde_json::to_value(tool_calls)
+                        .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+                }
+                if let Some(tool_call_id) = msg.tool_call_id {
+                    value["tool_call_id"] = serde_json::Value::String(tool_call_id);
+                }
+                Ok(value)
+            })
+            .collect()
+    }
+
+    fn convert_chat_response(res: OpenAIChatCompletionResponse) -> ChatCompletionResponse {
+        ChatCompletionResponse {
             id: res.id,
             choices: res.choices,
             model: res.model,
-            usage: res.usage,
+            usage: res.usage.map(|usage| ChatUsage {
+                input_tokens: Some(usage.prompt_tokens),
+                output_tokens: Some(usage.completion_tokens),
+                cached_input_tokens: usage.prompt_tokens_details.and_then(|d| d.cached_tokens),
+                reasoning_tokens: usage
+                    .completion_tokens_details
+                    .and_then(|d| d.reasoning_tokens),
+                ..Default::default()
+            }),
+            ..Default::default()
+        }
+    }
+
+    async fn chat(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        let url = format!("{}/v1/chat/completions", self.domain);
+        let req = Self::chat_body(request)?;
+
+        let res: OpenAIChatCompletionResponse =
+            self.client.send_request(url, self.headers(), &req).await?;
+
+        Ok(Self::convert_chat_response(res))
+    }
+
+    // OpenAI does not report a MIME type, so it is guessed from the file name.
+    // gpt-image models reject `response_format` and always return base64, so it is
+    // only sent when asked for.
+    fn response_format(format: Option<ImageFormat>) -> Option<&'static str> {
+        format.map(|format| match format {
+            ImageFormat::Url => "url",
+            ImageFormat::Base64 => "b64_json",
+        })
+    }
+
+    fn convert_images(
+        model: String,
+        res: OpenAIImageResponse,
+    ) -> Result<ImageResponse, LlmApiError> {
+        let mime_type = format!("image/{}", res.output_format.as_deref().unwrap_or("png"));
+        let images = res
+            .data
+            .into_iter()
+            .map(|image| {
+                let (data, mime_type) = match (image.b64_json, image.url) {
+                    (Some(data), _) => (ImageData::Base64(data), Some(mime_type.clone())),
+                    (None, Some(url)) => (ImageData::Url(url), None),
+                    (None, None) => {
+                        return Err(LlmApiError::ProviderError(
+                            "image has neither url nor b64_json".to_string(),
+                        ))
+                    }
+                };
+                Ok(GeneratedImage {
+                    data,
+                    mime_type,
+                    revised_prompt: image.revised_prompt,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ImageResponse { model, images })
+    }
+
+    /// Edit `image` as `request.prompt` describes, through `/v1/images/edits`.
+    ///
+    /// Only the transparent areas of `mask`, a PNG the size of `image`, are changed;
+    /// without a mask the model decides what to change.
+    pub async fn edit_image(
+        &self,
+        request: ImageRequest,
+        image: FileUpload,
+        mask: Option<FileUpload>,
+    ) -> Result<ImageResponse, LlmApiError> {
+        let url = format!("{}/v1/images/edits", self.domain);
+        let mut form = Multipart::new()
+            .text("model", &request.model)
+            .text("prompt", &request.prompt);
+        if let Some(count) = request.count {
+            form = form.text("n", &count.to_string());
+        }
+        if let Some(size) = &request.size {
+            form = form.text("size", size);
+        }
+        if let Some(quality) = &request.quality {
+            form = form.text("quality", quality);
+        }
+        if let Some(format) = Self::response_format(request.format) {
+            form = form.text("response_format", format);
+        }
+        form = form.file("image", &image.filename, &image.mime_type, image.data);
+        if let Some(mask) = mask {
+            form = form.file("mask", &mask.filename, &mask.mime_type, mask.data);
+        }
+        let res: OpenAIImageResponse = self
+            .client
+            .send_multipart(url, self.headers(), form)
+            .await?;
+        Self::convert_images(request.model, res)
+    }
+
+    fn convert_file(file: OpenAIFile) -> FileHandle {
+        FileHandle {
+            provider: "openai".to_string(),
+            mime_type: mime_type_for(&file.filename).to_string(),
+            id: file.id,
+            filename: file.filename,
+            size_bytes: file.bytes,
+            uri: None,
+        }
+    }
+
+    fn batch_status(status: &str) -> BatchStatus {
+        match status {
+            "completed" => BatchStatus::Completed,
+            "failed" => BatchStatus::Failed,
+            "expired" => BatchStatus::Expired,
+            "cancelled" => BatchStatus::Cancelled,
+            // validating, in_progress, finalizing and cancelling
+            _ => BatchStatus::InProgress,
+        }
+    }
+
+    fn convert_batch(batch: OpenAIBatch) -> BatchJob {
+        let counts = batch.request_counts.unwrap_or_default();
+        BatchJob {
+            status: Self::batch_status(&batch.status),
+            id: batch.id,
+            counts: BatchCounts {
+                total: counts.total,
+                succeeded: counts.completed,
+                failed: counts.failed,
+            },
+        }
+    }
+
+    fn convert_batch_line(line: &str) -> Result<BatchResult, LlmApiError> {
+        let line: OpenAIBatchOutputLine = serde_json::from_str(line)
+            .map_err(|e| LlmApiError::DeserializationError(e.to_string()))?;
+        let response = match (line.response, line.error) {
+            (_, Some(error)) => Err(LlmApiError::ProviderError(error.message)),
+            (Some(response), None) if response.status_code == 200 => {
+                serde_json::from_value(response.body)
+                    .map(Self::convert_chat_response)
+                    .map_err(|e| LlmApiError::DeserializationError(e.to_string()))
+            }
+            (Some(response), None) => Err(LlmApiError::from_response(
+                response.status_code,
+                response.body.to_string(),
+            )),
+            (None, None) => Err(LlmApiError::ProviderError(
+                "batch line has neither response nor error".to_string(),
+            )),
+        };
+        Ok(BatchResult {
+            custom_id: line.custom_id,
+            response,
+        })
+    }
+}
+
+#[async_trait]
+impl crate::providers::LlmProvider for OpenAI {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.capabilities.validate("openai", &request)?;
+        if self.use_responses_api {
+            return self
+                .create_response(request, ResponsesOptions::default())
+                .await;
+        }
+
+        ChatSpan::new("openai", &request)
+            .instrument(self.chat(request))
+            .await
+    }
+
+    /// Counted locally with the model's tokenizer when the `tiktoken` feature is
+    /// enabled, estimated otherwise.
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        #[cfg(feature = "tiktoken")]
+        if let Some(count) = crate::core::tokens::count_openai_tokens(request) {
+            return Ok(count);
+        }
+        Ok(TokenCount::estimate(request))
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        let url = format!("{}/v1/models", self.domain);
+        let list: OpenAIModelList = self.client.get_request(url, self.headers()).await?;
+        Ok(list
+            .data
+            .into_iter()
+            .map(|model| ModelInfo {
+                id: model.id,
+                provider: "openai".to_string(),
+                owned_by: model.owned_by,
+                created: model.created,
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+/// Batches go through a JSONL file uploaded to the Files API, against
+/// `/v1/chat/completions` with a 24 hour completion window.
+#[async_trait]
+impl BatchProvider for OpenAI {
+    async fn create_batch(&self, requests: Vec<BatchRequest>) -> Result<BatchJob, LlmApiError> {
+        let mut jsonl = Vec::new();
+        for request in requests {
+            let line = OpenAIBatchLine {
+                custom_id: &request.custom_id,
+                method: "POST",
+                url: "/v1/chat/completions",
+                body: Self::chat_body(request.request)?,
+            };
+            serde_json::to_writer(&mut jsonl, &line)
+                .map_err(|e| LlmApiError::SerializationError(e.to_string()))?;
+            jsonl.push(b'\n');
+        }
+        let upload =
+            FileUpload::new("batch.jsonl", "application/jsonl", jsonl).with_purpose("batch");
+        let file = self.upload_file(upload).await?;
+
+        let url = format!("{}/v1/batches", self.domain);
+        let req = OpenAICreateBatchRequest {
+            input_file_id: file.id,
+            endpoint: "/v1/chat/completions",
+            completion_window: "24h",
+        };
+        let batch: OpenAIBatch = self.client.send_request(url, self.headers(), &req).await?;
+        Ok(Self::convert_batch(batch))
+    }
+
+    async fn get_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        let url = format!("{}/v1/batches/{}", self.domain, id);
+        let batch: OpenAIBatch = self.client.get_request(url, self.headers()).await?;
+        Ok(Self::convert_batch(batch))
+    }
+
+    async fn cancel_batch(&self, id: &str) -> Result<BatchJob, LlmApiError> {
+        let url = format!("{}/v1/batches/{}/cancel", self.domain, id);
+        let batch: OpenAIBatch = self
+            .client
+            .send_request(url, self.headers(), &serde_json::json!({}))
+            .await?;
+        Ok(Self::convert_batch(batch))
+    }
+
+    /// Read from the batch's output file and, for failed requests, its error file.
+    async fn batch_results(&self, id: &str) -> Result<Vec<BatchResult>, LlmApiError> {
+        let url = format!("{}/v1/batches/{}", self.domain, id);
+        let batch: OpenAIBatch = self.client.get_request(url, self.headers()).await?;
+        // The result files can still be missing or partial before then.
+        if !Self::batch_status(&batch.status).is_done() {
+            return Err(LlmApiError::ProviderError(format!(
+                "batch {} has not ended",
+                id
+            )));
+        }
+
+        let mut results = Vec::new();
+        for file_id in [batch.output_file_id, batch.error_file_id]
+            .into_iter()
+            .flatten()
+        {
+            let url = format!("{}/v1/files/{}/content", self.domain, file_id);
+            let content = self.client.get_text(url, self.headers()).await?;
+            for line in content.lines().filter(|line| !line.trim().is_empty()) {
+                results.push(Self::convert_batch_line(line)?);
+            }
+        }
+        Ok(results)
+    }
+}
+
+#[async_trait]
+impl FileProvider for OpenAI {
+    async fn upload_file(&self, upload: FileUpload) -> Result<FileHandle, LlmApiError> {
+        let url = format!("{}/v1/files", self.domain);
+        let purpose = upload.purpose.as_deref().unwrap_or("user_data");
+        let form = Multipart::new().text("purpose", purpose).file(
+            "file",
+            &upload.filename,
+            &upload.mime_type,
+            upload.data,
+        );
+        let file: OpenAIFile = self
+            .client
+            .send_multipart(url, self.headers(), form)
+            .await?;
+        Ok(Self::convert_file(file))
+    }
+
+    async fn list_files(&self) -> Result<Vec<FileHandle>, LlmApiError> {
+        let url = format!("{}/v1/files", self.domain);
+        let list: OpenAIFileList = self.client.get_request(url, self.headers()).await?;
+        Ok(list.data.into_iter().map(Self::convert_file).collect())
+    }
+
+    async fn get_file(&self, id: &str) -> Result<FileHandle, LlmApiError> {
+        let url = format!("{}/v1/files/{}", self.domain, id);
+        let file: OpenAIFile = self.client.get_request(url, self.headers()).await?;
+        Ok(Self::convert_file(file))
+    }
+
+    async fn delete_file(&self, id: &str) -> Result<(), LlmApiError> {
+        let url = format!("{}/v1/files/{}", self.domain, id);
+        let _: serde_json::Value = self.client.delete_request(url, self.headers()).await?;
+        Ok(())
+    }
+}
+
+#[async_trait]
+impl ImageProvider for OpenAI {
+    async fn generate_image(&self, request: ImageRequest) -> Result<ImageResponse, LlmApiError> {
+        let url = format!("{}/v1/images/generations", self.domain);
+        let req = OpenAIImageRequest {
+            model: request.model.clone(),
+            prompt: request.prompt,
+            n: request.count,
+            size: request.size,
+            quality: request.quality,
+            response_format: Self::response_format(request.format),
+        };
+        let res: OpenAIImageResponse = self.client.send_request(url, self.headers(), &req).await?;
+        Self::convert_images(request.model, res)
+    }
+}
+
+#[async_trait]
+impl TranscriptionProvider for OpenAI {
+    /// Timestamps need `verbose_json`, which only `whisper-1` supports.
+    async fn transcribe(
+        &self,
+        request: TranscriptionRequest,
+    ) -> Result<Transcription, LlmApiError> {
+        let url = format!("{}/v1/audio/transcriptions", self.domain);
+        let mut form = Multipart::new().text("model", &request.model);
+        if let Some(language) = &request.language {
+            form = form.text("language", language);
+        }
+        if let Some(prompt) = &request.prompt {
+            form = form.text("prompt", prompt);
+        }
+        if let Some(temperature) = request.temperature {
+            form = form.text("temperature", &temperature.to_string());
+        }
+        if request.timestamps.is_empty() {
+            form = form.text("response_format", "json");
+        } else {
+            form = form.text("response_format", "verbose_json");
+            for granularity in &request.timestamps {
+                let granularity = match granularity {
+                    TimestampGranularity::Segment => "segment",
+                    TimestampGranularity::Word => "word",
+                };
+                form = form.text("timestamp_granularities[]", granularity);
+            }
+        }
+        let audio = request.audio;
+        form = form.file("file", &audio.filename, &audio.mime_type, audio.data);
+
+        let res: OpenAITranscription = self
+            .client
+            .send_multipart(url, self.headers(), form)
+            .await?;
+        Ok(Transcription {
+            text: res.text,
+            language: res.language,
+            duration: res.duration,
+            segments: res
+                .segments
+                .into_iter()
+                .map(|segment| TranscriptSegment {
+                    start: segment.start,
+                    end: segment.end,
+                    text: segment.text,
+                })
+                .collect(),
+            words: res
+                .words
+                .into_iter()
+                .map(|word| TranscriptSegment {
+                    start: word.start,
+                    end: word.end,
+                    text: word.word,
+                })
+                .collect(),
+        })
+    }
+}
+
+#[async_trait]
+impl SpeechProvider for OpenAI {
+    async fn speech(&self, request: SpeechRequest) -> Result<SpeechAudio, LlmApiError> {
+        let url = format!("{}/v1/audio/speech", self.domain);
+        let format = request.format;
+        let req = OpenAISpeechRequest::from(request);
+        let data = self
+            .client
+            .send_request_bytes(url, self.headers(), &req)
+            .await?;
+        Ok(SpeechAudio { format, data })
+    }
+
+    async fn speech_stream(&self, request: SpeechRequest) -> Result<ByteStream, LlmApiError> {
+        let url = format!("{}/v1/audio/speech", self.domain);
+        let req = OpenAISpeechRequest::from(request);
+        self.client
+            .send_request_stream(url, self.headers(), &req)
+            .await
+    }
+}
+
+#[async_trait]
+impl ModerationProvider for OpenAI {
+    /// Text-only requests are sent as a list of strings, which gets one result per
+    /// string. Requests with images are sent as one multi-modal input.
+    async fn moderate(
+        &self,
+        request: ModerationRequest,
+    ) -> Result<ModerationResponse, LlmApiError> {
+        let url = format!("{}/v1/moderations", self.domain);
+        let has_images = request
+            .inputs
+            .iter()
+            .any(|input| matches!(input, ModerationInput::ImageUrl(_)));
+        let input = request
+            .inputs
+            .into_iter()
+            .map(|input| match input {
+                ModerationInput::Text(text) if !has_images => serde_json::json!(text),
+                ModerationInput::Text(text) => serde_json::json!({"type": "text", "text": text}),
+                ModerationInput::ImageUrl(url) => {
+                    serde_json::json!({"type": "image_url", "image_url": {"url": url}})
+                }
+            })
+            .collect();
+        let req = OpenAIModerationRequest {
+            model: request.model,
+            input: serde_json::Value::Array(input),
+        };
+        let res: OpenAIModerationResponse =
+            self.client.send_request(url, self.headers(), &req).await?;
+        Ok(ModerationResponse {
+            id: res.id,
+            model: res.model,
+            results: res
+                .results
+                .into_iter()
+                .map(|result| ModerationResult {
+                    flagged: result.flagged,
+                    categories: ModerationCategories::from_fn(|name| {
+                        result
+                            .categories
+                            .get(name)
+                            .copied()
+                            .flatten()
+                            .unwrap_or(false)
+                    }),
+                    scores: ModerationCategories::from_fn(|name| {
+                        result
+                            .category_scores
+                            .get(name)
+                            .copied()
+                            .flatten()
+                            .unwrap_or(0.0)
+                    }),
+                })
+                .collect(),
         })
     }
 }
@@ -0,0 +1,345 @@
+// Router over several providers, itself usable as a provider.
+// Picks a route per call, falls back to the others on retryable errors and takes
+// routes that keep failing out of rotation for a while.
+
+use crate::core::runtime::Instant;
+use crate::core::tokens::TokenCount;
+use crate::core::{ChatCompletionRequest, ChatCompletionResponse, ModelInfo};
+use crate::error::LlmApiError;
+use crate::providers::LlmProvider;
+use async_trait::async_trait;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// How the router orders routes for each call. Whatever route comes first, the
+/// others are tried after it on retryable errors.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum RoutingStrategy {
+    /// Always try routes in the order they were added.
+    #[default]
+    Fallback,
+    /// Start at each route in proportion to its weight.
+    WeightedRoundRobin,
+    /// Start at the route with the lowest average latency. Routes without a
+    /// measurement yet are tried first.
+    LeastLatency,
+}
+
+/// A provider together with the model to request from it.
+pub struct Route {
+    name: String,
+    provider: Arc<dyn LlmProvider + Send + Sync>,
+    model: String,
+    weight: u32,
+}
+
+impl Route {
+    pub fn new(name: &str, provider: Arc<dyn LlmProvider + Send + Sync>, model: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            provider,
+            model: model.to_string(),
+            weight: 1,
+        }
+    }
+
+    /// Relative share of calls under `RoutingStrategy::WeightedRoundRobin`. Defaults to 1.
+    pub fn with_weight(mut self, weight: u32) -> Self {
+        self.weight = weight;
+        self
+    }
+}
+
+/// Open a route's circuit after `failure_threshold` consecutive retryable errors and
+/// skip it for `cooldown`. After the cooldown one call is let through to probe it; if
+/// the probe fails, the circuit opens for another `cooldown`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CircuitBreaker {
+    pub failure_threshold: u32,
+    pub cooldown: Duration,
+}
+
+impl Default for CircuitBreaker {
+    fn default() -> Self {
+        Self {
+            failure_threshold: 5,
+            cooldown: Duration::from_secs(30),
+        }
+    }
+}
+
+#[derive(Default)]
+struct RouteHealth {
+    consecutive_failures: u32,
+    open_until: Option<Instant>,
+    // Set while a call probes a half-open circuit, so other calls keep away. It
+    // expires after a cooldown in case the probing call is dropped.
+    probing_until: Option<Instant>,
+    // Exponentially weighted moving average of successful calls.
+    latency: Option<Duration>,
+}
+
+impl RouteHealth {
+    // Closed, or half-open with no probe under way.
+    fn admits(&self, now: Instant) -> bool {
+        match self.open_until {
+            Some(until) if until > now => false,
+            Some(_) => self.probing_until.is_none_or(|until| until <= now),
+            None => true,
+        }
+    }
+
+    fn is_closed(&self) -> bool {
+        self.open_until.is_none()
+    }
+}
+
+/// Sends each call to one of several routes.
+///
+/// The `model` of incoming requests is replaced by the model of the route that
+/// serves it. Errors that are not retryable, such as invalid requests, are returned
+/// right away; otherwise the last route's error is returned once all have failed.
+pub struct Router {
+    routes: Vec<Route>,
+    strategy: RoutingStrategy,
+    circuit_breaker: Option<CircuitBreaker>,
+    health: Mutex<Vec<RouteHealth>>,
+    // Position in the weighted round-robin cycle.
+    turn: Mutex<u64>,
+}
+
+impl Default for Router {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Router {
+    pub fn new() -> Self {
+        Self {
+            routes: Vec::new(),
+            strategy: RoutingStrategy::default(),
+            circuit_breaker: None,
+            health: Mutex::new(Vec::new()),
+            turn: Mutex::new(0),
+        }
+    }
+
+    pub fn with_route(mut self, route: Route) -> Self {
+        self.routes.push(route);
+        self.health.get_mut().unwrap().push(RouteHealth::default());
+        self
+    }
+
+    pub fn with_strategy(mut self, strategy: RoutingStrategy) -> Self {
+        self.strategy = strategy;
+        self
+    }
+
+    pub fn with_circuit_breaker(mut self, circuit_breaker: CircuitBreaker) -> Self {
+        self.circuit_breaker = Some(circuit_breaker);
+        self
+    }
+
+    /// Names of the routes whose circuit is currently open.
+    pub fn unavailable_routes(&self) -> Vec<String> {
+        let now = Instant::now();
+        let health = self.health.lock().unwrap();
+        self.routes
+            .iter()
+            .zip(health.iter())
+            .filter(|(_, health)| health.open_until.is_some_and(|until| until > now))
+            .map(|(route, _)| route.name.clone())
+            .collect()
+    }
+
+    // Routes whose circuit is closed, in the order they were added.
+    fn closed_routes(&self) -> Vec<usize> {
+        let health = self.health.lock().unwrap();
+        (0..self.routes.len())
+            .filter(|&i| health[i].is_closed())
+            .collect()
+    }
+
+    fn no_route_error(&self) -> LlmApiError {
+        LlmApiError::ProviderError(if self.routes.is_empty() {
+            "Router has no routes".to_string()
+        } else {
+            "all routes are unavailable".to_string()
+        })
+    }
+
+    /// Route indices in the order to try them for the next call.
+    fn candidates(&self) -> Vec<usize> {
+        let mut order: Vec<usize> = (0..self.routes.len()).collect();
+        match self.strategy {
+            RoutingStrategy::Fallback => {}
+            RoutingStrategy::WeightedRoundRobin => {
+                let total: u64 = self.routes.iter().map(|r| r.weight as u64).sum();
+                if total > 0 {
+                    let mut turn = self.turn.lock().unwrap();
+                    let mut slot = *turn % total;
+                    *turn += 1;
+                    let first = self
+                        .routes
+                        .iter()
+                        .position(|route| {
+                            let weight = route.weight as u64;
+                            if slot < weight {
+                                true
+                            } else {
+                                slot -= weight;
+                                false
+                            }
+                        })
+                        .unwrap_or(0);
+                    order.rotate_left(first);
+                }
+            }
+            RoutingStrategy::LeastLatency => {
+                let health = self.health.lock().unwrap();
+                order.sort_by_key(|&i| health[i].latency);
+            }
+        }
+
+        let now = Instant::now();
+        let health = self.health.lock().unwrap();
+        order.retain(|&i| health[i].admits(now));
+        order
+    }
+
+    /// Claim route `index` for a call. A half-open route admits one probing call
+    /// at a time.
+    fn try_route(&self, index: usize) -> bool {
+        let now = Instant::now();
+        let mut health = self.health.lock().unwrap();
+        let health = &mut health[index];
+        if !health.admits(now) {
+            return false;
+        }
+        if health.open_until.is_some() {
+            if let Some(breaker) = self.circuit_breaker {
+                health.probing_until = Some(now + breaker.cooldown);
+            }
+        }
+        true
+    }
+
+    /// The route answered. `elapsed` is only given for successful calls, since
+    /// rejected requests say little about latency.
+    fn record_answer(&self, index: usize, elapsed: Option<Duration>) {
+        let mut health = self.health.lock().unwrap();
+        let health = &mut health[index];
+        health.consecutive_failures = 0;
+        health.open_until = None;
+        health.probing_until = None;
+        if let Some(elapsed) = elapsed {
+            health.latency = Some(match health.latency {
+                Some(average) => (average * 4 + elapsed) / 5,
+                None => elapsed,
+            });
+        }
+    }
+
+    fn record_failure(&self, index: usize) {
+        let Some(breaker) = self.circuit_breaker else {
+            return;
+        };
+        let mut health = self.health.lock().unwrap();
+        let health = &mut health[index];
+        health.consecutive_failures += 1;
+        health.probing_until = None;
+        if health.consecutive_failures >= breaker.failure_threshold {
+            health.open_until = Some(Instant::now() + breaker.cooldown);
+        }
+    }
+}
+
+#[async_trait]
+impl LlmProvider for Router {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        let candidates = self.candidates();
+        if candidates.is_empty() {
+            return Err(self.no_route_error());
+        }
+
+        let mut last_error = None;
+        for index in candidates {
+            if !self.try_route(index) {
+                continue;
+            }
+            let route = &self.routes[index];
+            let mut request = request.clone();
+            request.model = route.model.clone();
+
+            let started = Instant::now();
+            match route.provider.chat_completion(request).await {
+                Ok(response) => {
+                    self.record_answer(index, Some(started.elapsed()));
+                    return Ok(response);
+                }
+                Err(error) if error.is_retryable() => {
+                    self.record_failure(index);
+                    last_error = Some(error);
+                }
+                Err(error) => {
+                    self.record_answer(index, None);
+                    return Err(error);
+                }
+            }
+        }
+        Err(last_error.unwrap_or_else(|| self.no_route_error()))
+    }
+
+    /// Counted by the first route whose circuit is closed, with its model. Routes
+    /// are tried in the order they were added, falling back on retryable errors.
+    async fn count_tokens(
+        &self,
+        request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        let mut last_error = None;
+        for index in self.closed_routes() {
+            let route = &self.routes[index];
+            let request = ChatCompletionRequest {
+                model: route.model.clone(),
+                ..request.clone()
+            };
+            match route.provider.count_tokens(&request).await {
+                Err(error) if error.is_retryable() => last_error = Some(error),
+                result => return result,
+            }
+        }
+        Err(last_error.unwrap_or_else(|| self.no_route_error()))
+    }
+
+    /// Models of every route whose circuit is closed, without duplicates. Routes
+    /// that share a provider are asked once.
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        let closed = self.closed_routes();
+        if closed.is_empty() {
+            return Err(self.no_route_error());
+        }
+        let mut models: Vec<ModelInfo> = Vec::new();
+        for (position, &index) in closed.iter().enumerate() {
+            let provider = &self.routes[index].provider;
+            if closed[..position]
+                .iter()
+                .any(|&earlier| Arc::ptr_eq(&self.routes[earlier].provider, provider))
+            {
+                continue;
+            }
+            for model in provider.list_models().await? {
+                if !models
+                    .iter()
+                    .any(|known| known.provider == model.provider && known.id == model.id)
+                {
+                    models.push(model);
+                }
+            }
+        }
+        Ok(models)
+    }
+}
@@ -1,13 +1,23 @@
 // XAI API provider
 // https://docs.x.ai/docs/guides/chat
+// https://docs.x.ai/docs/guides/image-generations
 // https://console.x.ai/
 
+use crate::capabilities::CapabilityCatalog;
 use crate::core::client::APIClient;
-use crate::core::{ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage};
+use crate::core::telemetry::ChatSpan;
+use crate::core::{
+    reject_parts, ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage,
+    ChatUsage, ModelInfo, Tool, ToolCall,
+};
 use crate::error::LlmApiError;
+use crate::images::{
+    GeneratedImage, ImageData, ImageFormat, ImageProvider, ImageRequest, ImageResponse,
+};
 use async_trait::async_trait;
 use reqwest::header;
 use serde::{Deserialize, Serialize};
+use std::sync::Arc;
 
 #[derive(Debug, Serialize)]
 struct XaiChatRequest {
@@ -17,12 +27,18 @@ struct XaiChatRequest {
     temperature: Option<f32>,
     #[serde(skip_serializing_if = "Option::is_none")]
     max_tokens: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tools: Option<Vec<Tool>>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 struct XaiMessage {
     role: String,
     content: Vec<XaiContent>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_calls: Option<Vec<ToolCall>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    tool_call_id: Option<String>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -37,6 +53,7 @@ struct XaiChatResponse {
     id: String,
     model: String,
     choices: Vec<XaiChoice>,
+    usage: Option<XaiUsage>,
 }
 
 #[derive(Debug, Deserialize)]
@@ -48,13 +65,63 @@ struct XaiChoice {
 #[derive(Debug, Serialize, Deserialize)]
 struct XaiMessageResponse {
     role: String,
-    content: String,
+    content: Option<String>,
+    tool_calls: Option<Vec<ToolCall>>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XaiUsage {
+    prompt_tokens: u32,
+    completion_tokens: u32,
+    prompt_tokens_details: Option<XaiTokenDetails>,
+    completion_tokens_details: Option<XaiTokenDetails>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XaiTokenDetails {
+    cached_tokens: Option<u32>,
+    reasoning_tokens: Option<u32>,
+}
+
+#[derive(Debug, Serialize)]
+struct XaiImageRequest {
+    model: String,
+    prompt: String,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    n: Option<u32>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    response_format: Option<&'static str>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XaiImageResponse {
+    data: Vec<XaiImage>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XaiImage {
+    url: Option<String>,
+    b64_json: Option<String>,
+    revised_prompt: Option<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XaiModelList {
+    data: Vec<XaiModel>,
+}
+
+#[derive(Debug, Deserialize)]
+struct XaiModel {
+    id: String,
+    created: Option<u64>,
+    owned_by: Option<String>,
 }
 
 pub struct XAI {
     domain: String,
     api_key: String,
     client: APIClient,
+    capabilities: Arc<CapabilityCatalog>,
 }
 
 impl XAI {
@@ -63,9 +130,29 @@ impl XAI {
             domain: "https://api.x.ai".to_string(),
             api_key,
             client: APIClient::new(),
+            capabilities: Arc::new(CapabilityCatalog::builtin()),
         }
     }
 
+    /// Override the API base URL, e.g. for a proxy or a local test server.
+    pub fn with_domain(mut self, domain: String) -> Self {
+        self.domain = domain;
+        self
+    }
+
+    /// Use a preconfigured client, e.g. one recording or replaying a cassette.
+    pub fn with_client(mut self, client: APIClient) -> Self {
+        self.client = client;
+        self
+    }
+
+    /// Check requests against `catalog` instead of the built-in capabilities. An
+    /// empty catalog turns the check off.
+    pub fn with_capabilities(mut self, catalog: Arc<CapabilityCatalog>) -> Self {
+        self.capabilities = catalog;
+        self
+    }
+
     fn convert_messages(messages: Vec<ChatMessage>) -> Vec<XaiMessage> {
         messages
             .into_iter()
@@ -75,17 +162,17 @@ impl XAI {
                     content_type: "text".to_string(),
                     text: msg.content,
                 }],
+                tool_calls: msg.tool_calls,
+                tool_call_id: msg.tool_call_id,
             })
             .collect()
     }
-}
 
-#[async_trait]
-impl crate::providers::LlmProvider for XAI {
-    async fn chat_completion(
+    async fn chat(
         &self,
         request: ChatCompletionRequest,
     ) -> Result<ChatCompletionResponse, LlmApiError> {
+        reject_parts("xai", &request.messages)?;
         let url = format!("{}/v1/chat/completions", self.domain);
 
         let req = XaiChatRequest {
@@ -93,6 +180,7 @@ impl crate::providers::LlmProvider for XAI {
             model: request.model,
             temperature: request.temperature,
             max_tokens: request.max_tokens,
+            tools: request.tools,
         };
         let headers = vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))];
         let res: XaiChatResponse = self.client.send_request(url, headers, &req).await?;
@@ -104,13 +192,103 @@ impl crate::providers::LlmProvider for XAI {
                 .map(|choice| ChatChoice {
                     message: ChatMessage {
                         role: choice.message.role,
-                        content: choice.message.content,
+                        content: choice.message.content.unwrap_or_default(),
+                        tool_calls: choice.message.tool_calls,
+                        ..Default::default()
                     },
                     finish_reason: choice.finish_reason,
                 })
                 .collect(),
             model: res.model,
-            usage: None,
+            usage: res.usage.map(|usage| ChatUsage {
+                input_tokens: Some(usage.prompt_tokens),
+                output_tokens: Some(usage.completion_tokens),
+                cached_input_tokens: usage.prompt_tokens_details.and_then(|d| d.cached_tokens),
+                reasoning_tokens: usage
+                    .completion_tokens_details
+                    .and_then(|d| d.reasoning_tokens),
+                ..Default::default()
+            }),
+            ..Default::default()
+        })
+    }
+}
+
+#[async_trait]
+impl crate::providers::LlmProvider for XAI {
+    async fn chat_completion(
+        &self,
+        request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        self.capabilities.validate("xai", &request)?;
+        ChatSpan::new("xai", &request)
+            .instrument(self.chat(request))
+            .await
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        let url = format!("{}/v1/models", self.domain);
+        let list: XaiModelList = self
+            .client
+            .get_request(
+                url,
+                vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))],
+            )
+            .await?;
+        Ok(list
+            .data
+            .into_iter()
+            .map(|model| ModelInfo {
+                id: model.id,
+                provider: "xai".to_string(),
+                owned_by: model.owned_by,
+                created: model.created,
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+/// xAI picks the size and quality itself, so `size` and `quality` are ignored.
+/// Images are JPEGs.
+#[async_trait]
+impl ImageProvider for XAI {
+    async fn generate_image(&self, request: ImageRequest) -> Result<ImageResponse, LlmApiError> {
+        let url = format!("{}/v1/images/generations", self.domain);
+        let req = XaiImageRequest {
+            model: request.model.clone(),
+            prompt: request.prompt,
+            n: request.count,
+            response_format: request.format.map(|format| match format {
+                ImageFormat::Url => "url",
+                ImageFormat::Base64 => "b64_json",
+            }),
+        };
+        let headers = vec![(header::AUTHORIZATION, format!("Bearer {}", self.api_key))];
+        let res: XaiImageResponse = self.client.send_request(url, headers, &req).await?;
+        let images = res
+            .data
+            .into_iter()
+            .map(|image| {
+                let (data, mime_type) = match (image.b64_json, image.url) {
+                    (Some(data), _) => (ImageData::Base64(data), Some("image/jpeg".to_string())),
+                    (None, Some(url)) => (ImageData::Url(url), None),
+                    (None, None) => {
+                        return Err(LlmApiError::ProviderError(
+                            "image has neither url nor b64_json".to_string(),
+                        ))
+                    }
+                };
+                Ok(GeneratedImage {
+                    data,
+                    mime_type,
+                    revised_prompt: image.revised_prompt,
+                })
+            })
+            .collect::<Result<Vec<_>, _>>()?;
+        Ok(ImageResponse {
+            model: request.model,
+            images,
         })
     }
 }
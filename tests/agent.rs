@@ -0,0 +1,231 @@
+use llm_api_rs::agent::{Agent, JsonSchema, ToolRegistry};
+use llm_api_rs::{
+    ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage, FunctionCall,
+    LlmApiError, MockProvider, ToolCall,
+};
+use serde::Deserialize;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+#[derive(Deserialize, JsonSchema)]
+struct WeatherArgs {
+    /// City name.
+    city: String,
+}
+
+fn tool_calls(calls: &[(&str, &str, &str)]) -> ChatCompletionResponse {
+    ChatCompletionResponse {
+        id: "resp".to_string(),
+        model: "gpt-4o-mini".to_string(),
+        choices: vec![ChatChoice {
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                tool_calls: Some(
+                    calls
+                        .iter()
+                        .map(|(id, name, arguments)| ToolCall {
+                            id: id.to_string(),
+                            call_type: "function".to_string(),
+                            function: FunctionCall {
+                                name: name.to_string(),
+                                arguments: arguments.to_string(),
+                            },
+                        })
+                        .collect(),
+                ),
+                ..Default::default()
+            },
+            finish_reason: "tool_calls".to_string(),
+        }],
+        ..Default::default()
+    }
+}
+
+fn weather_registry() -> ToolRegistry {
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        "get_weather",
+        "Current weather for a city",
+        |args: WeatherArgs| async move {
+            if args.city == "Atlantis" {
+                return Err(LlmApiError::ProviderError("no such city".to_string()));
+            }
+            Ok(format!("Sunny in {}", args.city))
+        },
+    );
+    registry
+}
+
+fn request() -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "gpt-4o-mini".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Weather in Tokyo?".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn registry_derives_parameter_schemas() {
+    let tools = weather_registry().tools();
+    assert_eq!(tools.len(), 1);
+    assert_eq!(tools[0].function.name, "get_weather");
+    let parameters = &tools[0].function.parameters;
+    assert_eq!(parameters["type"], "object");
+    assert_eq!(parameters["properties"]["city"]["type"], "string");
+    assert_eq!(parameters["required"], serde_json::json!(["city"]));
+    assert!(parameters.get("$schema").is_none());
+}
+
+#[tokio::test]
+async fn runs_tools_until_a_final_answer() {
+    let provider = MockProvider::new();
+    provider.push(Ok(tool_calls(&[(
+        "call_1",
+        "get_weather",
+        r#"{"city":"Tokyo"}"#,
+    )])));
+    let provider = provider.with_reply("It is sunny in Tokyo.");
+    let steps = Arc::new(Mutex::new(Vec::new()));
+    let seen = steps.clone();
+    let agent = Agent::new(provider, weather_registry())
+        .with_step_callback(move |step| seen.lock().unwrap().push(step.clone()));
+
+    let output = agent.run(request()).await.unwrap();
+
+    assert_eq!(output.content, "It is sunny in Tokyo.");
+    assert_eq!(output.iterations, 2);
+    let roles: Vec<_> = output.messages.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, ["user", "assistant", "tool", "assistant"]);
+    assert_eq!(output.messages[2].content, "Sunny in Tokyo");
+    assert_eq!(output.messages[2].tool_call_id.as_deref(), Some("call_1"));
+
+    let steps = steps.lock().unwrap();
+    assert_eq!(steps.len(), 2);
+    assert_eq!(steps[0].tool_results.len(), 1);
+    assert!(steps[1].tool_results.is_empty());
+
+    let requests = agent.provider().requests();
+    assert_eq!(requests[0].tools.as_ref().unwrap().len(), 1);
+    assert_eq!(requests[1].messages.len(), 3);
+}
+
+#[tokio::test]
+async fn tool_errors_are_reported_to_the_model() {
+    let provider = MockProvider::new();
+    provider.push(Ok(tool_calls(&[
+        ("call_1", "get_weather", r#"{"city":"Atlantis"}"#),
+        ("call_2", "get_weather", r#"{"town":"Paris"}"#),
+        ("call_3", "book_flight", "{}"),
+    ])));
+    let provider = provider.with_reply("I could not find that.");
+    let agent = Agent::new(provider, weather_registry());
+
+    let output = agent.run(request()).await.unwrap();
+
+    let tool_messages: Vec<_> = output
+        .messages
+        .iter()
+        .filter(|m| m.role == "tool")
+        .collect();
+    assert_eq!(tool_messages.len(), 3);
+    assert!(tool_messages[0].content.contains("no such city"));
+    assert!(tool_messages[1].content.contains("city"));
+    assert!(tool_messages[2]
+        .content
+        .contains("unknown tool book_flight"));
+    assert!(tool_messages
+        .iter()
+        .all(|m| m.content.starts_with("error: ")));
+}
+
+#[tokio::test(start_paused = true)]
+async fn parallel_tools_keep_the_call_order() {
+    let mut registry = ToolRegistry::new();
+    registry.register(
+        "sleep",
+        "Sleep for a number of milliseconds",
+        |ms: u64| async move {
+            tokio::time::sleep(Duration::from_millis(ms)).await;
+            Ok(ms)
+        },
+    );
+    let provider = MockProvider::new();
+    provider.push(Ok(tool_calls(&[
+        ("call_1", "sleep", "300"),
+        ("call_2", "sleep", "100"),
+        ("call_3", "sleep", "200"),
+    ])));
+    let provider = provider.with_reply("Done.");
+    let agent = Agent::new(provider, registry);
+
+    let start = tokio::time::Instant::now();
+    let output = agent.run(request()).await.unwrap();
+
+    assert_eq!(start.elapsed(), Duration::from_millis(300));
+    let outputs: Vec<_> = output.messages[2..5]
+        .iter()
+        .map(|m| (m.tool_call_id.clone().unwrap(), m.content.clone()))
+        .collect();
+    assert_eq!(
+        outputs,
+        [
+            ("call_1".to_string(), "300".to_string()),
+            ("call_2".to_string(), "100".to_string()),
+            ("call_3".to_string(), "200".to_string()),
+        ]
+    );
+}
+
+#[tokio::test]
+async fn panics_are_reported_in_both_modes() {
+    for parallel_tools in [true, false] {
+        let mut registry = weather_registry();
+        registry.register(
+            "explode",
+            "Always panics",
+            |_: serde_json::Value| async move {
+                if true {
+                    panic!("boom");
+                }
+                Ok("unreachable")
+            },
+        );
+        let provider = MockProvider::new();
+        provider.push(Ok(tool_calls(&[
+            ("call_1", "explode", "{}"),
+            ("call_2", "get_weather", r#"{"city":"Tokyo"}"#),
+        ])));
+        let provider = provider.with_reply("Sunny.");
+        let agent = Agent::new(provider, registry).with_parallel_tools(parallel_tools);
+
+        let output = agent.run(request()).await.unwrap();
+
+        assert_eq!(
+            output.messages[2].content,
+            "error: Provider error: tool panicked"
+        );
+        assert_eq!(output.messages[3].content, "Sunny in Tokyo");
+    }
+}
+
+#[tokio::test]
+async fn stops_after_max_iterations() {
+    let provider = MockProvider::new();
+    for id in ["call_1", "call_2", "call_3"] {
+        provider.push(Ok(tool_calls(&[(
+            id,
+            "get_weather",
+            r#"{"city":"Tokyo"}"#,
+        )])));
+    }
+    let agent = Agent::new(provider, weather_registry()).with_max_iterations(2);
+
+    let error = agent.run(request()).await.unwrap_err();
+
+    assert!(error.to_string().contains("after 2 rounds"));
+    assert_eq!(agent.provider().requests().len(), 2);
+}
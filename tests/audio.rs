@@ -0,0 +1,211 @@
+#![cfg(feature = "openai")]
+
+use async_trait::async_trait;
+use llm_api_rs::audio::{
+    AudioFormat, SpeechProvider, SpeechRequest, TimestampGranularity, TranscriptSegment,
+    TranscriptionProvider, TranscriptionRequest,
+};
+use llm_api_rs::core::cassette::Cassette;
+use llm_api_rs::core::client::APIClient;
+use llm_api_rs::core::middleware::{HttpRequest, Middleware};
+use llm_api_rs::files::FileUpload;
+use llm_api_rs::{LlmApiError, OpenAI};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{body_json, body_string_contains, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Speech bytes that are not valid UTF-8, to catch bodies decoded as text.
+const AUDIO: &[u8] = &[0xff, 0xfb, 0x90, 0x00, 0x80];
+
+async fn speech_server() -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/audio/speech"))
+        .and(body_json(json!({
+            "model": "gpt-4o-mini-tts",
+            "input": "Hello there",
+            "voice": "alloy",
+            "response_format": "wav",
+            "speed": 1.25
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_bytes(AUDIO))
+        .mount(&server)
+        .await;
+    server
+}
+
+// Keeps the request bodies middleware sees.
+#[derive(Default)]
+struct Bodies(Mutex<Vec<serde_json::Value>>);
+
+#[async_trait]
+impl Middleware for Bodies {
+    async fn before_request(&self, request: &mut HttpRequest) -> Result<(), LlmApiError> {
+        self.0.lock().unwrap().push(request.body.clone());
+        Ok(())
+    }
+}
+
+fn speech_request() -> SpeechRequest {
+    SpeechRequest::new("gpt-4o-mini-tts", "Hello there", "alloy")
+        .with_format(AudioFormat::Wav)
+        .with_speed(1.25)
+}
+
+#[tokio::test]
+async fn transcribes_with_timestamps() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/audio/transcriptions"))
+        .and(body_string_contains("name=\"model\"\r\n\r\nwhisper-1"))
+        .and(body_string_contains("name=\"language\"\r\n\r\nen"))
+        .and(body_string_contains(
+            "name=\"response_format\"\r\n\r\nverbose_json",
+        ))
+        .and(body_string_contains(
+            "name=\"timestamp_granularities[]\"\r\n\r\nsegment",
+        ))
+        .and(body_string_contains(
+            "name=\"timestamp_granularities[]\"\r\n\r\nword",
+        ))
+        .and(body_string_contains(
+            "filename=\"note.mp3\"\r\nContent-Type: audio/mpeg",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "task": "transcribe",
+            "language": "english",
+            "duration": 1.5,
+            "text": "Hello there.",
+            "segments": [{"id": 0, "seek": 0, "start": 0.0, "end": 1.5, "text": " Hello there."}],
+            "words": [
+                {"word": "Hello", "start": 0.0, "end": 0.5},
+                {"word": "there", "start": 0.6, "end": 1.2}
+            ]
+        })))
+        .mount(&server)
+        .await;
+    let bodies = Arc::new(Bodies::default());
+    let provider = OpenAI::new("sk".to_string())
+        .with_domain(server.uri())
+        .with_client(APIClient::new().with_middleware(bodies.clone()));
+
+    let request = TranscriptionRequest::new(
+        "whisper-1",
+        FileUpload::new("note.mp3", "audio/mpeg", b"ID3".to_vec()),
+    )
+    .with_language("en")
+    .with_timestamps(TimestampGranularity::Segment)
+    .with_timestamps(TimestampGranularity::Word);
+    let transcription = provider.transcribe(request).await.unwrap();
+
+    assert_eq!(transcription.text, "Hello there.");
+    assert_eq!(transcription.language.as_deref(), Some("english"));
+    assert_eq!(transcription.duration, Some(1.5));
+    assert_eq!(transcription.segments.len(), 1);
+    assert_eq!(
+        transcription.words[1],
+        TranscriptSegment {
+            start: 0.6,
+            end: 1.2,
+            text: "there".to_string(),
+        }
+    );
+    let body = &bodies.0.lock().unwrap()[0];
+    assert_eq!(
+        body["timestamp_granularities[]"],
+        json!(["segment", "word"])
+    );
+    assert_eq!(body["file"]["filename"], "note.mp3");
+}
+
+#[tokio::test]
+async fn transcribes_text_only_by_default() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/audio/transcriptions"))
+        .and(body_string_contains("name=\"response_format\"\r\n\r\njson"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"text": "Hi."})))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let request = TranscriptionRequest::new(
+        "gpt-4o-transcribe",
+        FileUpload::new("note.wav", "audio/wav", b"RIFF".to_vec()),
+    );
+    let transcription = provider.transcribe(request).await.unwrap();
+
+    assert_eq!(transcription.text, "Hi.");
+    assert!(transcription.segments.is_empty());
+    assert_eq!(transcription.language, None);
+}
+
+#[tokio::test]
+async fn speech_returns_audio_bytes() {
+    let server = speech_server().await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let audio = provider.speech(speech_request()).await.unwrap();
+
+    assert_eq!(audio.format, AudioFormat::Wav);
+    assert_eq!(audio.data, AUDIO);
+}
+
+#[tokio::test]
+async fn speech_streams_audio_chunks() {
+    let server = speech_server().await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let mut stream = provider.speech_stream(speech_request()).await.unwrap();
+    let mut data = Vec::new();
+    while let Some(chunk) = stream.next_chunk().await.unwrap() {
+        data.extend_from_slice(&chunk);
+    }
+
+    assert_eq!(data, AUDIO);
+}
+
+#[tokio::test]
+async fn speech_errors_are_api_errors() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/audio/speech"))
+        .respond_with(ResponseTemplate::new(400).set_body_json(json!({
+            "error": {"message": "Invalid voice", "type": "invalid_request_error"}
+        })))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let error = provider.speech_stream(speech_request()).await.unwrap_err();
+
+    assert!(matches!(error, LlmApiError::ApiError { status: 400, .. }));
+}
+
+#[tokio::test]
+async fn speech_records_and_replays() {
+    let path = std::env::temp_dir().join(format!("llm-api-rs-speech-{}.json", std::process::id()));
+    let server = speech_server().await;
+
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::record(&path)));
+    let provider = OpenAI::new("sk".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    provider.speech(speech_request()).await.unwrap();
+
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::replay(&path).unwrap()));
+    let provider = OpenAI::new("sk".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    let audio = provider
+        .speech_stream(speech_request())
+        .await
+        .unwrap()
+        .collect()
+        .await
+        .unwrap();
+
+    assert_eq!(audio, AUDIO);
+    std::fs::remove_file(path).unwrap();
+}
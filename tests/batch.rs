@@ -0,0 +1,390 @@
+#![cfg(all(feature = "openai", feature = "anthropic", feature = "gemini"))]
+
+use llm_api_rs::batch::{run_batch, BatchProvider, BatchRequest, BatchStatus};
+use llm_api_rs::{Anthropic, ChatCompletionRequest, ChatMessage, Gemini, LlmApiError, OpenAI};
+use serde_json::json;
+use std::time::Duration;
+use wiremock::matchers::{body_json, body_string_contains, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+const POLL: Duration = Duration::from_millis(10);
+
+fn request(model: &str, content: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn requests(model: &str) -> Vec<BatchRequest> {
+    vec![
+        BatchRequest::new("doc-1", request(model, "first")),
+        BatchRequest::new("doc-2", request(model, "second")),
+        BatchRequest::new("doc-3", request(model, "third")),
+    ]
+}
+
+fn batch_file() -> serde_json::Value {
+    json!({"id": "file-in", "object": "file", "filename": "batch.jsonl", "bytes": 512, "purpose": "batch"})
+}
+
+fn openai_completion(content: &str) -> serde_json::Value {
+    json!({
+        "id": "chatcmpl-1",
+        "model": "gpt-4o-mini",
+        "choices": [{
+            "message": {"role": "assistant", "content": content},
+            "finish_reason": "stop"
+        }],
+        "usage": {"prompt_tokens": 5, "completion_tokens": 1}
+    })
+}
+
+#[tokio::test]
+async fn openai_batch_uploads_jsonl_and_maps_results() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/files"))
+        .and(header("authorization", "Bearer sk"))
+        .and(body_string_contains("name=\"purpose\"\r\n\r\nbatch"))
+        .and(body_string_contains(
+            r#"{"custom_id":"doc-2","method":"POST","url":"/v1/chat/completions","body":{"model":"gpt-4o-mini","messages":[{"content":"second","role":"user"}]"#,
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(batch_file()))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/batches"))
+        .and(body_json(json!({
+            "input_file_id": "file-in",
+            "endpoint": "/v1/chat/completions",
+            "completion_window": "24h"
+        })))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"id": "batch_1", "status": "validating"})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/batches/batch_1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "batch_1",
+            "status": "in_progress",
+            "request_counts": {"total": 3, "completed": 1, "failed": 0}
+        })))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/batches/batch_1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "batch_1",
+            "status": "completed",
+            "request_counts": {"total": 3, "completed": 1, "failed": 1},
+            "output_file_id": "file-out",
+            "error_file_id": "file-err"
+        })))
+        .mount(&server)
+        .await;
+    let output = [
+        json!({"custom_id": "doc-1", "response": {"status_code": 200, "body": openai_completion("positive")}, "error": null}),
+        json!({"custom_id": "doc-3", "response": {"status_code": 500, "body": {"error": {"message": "overloaded"}}}, "error": null}),
+    ];
+    Mock::given(method("GET"))
+        .and(path("/v1/files/file-out/content"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(format!("{}\n{}\n", output[0], output[1])),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/files/file-err/content"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            json!({"custom_id": "doc-2", "response": null, "error": {"code": "invalid", "message": "bad request"}})
+                .to_string(),
+        ))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let results = run_batch(&provider, requests("gpt-4o-mini"), POLL)
+        .await
+        .unwrap();
+
+    let ids: Vec<_> = results.iter().map(|r| r.custom_id.as_str()).collect();
+    assert_eq!(ids, ["doc-1", "doc-2", "doc-3"]);
+    let response = results[0].response.as_ref().unwrap();
+    assert_eq!(response.choices[0].message.content, "positive");
+    assert_eq!(response.usage.as_ref().unwrap().input_tokens, Some(5));
+    assert!(
+        matches!(&results[1].response, Err(LlmApiError::ProviderError(m)) if m == "bad request")
+    );
+    assert_eq!(
+        results[2].response.as_ref().unwrap_err().status(),
+        Some(500)
+    );
+
+    let job = provider.get_batch("batch_1").await.unwrap();
+    assert_eq!(job.status, BatchStatus::Completed);
+    assert_eq!(job.counts.failed, 1);
+}
+
+#[tokio::test]
+async fn anthropic_batch_reads_results_url() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages/batches"))
+        .and(header("x-api-key", "sk-ant"))
+        .and(body_json(json!({"requests": [
+            {"custom_id": "doc-1", "params": {"messages": [{"role": "user", "content": "first"}], "model": "claude-sonnet-4-0", "max_tokens": 1000, "temperature": null}},
+            {"custom_id": "doc-2", "params": {"messages": [{"role": "user", "content": "second"}], "model": "claude-sonnet-4-0", "max_tokens": 1000, "temperature": null}},
+            {"custom_id": "doc-3", "params": {"messages": [{"role": "user", "content": "third"}], "model": "claude-sonnet-4-0", "max_tokens": 1000, "temperature": null}}
+        ]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msgbatch_1",
+            "processing_status": "in_progress",
+            "request_counts": {"processing": 3, "succeeded": 0, "errored": 0, "canceled": 0, "expired": 0}
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/messages/batches/msgbatch_1"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msgbatch_1",
+            "processing_status": "ended",
+            "request_counts": {"processing": 0, "succeeded": 1, "errored": 1, "canceled": 0, "expired": 1},
+            "results_url": format!("{}/v1/messages/batches/msgbatch_1/results", server.uri())
+        })))
+        .mount(&server)
+        .await;
+    let lines = [
+        json!({"custom_id": "doc-2", "result": {"type": "succeeded", "message": {
+            "id": "msg_1", "model": "claude-sonnet-4-0", "role": "assistant", "stop_reason": "end_turn",
+            "content": [{"type": "text", "text": "negative"}],
+            "usage": {"input_tokens": 7, "output_tokens": 1}
+        }}}),
+        json!({"custom_id": "doc-1", "result": {"type": "errored", "error": {"type": "error", "error": {"type": "invalid_request_error", "message": "max_tokens too large"}}}}),
+        json!({"custom_id": "doc-3", "result": {"type": "expired"}}),
+    ];
+    Mock::given(method("GET"))
+        .and(path("/v1/messages/batches/msgbatch_1/results"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_string(
+                lines
+                    .iter()
+                    .map(|l| l.to_string())
+                    .collect::<Vec<_>>()
+                    .join("\n"),
+            ),
+        )
+        .mount(&server)
+        .await;
+    let provider = Anthropic::new("sk-ant".to_string()).with_domain(server.uri());
+
+    let job = provider
+        .create_batch(requests("claude-sonnet-4-0"))
+        .await
+        .unwrap();
+    assert_eq!(job.status, BatchStatus::InProgress);
+    assert_eq!(job.counts.total, 3);
+
+    let results = run_batch(&provider, requests("claude-sonnet-4-0"), POLL)
+        .await
+        .unwrap();
+    assert!(
+        matches!(&results[0].response, Err(LlmApiError::ProviderError(m)) if m == "max_tokens too large")
+    );
+    assert_eq!(
+        results[1].response.as_ref().unwrap().choices[0]
+            .message
+            .content,
+        "negative"
+    );
+    assert!(results[2].response.is_err());
+}
+
+#[tokio::test]
+async fn gemini_batch_sends_inline_requests() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:batchGenerateContent"))
+        .and(query_param("key", "g-key"))
+        .and(body_string_contains(r#""metadata":{"key":"doc-1"}"#))
+        .and(body_string_contains(r#""parts":[{"text":"third"}]"#))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "batches/123",
+            "metadata": {"model": "models/gemini-2.0-flash", "state": "BATCH_STATE_PENDING"}
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1beta/batches/123"))
+        .and(query_param("key", "g-key"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "batches/123",
+            "metadata": {
+                "model": "models/gemini-2.0-flash",
+                "state": "BATCH_STATE_SUCCEEDED",
+                "batchStats": {"requestCount": "3", "successfulRequestCount": "2", "failedRequestCount": "1"}
+            },
+            "done": true,
+            "response": {"inlinedResponses": {"inlinedResponses": [
+                {"metadata": {"key": "doc-1"}, "response": {"candidates": [{"content": {"role": "model", "parts": [{"text": "positive"}]}, "finishReason": "STOP"}]}},
+                {"metadata": {"key": "doc-2"}, "error": {"code": 3, "message": "invalid argument"}},
+                {"metadata": {"key": "doc-3"}, "response": {"candidates": [{"content": {"role": "model", "parts": [{"text": "neutral"}]}}]}}
+            ]}}
+        })))
+        .mount(&server)
+        .await;
+    let provider = Gemini::new("g-key".to_string()).with_domain(server.uri());
+
+    let results = run_batch(&provider, requests("gemini-2.0-flash"), POLL)
+        .await
+        .unwrap();
+
+    let response = results[0].response.as_ref().unwrap();
+    assert_eq!(response.model, "gemini-2.0-flash");
+    assert_eq!(response.choices[0].message.content, "positive");
+    assert!(
+        matches!(&results[1].response, Err(LlmApiError::ProviderError(m)) if m == "invalid argument")
+    );
+    assert_eq!(
+        results[2].response.as_ref().unwrap().choices[0]
+            .message
+            .content,
+        "neutral"
+    );
+    let job = provider.get_batch("batches/123").await.unwrap();
+    assert_eq!(job.counts.total, 3);
+    assert_eq!(job.counts.succeeded, 2);
+
+    let mixed = vec![
+        BatchRequest::new("a", request("gemini-2.0-flash", "x")),
+        BatchRequest::new("b", request("gemini-2.5-pro", "y")),
+    ];
+    assert!(matches!(
+        provider.create_batch(mixed).await,
+        Err(LlmApiError::ConfigError(_))
+    ));
+}
+
+#[tokio::test]
+async fn gemini_blocked_items_fail_on_their_own() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1beta/batches/456"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "name": "batches/456",
+            "metadata": {"model": "models/gemini-2.0-flash", "state": "BATCH_STATE_SUCCEEDED"},
+            "done": true,
+            "response": {"inlinedResponses": {"inlinedResponses": [
+                {"metadata": {"key": "doc-1"}, "response": {"candidates": [{"finishReason": "SAFETY"}]}},
+                {"metadata": {"key": "doc-2"}, "response": {"promptFeedback": {"blockReason": "PROHIBITED_CONTENT"}}},
+                {"metadata": {"key": "doc-3"}, "response": {"responseId": "r-3", "candidates": [{"content": {"role": "model", "parts": [{"text": "fine"}]}, "finishReason": "STOP"}]}}
+            ]}}
+        })))
+        .mount(&server)
+        .await;
+    let provider = Gemini::new("g-key".to_string()).with_domain(server.uri());
+
+    let results = provider.batch_results("batches/456").await.unwrap();
+
+    assert!(
+        matches!(&results[0].response, Err(LlmApiError::ProviderError(m)) if m.contains("SAFETY"))
+    );
+    assert!(
+        matches!(&results[1].response, Err(LlmApiError::ProviderError(m)) if m == "prompt blocked: PROHIBITED_CONTENT")
+    );
+    let response = results[2].response.as_ref().unwrap();
+    assert_eq!(response.id, "r-3");
+    assert_eq!(response.choices[0].message.content, "fine");
+}
+
+#[tokio::test]
+async fn failed_batches_and_missing_results() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/files"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(batch_file()))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/batches"))
+        .and(body_string_contains("file-in"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"id": "batch_2", "status": "validating"})),
+        )
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/batches"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"id": "batch_3", "status": "validating"})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/batches/batch_2"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"id": "batch_2", "status": "failed"})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/batches/batch_3"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "batch_3", "status": "expired", "output_file_id": "file-out"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/files/file-out/content"))
+        .respond_with(ResponseTemplate::new(200).set_body_string(
+            json!({"custom_id": "doc-2", "response": {"status_code": 200, "body": openai_completion("ok")}})
+                .to_string(),
+        ))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let error = run_batch(&provider, requests("gpt-4o-mini"), POLL)
+        .await
+        .unwrap_err();
+    assert!(error.to_string().contains("batch batch_2 failed"));
+
+    let results = run_batch(&provider, requests("gpt-4o-mini"), POLL)
+        .await
+        .unwrap();
+    assert!(results[0].response.is_err());
+    assert!(results[1].response.is_ok());
+    assert!(results[2].response.is_err());
+}
+
+#[tokio::test]
+async fn results_of_running_batches_are_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/batches/batch_4"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "batch_4", "status": "finalizing", "output_file_id": "file-out"
+        })))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    match provider.batch_results("batch_4").await.unwrap_err() {
+        LlmApiError::ProviderError(message) => assert_eq!(message, "batch batch_4 has not ended"),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
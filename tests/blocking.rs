@@ -0,0 +1,52 @@
+#![cfg(all(feature = "blocking", feature = "openai"))]
+
+use llm_api_rs::blocking::Blocking;
+use llm_api_rs::{ChatCompletionRequest, ChatMessage, MockProvider, OpenAI};
+use serde_json::json;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn request(content: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "gpt-4o".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[test]
+fn chat_without_a_runtime() {
+    let client = Blocking::new(MockProvider::new().with_reply("Hi!")).unwrap();
+
+    let response = client.chat_completion(request("Hello")).unwrap();
+    let count = client.count_tokens(&request("Hello")).unwrap();
+
+    assert_eq!(response.choices[0].message.content, "Hi!");
+    assert!(count.input_tokens > 0);
+    assert_eq!(client.inner().requests().len(), 1);
+}
+
+#[test]
+fn http_providers_work_synchronously() {
+    // The mock server runs on its own thread; this runtime only sets it up.
+    let setup = tokio::runtime::Runtime::new().unwrap();
+    let server = setup.block_on(MockServer::start());
+    setup.block_on(
+        Mock::given(method("GET"))
+            .and(path("/v1/models"))
+            .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+                "object": "list",
+                "data": [{"id": "gpt-4o", "object": "model", "created": 1715367049, "owned_by": "system"}]
+            })))
+            .mount(&server),
+    );
+    let client = Blocking::new(OpenAI::new("sk".to_string()).with_domain(server.uri())).unwrap();
+
+    let models = client.list_models().unwrap();
+
+    assert_eq!(models[0].id, "gpt-4o");
+}
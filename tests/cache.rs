@@ -0,0 +1,108 @@
+use llm_api_rs::providers::cache::cache_key;
+use llm_api_rs::{
+    CacheStore, CachedProvider, ChatCompletionRequest, ChatMessage, DiskCache, LlmProvider,
+    MemoryCache, MockProvider,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn request(content: &str, temperature: Option<f32>) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "gpt-4o-mini".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }],
+        temperature,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn serves_repeated_deterministic_requests_from_memory() {
+    let mock = MockProvider::new().with_reply("4").with_reply("four");
+    let provider = CachedProvider::new(mock, Arc::new(MemoryCache::new(16)));
+
+    let first = provider
+        .chat_completion(request("2+2?", Some(0.0)))
+        .await
+        .unwrap();
+    let second = provider
+        .chat_completion(request("2+2?", Some(0.0)))
+        .await
+        .unwrap();
+
+    assert!(!first.metadata.cache_hit);
+    assert!(second.metadata.cache_hit);
+    assert_eq!(second.choices[0].message.content, "4");
+    assert_eq!(provider.inner().requests().len(), 1);
+}
+
+#[tokio::test]
+async fn skips_non_deterministic_requests_unless_forced() {
+    let mock = MockProvider::new().with_reply("a").with_reply("b");
+    let provider = CachedProvider::new(mock, Arc::new(MemoryCache::new(16)));
+    provider.chat_completion(request("hi", None)).await.unwrap();
+    let res = provider.chat_completion(request("hi", None)).await.unwrap();
+    assert_eq!(res.choices[0].message.content, "b");
+
+    let mock = MockProvider::new().with_reply("a").with_reply("b");
+    let provider = CachedProvider::new(mock, Arc::new(MemoryCache::new(16))).with_force(true);
+    provider
+        .chat_completion(request("hi", Some(0.7)))
+        .await
+        .unwrap();
+    let res = provider
+        .chat_completion(request("hi", Some(0.7)))
+        .await
+        .unwrap();
+    assert_eq!(res.choices[0].message.content, "a");
+    assert!(res.metadata.cache_hit);
+}
+
+#[tokio::test(start_paused = true)]
+async fn memory_cache_evicts_least_recently_used_and_expires() {
+    let mock = MockProvider::new().with_reply("x");
+    let response = mock.chat_completion(request("x", Some(0.0))).await.unwrap();
+    let cache = MemoryCache::new(2).with_ttl(Duration::from_secs(60));
+
+    cache.put("a", &response).unwrap();
+    cache.put("b", &response).unwrap();
+    assert!(cache.get("a").is_some());
+    cache.put("c", &response).unwrap();
+    assert!(cache.get("b").is_none());
+    assert_eq!(cache.len(), 2);
+
+    tokio::time::advance(Duration::from_secs(61)).await;
+    assert!(cache.get("a").is_none());
+    assert!(cache.get("c").is_none());
+}
+
+#[tokio::test]
+async fn disk_cache_survives_a_new_provider() {
+    let dir = std::env::temp_dir().join(format!("llm-api-rs-cache-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+
+    let provider = CachedProvider::new(
+        MockProvider::new().with_reply("stored"),
+        Arc::new(DiskCache::new(&dir)),
+    );
+    provider
+        .chat_completion(request("hello", Some(0.0)))
+        .await
+        .unwrap();
+
+    let provider = CachedProvider::new(MockProvider::new(), Arc::new(DiskCache::new(&dir)));
+    let res = provider
+        .chat_completion(request("hello", Some(0.0)))
+        .await
+        .unwrap();
+    assert!(res.metadata.cache_hit);
+    assert_eq!(res.choices[0].message.content, "stored");
+
+    let key = cache_key(&request("hello", Some(0.0))).unwrap();
+    assert!(dir.join(format!("{}.json", key)).exists());
+    assert_ne!(key, cache_key(&request("hello!", Some(0.0))).unwrap());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
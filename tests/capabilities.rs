@@ -0,0 +1,197 @@
+#![cfg(all(feature = "openai", feature = "anthropic"))]
+
+use llm_api_rs::capabilities::{CapabilityCatalog, ModelCapabilities};
+use llm_api_rs::providers::openai::ResponsesOptions;
+use llm_api_rs::{
+    Anthropic, ChatCompletionRequest, ChatMessage, ContentPart, FileHandle, LlmApiError,
+    LlmProvider, OpenAI,
+};
+use serde_json::json;
+use std::sync::Arc;
+use wiremock::matchers::{method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+// Nothing listens here, so a request that passes validation fails with a network error.
+const UNREACHABLE: &str = "http://127.0.0.1:9";
+
+fn image() -> FileHandle {
+    FileHandle {
+        provider: "openai".to_string(),
+        id: "file-1".to_string(),
+        filename: "chart.png".to_string(),
+        mime_type: "image/png".to_string(),
+        size_bytes: 4,
+        uri: None,
+    }
+}
+
+fn request(model: &str, messages: Vec<ChatMessage>) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages,
+        ..Default::default()
+    }
+}
+
+fn user_with_image() -> ChatMessage {
+    ChatMessage {
+        role: "user".to_string(),
+        content: "What does this chart show?".to_string(),
+        parts: vec![ContentPart::File(image())],
+        ..Default::default()
+    }
+}
+
+fn rejection(error: LlmApiError) -> String {
+    match error {
+        LlmApiError::ConfigError(message) => message,
+        other => panic!("expected a ConfigError, got {:?}", other),
+    }
+}
+
+#[test]
+fn lookups_match_snapshots_but_not_other_models() {
+    let catalog = CapabilityCatalog::builtin();
+
+    let mini = catalog.get("openai", "o1-mini-2024-09-12").unwrap();
+    assert!(!mini.system_prompt);
+    assert!(
+        catalog
+            .get("openai", "o1-2024-12-17")
+            .unwrap()
+            .system_prompt
+    );
+    assert_eq!(
+        catalog.get("openai", "gpt-4o-mini").unwrap().context_window,
+        128_000
+    );
+    assert!(catalog.get("anthropic", "gpt-4o").is_none());
+
+    let narrow = CapabilityCatalog::empty()
+        .with_capabilities("openai", "o3", ModelCapabilities::new(200_000))
+        .with_capabilities(
+            "gemini",
+            "gemini-2.5-flash",
+            ModelCapabilities::new(1_048_576),
+        );
+    assert!(narrow.get("openai", "o3-2025-04-16").is_some());
+    assert!(narrow.get("openai", "o3-pro").is_none());
+    assert!(narrow
+        .get("gemini", "gemini-2.5-flash-preview-05-20")
+        .is_some());
+    assert!(narrow.get("gemini", "gemini-2.5-flash-lite").is_none());
+    assert!(catalog.get("mistral", "mistral-large-latest").is_some());
+    assert_eq!(
+        catalog
+            .find("claude-sonnet-4-20250514")
+            .unwrap()
+            .context_window,
+        200_000
+    );
+}
+
+#[tokio::test]
+async fn rejects_images_for_text_only_models() {
+    let provider = OpenAI::new("sk".to_string()).with_domain(UNREACHABLE.to_string());
+
+    let error = provider
+        .chat_completion(request("gpt-3.5-turbo", vec![user_with_image()]))
+        .await
+        .unwrap_err();
+
+    assert_eq!(
+        rejection(error),
+        "gpt-3.5-turbo does not support image input"
+    );
+}
+
+#[tokio::test]
+async fn rejects_system_messages_and_excess_output() {
+    let openai = OpenAI::new("sk".to_string()).with_domain(UNREACHABLE.to_string());
+    let system = ChatMessage {
+        role: "system".to_string(),
+        content: "Be brief.".to_string(),
+        ..Default::default()
+    };
+    let error = openai
+        .chat_completion(request("o1-mini", vec![system.clone()]))
+        .await
+        .unwrap_err();
+    assert_eq!(rejection(error), "o1-mini does not support system messages");
+    let error = openai
+        .create_response(
+            request("o1-mini", vec![system.clone()]),
+            ResponsesOptions::default(),
+        )
+        .await
+        .unwrap_err();
+    assert_eq!(rejection(error), "o1-mini does not support system messages");
+
+    let anthropic = Anthropic::new("sk-ant".to_string()).with_domain(UNREACHABLE.to_string());
+    let mut long = request("claude-3-haiku-20240307", vec![]);
+    long.max_tokens = Some(8_192);
+    let error = anthropic.chat_completion(long).await.unwrap_err();
+    assert!(rejection(error).contains("exceeds the 4096 output tokens"));
+}
+
+#[tokio::test]
+async fn overrides_replace_builtin_entries() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "model": "gpt-3.5-turbo",
+            "choices": [{"message": {"role": "assistant", "content": "A bar chart."}, "finish_reason": "stop"}]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let catalog = CapabilityCatalog::builtin().with_capabilities(
+        "openai",
+        "gpt-3.5-turbo",
+        ModelCapabilities::new(16_385).with_vision(true),
+    );
+    let provider = OpenAI::new("sk".to_string())
+        .with_domain(server.uri())
+        .with_capabilities(Arc::new(catalog));
+
+    let response = provider
+        .chat_completion(request("gpt-3.5-turbo", vec![user_with_image()]))
+        .await
+        .unwrap();
+
+    assert_eq!(response.choices[0].message.content, "A bar chart.");
+}
+
+#[test]
+fn overrides_load_from_json() {
+    let path = std::env::temp_dir().join(format!(
+        "llm-api-rs-capabilities-{}.json",
+        std::process::id()
+    ));
+    std::fs::write(
+        &path,
+        r#"{"ollama": {"llava": {"vision": true, "tools": false, "json_mode": true,
+            "streaming": true, "system_prompt": true, "context_window": 4096}}}"#,
+    )
+    .unwrap();
+
+    let mut catalog = CapabilityCatalog::empty();
+    catalog.load_overrides(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let llava = catalog.get("ollama", "llava:13b").unwrap();
+    assert!(llava.vision);
+    assert!(!llava.tools);
+    assert_eq!(llava.max_output_tokens, None);
+
+    let mut tools = request("llava:13b", vec![]);
+    tools.tools = Some(vec![serde_json::from_value(json!({
+        "type": "function",
+        "function": {"name": "lookup", "parameters": {"type": "object"}}
+    }))
+    .unwrap()]);
+    let error = catalog.validate("ollama", &tools).unwrap_err();
+    assert_eq!(rejection(error), "llava:13b does not support tools");
+}
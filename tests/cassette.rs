@@ -0,0 +1,125 @@
+#![cfg(all(feature = "openai", feature = "gemini"))]
+
+use llm_api_rs::core::cassette::Cassette;
+use llm_api_rs::core::client::APIClient;
+use llm_api_rs::{ChatCompletionRequest, ChatMessage, Gemini, LlmProvider, OpenAI};
+use serde_json::json;
+use std::path::PathBuf;
+use std::sync::Arc;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn cassette_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("llm-api-rs-{}-{}.json", name, std::process::id()))
+}
+
+fn request(content: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "gpt-4o-mini".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+async fn server_replying(body: serde_json::Value) -> MockServer {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(body))
+        .mount(&server)
+        .await;
+    server
+}
+
+#[tokio::test]
+async fn records_then_replays_without_network() {
+    let path = cassette_path("openai");
+    let server = server_replying(json!({
+        "id": "chatcmpl-1",
+        "model": "gpt-4o-mini",
+        "choices": [{"message": {"role": "assistant", "content": "Hi!"}, "finish_reason": "stop"}]
+    }))
+    .await;
+    let domain = server.uri();
+
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::record(&path)));
+    let provider = OpenAI::new("sk-secret".to_string())
+        .with_domain(domain.clone())
+        .with_client(client);
+    provider.chat_completion(request("Hello!")).await.unwrap();
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    assert!(!recorded.contains("sk-secret"));
+    assert!(recorded.contains("[REDACTED]"));
+
+    drop(server);
+
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::replay(&path).unwrap()));
+    let provider = OpenAI::new("sk-other".to_string())
+        .with_domain(domain)
+        .with_client(client);
+    let res = provider.chat_completion(request("Hello!")).await.unwrap();
+    assert_eq!(res.choices[0].message.content, "Hi!");
+
+    // Each recorded interaction is served once, and bodies must match exactly.
+    assert!(provider.chat_completion(request("Hello!")).await.is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn replay_fails_on_unmatched_request() {
+    let path = cassette_path("unmatched");
+    let server = server_replying(json!({
+        "id": "chatcmpl-1",
+        "model": "gpt-4o-mini",
+        "choices": [{"message": {"role": "assistant", "content": "Hi!"}, "finish_reason": "stop"}]
+    }))
+    .await;
+
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::record(&path)));
+    let provider = OpenAI::new("sk-secret".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    provider.chat_completion(request("Hello!")).await.unwrap();
+
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::replay(&path).unwrap()));
+    let provider = OpenAI::new("sk-secret".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    assert!(provider.chat_completion(request("Goodbye!")).await.is_err());
+
+    std::fs::remove_file(path).unwrap();
+}
+
+#[tokio::test]
+async fn redacts_api_key_in_query_string() {
+    let path = cassette_path("gemini");
+    let server = server_replying(json!({
+        "candidates": [{"content": {"role": "model", "parts": [{"text": "Hi!"}]}, "finishReason": "STOP"}]
+    }))
+    .await;
+
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::record(&path)));
+    let provider = Gemini::new("gm-secret".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    provider.chat_completion(request("Hello!")).await.unwrap();
+
+    let recorded = std::fs::read_to_string(&path).unwrap();
+    assert!(!recorded.contains("gm-secret"));
+    assert!(recorded.contains("key=[REDACTED]"));
+
+    // A different key still matches the redacted URL on replay.
+    let client = APIClient::new().with_cassette(Arc::new(Cassette::replay(&path).unwrap()));
+    let provider = Gemini::new("gm-other".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    let res = provider.chat_completion(request("Hello!")).await.unwrap();
+    assert_eq!(res.choices[0].message.content, "Hi!");
+
+    std::fs::remove_file(path).unwrap();
+}
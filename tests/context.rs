@@ -0,0 +1,185 @@
+#![cfg(feature = "openai")]
+
+use llm_api_rs::capabilities::{CapabilityCatalog, ModelCapabilities};
+use llm_api_rs::context::{estimate_tokens, ContextManager, TruncationStrategy};
+use llm_api_rs::core::client::APIClient;
+use llm_api_rs::{
+    ChatCompletionRequest, ChatMessage, LlmApiError, LlmProvider, MockProvider, OpenAI,
+};
+use std::sync::Arc;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn message(role: &str, content: &str) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: content.to_string(),
+        ..Default::default()
+    }
+}
+
+/// A system prompt followed by `turns` user/assistant pairs of ~100 tokens each.
+fn long_chat(turns: usize) -> ChatCompletionRequest {
+    let mut messages = vec![message("system", "Be helpful.")];
+    for turn in 0..turns {
+        messages.push(message(
+            "user",
+            &format!("question {} {}", turn, "x".repeat(400)),
+        ));
+        messages.push(message(
+            "assistant",
+            &format!("answer {} {}", turn, "y".repeat(400)),
+        ));
+    }
+    ChatCompletionRequest {
+        model: "tiny-model".to_string(),
+        messages,
+        max_tokens: Some(100),
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn leaves_fitting_and_unknown_models_alone() {
+    let manager = ContextManager::new(MockProvider::new(), TruncationStrategy::DropOldest)
+        .with_context_length("tiny-model", 10_000);
+    let request = long_chat(5);
+    assert_eq!(
+        manager.fit(request.clone()).await.unwrap().messages.len(),
+        11
+    );
+
+    let mut unknown = long_chat(50);
+    unknown.model = "unknown".to_string();
+    assert_eq!(manager.fit(unknown).await.unwrap().messages.len(), 101);
+    assert_eq!(
+        manager.context_length("gpt-4o-mini-2024-07-18"),
+        Some(128_000)
+    );
+
+    let catalog = CapabilityCatalog::builtin().with_capabilities(
+        "openai",
+        "gpt-4o-mini",
+        ModelCapabilities::new(64_000),
+    );
+    let manager = manager.with_capabilities(Arc::new(catalog));
+    assert_eq!(
+        manager.context_length("gpt-4o-mini-2024-07-18"),
+        Some(64_000)
+    );
+    assert_eq!(manager.context_length("tiny-model"), Some(10_000));
+}
+
+#[tokio::test]
+async fn drop_oldest_keeps_the_system_prompt_and_latest_turns() {
+    let manager = ContextManager::new(MockProvider::new(), TruncationStrategy::DropOldest)
+        .with_context_length("tiny-model", 600);
+
+    let fitted = manager.fit(long_chat(10)).await.unwrap();
+
+    assert!(estimate_tokens(&fitted) <= 500);
+    assert_eq!(fitted.messages[0].role, "system");
+    assert!(fitted
+        .messages
+        .last()
+        .unwrap()
+        .content
+        .starts_with("answer 9"));
+    assert!(fitted.messages.len() < 21);
+}
+
+#[tokio::test]
+async fn keep_last_keeps_system_plus_last_n() {
+    let manager = ContextManager::new(MockProvider::new(), TruncationStrategy::KeepLast(2))
+        .with_context_length("tiny-model", 2_000);
+
+    let fitted = manager.fit(long_chat(10)).await.unwrap();
+
+    let contents: Vec<_> = fitted.messages.iter().map(|m| &m.content[..8]).collect();
+    assert_eq!(contents, ["Be helpf", "question", "answer 9"]);
+}
+
+#[tokio::test]
+async fn summarize_replaces_older_turns_with_a_summary() {
+    let provider = MockProvider::new()
+        .with_reply("The user asked ten questions.")
+        .with_reply("done");
+    let manager = ContextManager::new(provider, TruncationStrategy::Summarize { keep_last: 2 })
+        .with_context_length("tiny-model", 2_000);
+
+    manager.chat_completion(long_chat(10)).await.unwrap();
+
+    let requests = manager.inner().requests();
+    assert_eq!(requests.len(), 2);
+    assert!(requests[0].messages[1].content.contains("question 0"));
+    let sent = &requests[1].messages;
+    assert_eq!(sent.len(), 3);
+    assert_eq!(sent[0].role, "system");
+    assert!(sent[0].content.starts_with("Be helpful.\n\n"));
+    assert!(sent[0].content.ends_with("The user asked ten questions."));
+    assert!(sent[2].content.starts_with("answer 9"));
+}
+
+#[cfg(feature = "anthropic")]
+#[tokio::test]
+async fn summaries_reach_anthropic_as_part_of_the_system_prompt() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(serde_json::json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-haiku-20241022",
+            "content": [{"type": "text", "text": "The user asked ten questions."}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 9, "output_tokens": 4}
+        })))
+        .mount(&server)
+        .await;
+    let provider = llm_api_rs::Anthropic::new("sk-ant-test".to_string()).with_domain(server.uri());
+    let manager = ContextManager::new(provider, TruncationStrategy::Summarize { keep_last: 2 })
+        .with_context_length("tiny-model", 2_000);
+
+    manager.chat_completion(long_chat(10)).await.unwrap();
+
+    let received = server.received_requests().await.unwrap();
+    assert_eq!(received.len(), 2);
+    let body: serde_json::Value = received[1].body_json().unwrap();
+    assert_eq!(
+        body["system"],
+        "Be helpful.\n\nSummary of the earlier conversation:\nThe user asked ten questions."
+    );
+    let roles: Vec<_> = body["messages"]
+        .as_array()
+        .unwrap()
+        .iter()
+        .map(|message| message["role"].as_str().unwrap())
+        .collect();
+    assert_eq!(roles, ["user", "assistant"]);
+}
+
+#[tokio::test]
+async fn reports_requests_that_cannot_fit() {
+    let manager = ContextManager::new(MockProvider::new(), TruncationStrategy::DropOldest)
+        .with_context_length("tiny-model", 150);
+
+    let error = manager.fit(long_chat(3)).await.unwrap_err();
+    assert!(matches!(error, LlmApiError::ContextLengthExceeded(_)));
+}
+
+#[tokio::test]
+async fn recognizes_context_length_errors_from_providers() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(400).set_body_string(
+            r#"{"error":{"message":"This model's maximum context length is 128000 tokens.","code":"context_length_exceeded"}}"#,
+        ))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk-test".to_string())
+        .with_domain(server.uri())
+        .with_client(APIClient::new());
+
+    let error = provider.chat_completion(long_chat(1)).await.unwrap_err();
+    assert!(matches!(error, LlmApiError::ContextLengthExceeded(_)));
+}
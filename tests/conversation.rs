@@ -0,0 +1,154 @@
+use llm_api_rs::conversation::Conversation;
+use llm_api_rs::{
+    ChatChoice, ChatCompletionResponse, ChatMessage, FunctionCall, LlmApiError, MockProvider, Tool,
+    ToolCall,
+};
+use serde_json::json;
+
+fn tool_call_response(id: &str, arguments: &str) -> ChatCompletionResponse {
+    ChatCompletionResponse {
+        id: "resp".to_string(),
+        model: "gpt-4o-mini".to_string(),
+        choices: vec![ChatChoice {
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                tool_calls: Some(vec![ToolCall {
+                    id: id.to_string(),
+                    call_type: "function".to_string(),
+                    function: FunctionCall {
+                        name: "get_weather".to_string(),
+                        arguments: arguments.to_string(),
+                    },
+                }]),
+                ..Default::default()
+            },
+            finish_reason: "tool_calls".to_string(),
+        }],
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn send_keeps_history_and_system_prompt() {
+    let provider = MockProvider::new()
+        .with_reply("Hi, Ann!")
+        .with_reply("You are Ann.");
+    let mut conversation =
+        Conversation::new("gpt-4o-mini").with_system_prompt("You are a helpful assistant.");
+
+    assert_eq!(
+        conversation.send(&provider, "I am Ann.").await.unwrap(),
+        "Hi, Ann!"
+    );
+    assert_eq!(
+        conversation.send(&provider, "Who am I?").await.unwrap(),
+        "You are Ann."
+    );
+
+    let second = &provider.requests()[1];
+    let roles: Vec<_> = second.messages.iter().map(|m| m.role.as_str()).collect();
+    assert_eq!(roles, ["system", "user", "assistant", "user"]);
+    assert_eq!(conversation.messages().len(), 4);
+}
+
+#[tokio::test]
+async fn failed_send_leaves_history_unchanged() {
+    let provider = MockProvider::new().with_error(LlmApiError::NetworkError("down".to_string()));
+    let mut conversation = Conversation::new("gpt-4o-mini");
+
+    assert!(conversation.send(&provider, "Hello").await.is_err());
+    assert!(conversation.messages().is_empty());
+}
+
+#[tokio::test]
+async fn send_refuses_tool_calls() {
+    let provider = MockProvider::new().with_response(tool_call_response("call_1", "{}"));
+    let mut conversation = Conversation::new("gpt-4o-mini");
+
+    let error = conversation.send(&provider, "Weather?").await.unwrap_err();
+
+    assert!(matches!(error, LlmApiError::ProviderError(_)));
+    assert!(conversation.messages().is_empty());
+}
+
+#[tokio::test]
+async fn history_leaves_out_reasoning() {
+    let provider = MockProvider::new().with_response(ChatCompletionResponse {
+        choices: vec![ChatChoice {
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content: "Hi!".to_string(),
+                reasoning: Some("A greeting.".to_string()),
+                ..Default::default()
+            },
+            finish_reason: "stop".to_string(),
+        }],
+        ..Default::default()
+    });
+    let mut conversation = Conversation::new("o4-mini");
+
+    conversation.send(&provider, "Hello").await.unwrap();
+
+    assert_eq!(conversation.messages()[1].content, "Hi!");
+    assert_eq!(conversation.messages()[1].reasoning, None);
+}
+
+#[tokio::test]
+async fn runs_tool_calls_until_the_model_answers() {
+    let provider = MockProvider::new()
+        .with_response(tool_call_response("call_1", r#"{"city":"Tokyo"}"#))
+        .with_reply("It is sunny in Tokyo.");
+    let mut conversation = Conversation::new("gpt-4o-mini").with_tools(vec![Tool::function(
+        "get_weather",
+        "Current weather for a city",
+        json!({"type": "object", "properties": {"city": {"type": "string"}}}),
+    )]);
+
+    let reply = conversation
+        .send_with_tools(&provider, "Weather in Tokyo?", 4, |call| async move {
+            assert_eq!(call.function.arguments, r#"{"city":"Tokyo"}"#);
+            Ok("sunny".to_string())
+        })
+        .await
+        .unwrap();
+
+    assert_eq!(reply, "It is sunny in Tokyo.");
+    let follow_up = &provider.requests()[1];
+    let tool_message = &follow_up.messages[2];
+    assert_eq!(tool_message.role, "tool");
+    assert_eq!(tool_message.tool_call_id.as_deref(), Some("call_1"));
+    assert_eq!(tool_message.content, "sunny");
+    assert!(follow_up.tools.is_some());
+}
+
+#[tokio::test]
+async fn tool_loop_stops_after_max_rounds() {
+    let provider = MockProvider::new()
+        .with_response(tool_call_response("call_1", "{}"))
+        .with_response(tool_call_response("call_2", "{}"));
+    let mut conversation = Conversation::new("gpt-4o-mini");
+
+    let error = conversation
+        .send_with_tools(&provider, "Loop", 2, |_| async { Ok("again".to_string()) })
+        .await
+        .unwrap_err();
+
+    assert!(matches!(error, LlmApiError::ProviderError(_)));
+    assert!(conversation.messages().is_empty());
+}
+
+#[tokio::test]
+async fn round_trips_through_json() {
+    let provider = MockProvider::new().with_reply("Noted.");
+    let mut conversation = Conversation::new("claude-sonnet-4-0")
+        .with_system_prompt("Be brief.")
+        .with_temperature(0.2);
+    conversation.send(&provider, "Remember 42.").await.unwrap();
+
+    let restored = Conversation::from_json(&conversation.to_json().unwrap()).unwrap();
+    assert_eq!(restored.model, "claude-sonnet-4-0");
+    assert_eq!(restored.system_prompt.as_deref(), Some("Be brief."));
+    assert_eq!(restored.temperature, Some(0.2));
+    assert_eq!(restored.messages().len(), 2);
+    assert_eq!(restored.messages()[1].content, "Noted.");
+}
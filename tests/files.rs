@@ -0,0 +1,282 @@
+#![cfg(all(feature = "openai", feature = "anthropic", feature = "gemini"))]
+
+use llm_api_rs::files::{FileProvider, FileUpload};
+use llm_api_rs::{
+    Anthropic, ChatCompletionRequest, ChatMessage, ContentPart, FileHandle, Gemini, LlmApiError,
+    LlmProvider, OpenAI,
+};
+use serde_json::json;
+use wiremock::matchers::{
+    body_json, body_string_contains, header, headers, method, path, query_param,
+};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn request_with(model: &str, file: FileHandle) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Summarize this.".to_string(),
+            parts: vec![ContentPart::File(file)],
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn pdf() -> FileUpload {
+    FileUpload::new("report.pdf", "application/pdf", b"%PDF-1.7".to_vec())
+}
+
+#[tokio::test]
+async fn openai_files_and_file_parts() {
+    let server = MockServer::start().await;
+    let file = json!({"id": "file-1", "object": "file", "filename": "report.pdf", "bytes": 8, "purpose": "user_data"});
+    Mock::given(method("POST"))
+        .and(path("/v1/files"))
+        .and(body_string_contains("name=\"purpose\"\r\n\r\nuser_data"))
+        .and(body_string_contains(
+            "filename=\"report.pdf\"\r\nContent-Type: application/pdf\r\n\r\n%PDF-1.7",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file.clone()))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/files"))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(json!({"object": "list", "data": [file]})),
+        )
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/v1/files/file-1"))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .set_body_json(json!({"id": "file-1", "object": "file", "deleted": true})),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_json(json!({
+            "model": "gpt-4o",
+            "messages": [{"role": "user", "content": [
+                {"type": "text", "text": "Summarize this."},
+                {"type": "file", "file": {"file_id": "file-1"}}
+            ]}],
+            "temperature": null,
+            "max_tokens": null
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "model": "gpt-4o",
+            "choices": [{"message": {"role": "assistant", "content": "A report."}, "finish_reason": "stop"}]
+        })))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let handle = provider.upload_file(pdf()).await.unwrap();
+    assert_eq!(
+        handle,
+        FileHandle {
+            provider: "openai".to_string(),
+            id: "file-1".to_string(),
+            filename: "report.pdf".to_string(),
+            mime_type: "application/pdf".to_string(),
+            size_bytes: 8,
+            uri: None,
+        }
+    );
+    assert_eq!(provider.list_files().await.unwrap(), vec![handle.clone()]);
+
+    let response = provider
+        .chat_completion(request_with("gpt-4o", handle))
+        .await
+        .unwrap();
+    assert_eq!(response.choices[0].message.content, "A report.");
+
+    provider.delete_file("file-1").await.unwrap();
+}
+
+#[tokio::test]
+async fn multipart_filenames_are_escaped() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/files"))
+        .and(body_string_contains(
+            "filename=\"q3 %22final%22%0D%0A.pdf\"\r\nContent-Type: application/pdf\r\n",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "file-2", "object": "file", "filename": "q3 \"final\"\r\n.pdf", "bytes": 8, "purpose": "user_data"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+    let upload = FileUpload::new(
+        "q3 \"final\"\r\n.pdf",
+        "application/pdf",
+        b"%PDF-1.7".to_vec(),
+    );
+
+    let handle = provider.upload_file(upload).await.unwrap();
+
+    assert_eq!(handle.id, "file-2");
+}
+
+#[tokio::test]
+async fn anthropic_files_use_the_beta_header() {
+    let server = MockServer::start().await;
+    let file = json!({
+        "id": "file_011", "type": "file", "filename": "report.pdf",
+        "mime_type": "application/pdf", "size_bytes": 8, "created_at": "2025-01-01T00:00:00Z"
+    });
+    Mock::given(method("POST"))
+        .and(path("/v1/files"))
+        .and(header("anthropic-beta", "files-api-2025-04-14"))
+        .and(body_string_contains(
+            "name=\"file\"; filename=\"report.pdf\"",
+        ))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file.clone()))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/files/file_011"))
+        .and(header("anthropic-beta", "files-api-2025-04-14"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(file))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("anthropic-beta", "files-api-2025-04-14"))
+        .and(body_json(json!({
+            "model": "claude-sonnet-4-0",
+            "max_tokens": 1000,
+            "temperature": null,
+            "messages": [{"role": "user", "content": [
+                {"type": "document", "source": {"type": "file", "file_id": "file_011"}},
+                {"type": "text", "text": "Summarize this."}
+            ]}]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1", "model": "claude-sonnet-4-0", "role": "assistant", "stop_reason": "end_turn",
+            "content": [{"type": "text", "text": "A report."}],
+            "usage": {"input_tokens": 100, "output_tokens": 3}
+        })))
+        .mount(&server)
+        .await;
+    let provider = Anthropic::new("sk-ant".to_string()).with_domain(server.uri());
+
+    let handle = provider.upload_file(pdf()).await.unwrap();
+    assert_eq!(handle.id, "file_011");
+    assert_eq!(provider.get_file("file_011").await.unwrap(), handle);
+
+    let response = provider
+        .chat_completion(request_with("claude-sonnet-4-0", handle))
+        .await
+        .unwrap();
+    assert_eq!(response.choices[0].message.content, "A report.");
+}
+
+#[tokio::test]
+async fn gemini_uploads_are_resumable() {
+    let server = MockServer::start().await;
+    let file = json!({
+        "name": "files/abc", "displayName": "report.pdf", "mimeType": "application/pdf",
+        "sizeBytes": "8", "uri": "https://generativelanguage.googleapis.com/v1beta/files/abc",
+        "state": "ACTIVE"
+    });
+    Mock::given(method("POST"))
+        .and(path("/upload/v1beta/files"))
+        .and(query_param("key", "g-key"))
+        .and(header("x-goog-upload-protocol", "resumable"))
+        .and(header("x-goog-upload-command", "start"))
+        .and(header("x-goog-upload-header-content-length", "8"))
+        .and(header(
+            "x-goog-upload-header-content-type",
+            "application/pdf",
+        ))
+        .and(body_json(json!({"file": {"display_name": "report.pdf"}})))
+        .respond_with(ResponseTemplate::new(200).insert_header(
+            "x-goog-upload-url",
+            format!("{}/upload-session/1", server.uri()),
+        ))
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/upload-session/1"))
+        .and(header("x-goog-upload-offset", "0"))
+        .and(headers("x-goog-upload-command", vec!["upload", "finalize"]))
+        .and(header("content-type", "application/pdf"))
+        .and(body_string_contains("%PDF-1.7"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"file": file})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:generateContent"))
+        .and(body_json(json!({
+            "contents": [{"role": "user", "parts": [
+                {"file_data": {"mime_type": "application/pdf", "file_uri": "https://generativelanguage.googleapis.com/v1beta/files/abc"}},
+                {"text": "Summarize this."}
+            ]}],
+            "generation_config": {}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{"content": {"role": "model", "parts": [{"text": "A report."}]}, "finishReason": "STOP"}]
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("DELETE"))
+        .and(path("/v1beta/files/abc"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let provider = Gemini::new("g-key".to_string()).with_domain(server.uri());
+
+    let handle = provider.upload_file(pdf()).await.unwrap();
+    assert_eq!(handle.id, "files/abc");
+    assert_eq!(handle.size_bytes, 8);
+
+    let response = provider
+        .chat_completion(request_with("gemini-2.0-flash", handle))
+        .await
+        .unwrap();
+    assert_eq!(response.choices[0].message.content, "A report.");
+
+    provider.delete_file("files/abc").await.unwrap();
+}
+
+#[tokio::test]
+async fn files_only_resolve_with_their_provider() {
+    let handle = FileHandle {
+        provider: "anthropic".to_string(),
+        id: "file_011".to_string(),
+        filename: "report.pdf".to_string(),
+        mime_type: "application/pdf".to_string(),
+        size_bytes: 8,
+        uri: None,
+    };
+    let error = OpenAI::new("sk".to_string())
+        .with_domain("http://127.0.0.1:9".to_string())
+        .chat_completion(request_with("gpt-4o", handle))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, LlmApiError::ConfigError(m) if m.contains("uploaded to anthropic")));
+}
+
+#[test]
+fn uploads_from_disk_guess_the_mime_type() {
+    let path = std::env::temp_dir().join(format!("llm-api-rs-files-{}.PNG", std::process::id()));
+    std::fs::write(&path, b"\x89PNG").unwrap();
+
+    let upload = FileUpload::from_path(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(upload.mime_type, "image/png");
+    assert_eq!(upload.data, b"\x89PNG");
+    assert!(upload.filename.starts_with("llm-api-rs-files-"));
+}
@@ -0,0 +1,154 @@
+#![cfg(all(feature = "openai", feature = "gemini", feature = "xai"))]
+
+use llm_api_rs::files::FileUpload;
+use llm_api_rs::images::{ImageData, ImageFormat, ImageProvider, ImageRequest};
+use llm_api_rs::{Gemini, LlmApiError, OpenAI, XAI};
+use serde_json::json;
+use wiremock::matchers::{body_json, body_string_contains, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+#[tokio::test]
+async fn openai_generates_images_with_options() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/images/generations"))
+        .and(header("authorization", "Bearer sk"))
+        .and(body_json(json!({
+            "model": "dall-e-3",
+            "prompt": "A lighthouse at dusk",
+            "n": 1,
+            "size": "1792x1024",
+            "quality": "hd",
+            "response_format": "url"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "created": 1700000000,
+            "data": [{"url": "https://images.example/1.png", "revised_prompt": "A red lighthouse at dusk"}]
+        })))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let request = ImageRequest::new("dall-e-3", "A lighthouse at dusk")
+        .with_count(1)
+        .with_size("1792x1024")
+        .with_quality("hd")
+        .with_format(ImageFormat::Url);
+    let response = provider.generate_image(request).await.unwrap();
+
+    assert_eq!(response.model, "dall-e-3");
+    let image = &response.images[0];
+    assert_eq!(
+        image.data,
+        ImageData::Url("https://images.example/1.png".to_string())
+    );
+    assert_eq!(
+        image.revised_prompt.as_deref(),
+        Some("A red lighthouse at dusk")
+    );
+    assert!(matches!(image.bytes(), Err(LlmApiError::ConfigError(_))));
+}
+
+#[tokio::test]
+async fn openai_edits_are_multipart() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/images/edits"))
+        .and(body_string_contains("name=\"model\"\r\n\r\ngpt-image-1"))
+        .and(body_string_contains("name=\"prompt\"\r\n\r\nAdd a boat"))
+        .and(body_string_contains(
+            "name=\"image\"; filename=\"coast.png\"\r\nContent-Type: image/png\r\n\r\n\x01\x02",
+        ))
+        .and(body_string_contains("name=\"mask\"; filename=\"mask.png\""))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "created": 1700000000,
+            "data": [{"b64_json": "aGVsbG8="}],
+            "output_format": "webp"
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let response = provider
+        .edit_image(
+            ImageRequest::new("gpt-image-1", "Add a boat"),
+            FileUpload::new("coast.png", "image/png", vec![1, 2]),
+            Some(FileUpload::new("mask.png", "image/png", vec![3])),
+        )
+        .await
+        .unwrap();
+
+    let image = &response.images[0];
+    assert_eq!(image.mime_type.as_deref(), Some("image/webp"));
+    assert_eq!(image.bytes().unwrap(), b"hello");
+}
+
+#[tokio::test]
+async fn xai_ignores_size_and_quality() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/images/generations"))
+        .and(body_json(json!({
+            "model": "grok-2-image",
+            "prompt": "A lighthouse at dusk",
+            "n": 2,
+            "response_format": "b64_json"
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [
+                {"b64_json": "aGVsbG8=", "revised_prompt": "A lighthouse"},
+                {"b64_json": "d29ybGQ=", "revised_prompt": "A lighthouse"}
+            ]
+        })))
+        .mount(&server)
+        .await;
+    let provider = XAI::new("xai".to_string()).with_domain(server.uri());
+
+    let request = ImageRequest::new("grok-2-image", "A lighthouse at dusk")
+        .with_count(2)
+        .with_size("1024x1024")
+        .with_quality("hd")
+        .with_format(ImageFormat::Base64);
+    let response = provider.generate_image(request).await.unwrap();
+
+    assert_eq!(response.images.len(), 2);
+    assert_eq!(response.images[1].bytes().unwrap(), b"world");
+    assert_eq!(response.images[1].mime_type.as_deref(), Some("image/jpeg"));
+}
+
+#[tokio::test]
+async fn imagen_predicts_base64_images() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/imagen-4.0-generate-001:predict"))
+        .and(query_param("key", "g-key"))
+        .and(body_json(json!({
+            "instances": [{"prompt": "A lighthouse at dusk"}],
+            "parameters": {"sampleCount": 2, "aspectRatio": "16:9"}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "predictions": [
+                {"bytesBase64Encoded": "aGVsbG8=", "mimeType": "image/png"},
+                {"raiFilteredReason": "blocked"}
+            ]
+        })))
+        .mount(&server)
+        .await;
+    let provider = Gemini::new("g-key".to_string()).with_domain(server.uri());
+
+    let request = ImageRequest::new("imagen-4.0-generate-001", "A lighthouse at dusk")
+        .with_count(2)
+        .with_size("16:9");
+    let response = provider.generate_image(request.clone()).await.unwrap();
+
+    assert_eq!(response.images.len(), 1);
+    assert_eq!(response.images[0].mime_type.as_deref(), Some("image/png"));
+    assert_eq!(response.images[0].bytes().unwrap(), b"hello");
+
+    let error = provider
+        .generate_image(request.with_format(ImageFormat::Url))
+        .await
+        .unwrap_err();
+    assert!(matches!(error, LlmApiError::ConfigError(_)));
+}
@@ -0,0 +1,152 @@
+#![cfg(feature = "openai")]
+
+use async_trait::async_trait;
+use llm_api_rs::core::client::APIClient;
+use llm_api_rs::core::middleware::{HttpRequest, HttpResponse, Middleware};
+use llm_api_rs::{ChatCompletionRequest, ChatMessage, LlmApiError, LlmProvider, OpenAI};
+use reqwest::header::{HeaderName, HeaderValue};
+use serde_json::json;
+use std::sync::{Arc, Mutex};
+use wiremock::matchers::{body_json, header, method};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn request(content: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "gpt-4o-mini".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn reply() -> serde_json::Value {
+    json!({
+        "id": "chatcmpl-1",
+        "model": "gpt-4o-mini",
+        "choices": [{"message": {"role": "assistant", "content": "Hi!"}, "finish_reason": "stop"}]
+    })
+}
+
+#[derive(Default)]
+struct Recorder {
+    events: Mutex<Vec<String>>,
+}
+
+#[async_trait]
+impl Middleware for Recorder {
+    async fn before_request(&self, request: &mut HttpRequest) -> Result<(), LlmApiError> {
+        request.headers.insert(
+            HeaderName::from_static("traceparent"),
+            HeaderValue::from_static("00-trace-span-01"),
+        );
+        request.body["messages"][0]["content"] = json!("[email]");
+        self.events.lock().unwrap().push("before".to_string());
+        Ok(())
+    }
+
+    async fn after_response(
+        &self,
+        _request: &HttpRequest,
+        response: &mut HttpResponse,
+    ) -> Result<(), LlmApiError> {
+        let remaining = response.headers["x-ratelimit-remaining-requests"]
+            .to_str()
+            .unwrap()
+            .to_string();
+        self.events
+            .lock()
+            .unwrap()
+            .push(format!("after {}", remaining));
+        Ok(())
+    }
+
+    async fn on_error(&self, _request: &HttpRequest, error: &LlmApiError) {
+        self.events.lock().unwrap().push(format!("error {}", error));
+    }
+}
+
+struct Budget;
+
+#[async_trait]
+impl Middleware for Budget {
+    async fn before_request(&self, _request: &mut HttpRequest) -> Result<(), LlmApiError> {
+        Err(LlmApiError::ConfigError("budget exhausted".to_string()))
+    }
+}
+
+#[tokio::test]
+async fn hooks_can_rewrite_request_and_read_response_headers() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(header("traceparent", "00-trace-span-01"))
+        .and(body_json(json!({
+            "model": "gpt-4o-mini",
+            "messages": [{"role": "user", "content": "[email]"}],
+            "temperature": null,
+            "max_tokens": null
+        })))
+        .respond_with(
+            ResponseTemplate::new(200)
+                .insert_header("x-ratelimit-remaining-requests", "99")
+                .set_body_json(reply()),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let recorder = Arc::new(Recorder::default());
+    let client = APIClient::new().with_middleware(recorder.clone());
+    let provider = OpenAI::new("sk-test".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    provider
+        .chat_completion(request("me@example.com"))
+        .await
+        .unwrap();
+
+    assert_eq!(*recorder.events.lock().unwrap(), ["before", "after 99"]);
+}
+
+#[tokio::test]
+async fn on_error_sees_failed_calls() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(
+            ResponseTemplate::new(500)
+                .insert_header("x-ratelimit-remaining-requests", "0")
+                .set_body_string("overloaded"),
+        )
+        .mount(&server)
+        .await;
+
+    let recorder = Arc::new(Recorder::default());
+    let client = APIClient::new().with_middleware(recorder.clone());
+    let provider = OpenAI::new("sk-test".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    assert!(provider.chat_completion(request("hi")).await.is_err());
+
+    let events = recorder.events.lock().unwrap();
+    assert_eq!(events[1], "after 0");
+    assert_eq!(events[2], "error API error (500): overloaded");
+}
+
+#[tokio::test]
+async fn before_request_error_aborts_the_call() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(reply()))
+        .expect(0)
+        .mount(&server)
+        .await;
+
+    let client = APIClient::new().with_middleware(Arc::new(Budget));
+    let provider = OpenAI::new("sk-test".to_string())
+        .with_domain(server.uri())
+        .with_client(client);
+    let err = provider.chat_completion(request("hi")).await.unwrap_err();
+    assert!(matches!(err, LlmApiError::ConfigError(msg) if msg == "budget exhausted"));
+}
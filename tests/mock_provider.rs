@@ -0,0 +1,43 @@
+use llm_api_rs::{ChatCompletionRequest, ChatMessage, LlmApiError, LlmProvider, MockProvider};
+
+fn request(content: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "test-model".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: content.to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn returns_scripted_results_in_order() {
+    let provider = MockProvider::new()
+        .with_reply("first")
+        .with_error(LlmApiError::ProviderError("overloaded".to_string()))
+        .with_reply("second");
+
+    let res = provider.chat_completion(request("a")).await.unwrap();
+    assert_eq!(res.choices[0].message.content, "first");
+
+    let err = provider.chat_completion(request("b")).await.unwrap_err();
+    assert!(matches!(err, LlmApiError::ProviderError(msg) if msg == "overloaded"));
+
+    let res = provider.chat_completion(request("c")).await.unwrap();
+    assert_eq!(res.choices[0].message.content, "second");
+}
+
+#[tokio::test]
+async fn records_requests_and_fails_when_script_is_exhausted() {
+    let provider = MockProvider::new().with_reply("only");
+
+    provider.chat_completion(request("hello")).await.unwrap();
+    assert!(provider.chat_completion(request("again")).await.is_err());
+
+    let requests = provider.requests();
+    assert_eq!(requests.len(), 2);
+    assert_eq!(requests[0].messages[0].content, "hello");
+    assert_eq!(requests[1].messages[0].content, "again");
+}
@@ -0,0 +1,182 @@
+#![cfg(all(
+    feature = "openai",
+    feature = "anthropic",
+    feature = "gemini",
+    feature = "ollama",
+    feature = "deepseek",
+    feature = "xai"
+))]
+
+use llm_api_rs::providers::ollama::Ollama;
+use llm_api_rs::{
+    Anthropic, DeepSeek, Gemini, LlmApiError, LlmProvider, MockProvider, ModelInfo, OpenAI, XAI,
+};
+use serde_json::json;
+use wiremock::matchers::{header, method, path, query_param, query_param_is_missing};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn openai_models() -> serde_json::Value {
+    json!({
+        "object": "list",
+        "data": [
+            {"id": "model-a", "object": "model", "created": 1686935002, "owned_by": "system"},
+            {"id": "model-b", "object": "model", "created": 1700000000, "owned_by": "org"}
+        ]
+    })
+}
+
+#[tokio::test]
+async fn openai_compatible_providers_list_models() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(openai_models()))
+        .expect(3)
+        .mount(&server)
+        .await;
+
+    let providers: Vec<(&str, Box<dyn LlmProvider + Send + Sync>)> = vec![
+        (
+            "openai",
+            Box::new(OpenAI::new("sk".to_string()).with_domain(server.uri())),
+        ),
+        (
+            "deepseek",
+            Box::new(DeepSeek::new("sk".to_string()).with_domain(server.uri())),
+        ),
+        (
+            "xai",
+            Box::new(XAI::new("sk".to_string()).with_domain(server.uri())),
+        ),
+    ];
+    for (name, provider) in providers {
+        let models = provider.list_models().await.unwrap();
+        assert_eq!(
+            models[0],
+            ModelInfo {
+                id: "model-a".to_string(),
+                provider: name.to_string(),
+                owned_by: Some("system".to_string()),
+                created: Some(1686935002),
+                ..Default::default()
+            }
+        );
+        assert_eq!(models.len(), 2);
+    }
+}
+
+#[tokio::test]
+async fn anthropic_follows_pages() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .and(header("x-api-key", "sk-ant"))
+        .and(query_param_is_missing("after_id"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{"type": "model", "id": "claude-sonnet-4-0", "display_name": "Claude Sonnet 4", "created_at": "2025-02-19T00:00:00Z"}],
+            "has_more": true,
+            "first_id": "claude-sonnet-4-0",
+            "last_id": "claude-sonnet-4-0"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1/models"))
+        .and(query_param("after_id", "claude-sonnet-4-0"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "data": [{"type": "model", "id": "claude-3-5-haiku-latest", "display_name": "Claude Haiku 3.5", "created_at": "2024-10-22T00:00:00Z"}],
+            "has_more": false,
+            "first_id": "claude-3-5-haiku-latest",
+            "last_id": "claude-3-5-haiku-latest"
+        })))
+        .mount(&server)
+        .await;
+    let provider = Anthropic::new("sk-ant".to_string()).with_domain(server.uri());
+
+    let models = provider.list_models().await.unwrap();
+
+    assert_eq!(models.len(), 2);
+    assert_eq!(models[0].display_name.as_deref(), Some("Claude Sonnet 4"));
+    assert_eq!(models[0].created, Some(1739923200));
+    assert_eq!(models[1].id, "claude-3-5-haiku-latest");
+}
+
+#[tokio::test]
+async fn gemini_reports_limits_and_methods() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/v1beta/models"))
+        .and(query_param("key", "g-key"))
+        .and(query_param_is_missing("pageToken"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{
+                "name": "models/gemini-2.0-flash",
+                "displayName": "Gemini 2.0 Flash",
+                "inputTokenLimit": 1048576,
+                "outputTokenLimit": 8192,
+                "supportedGenerationMethods": ["generateContent", "countTokens"]
+            }],
+            "nextPageToken": "Cg+8/ZW1i==&v=2"
+        })))
+        .mount(&server)
+        .await;
+    Mock::given(method("GET"))
+        .and(path("/v1beta/models"))
+        .and(query_param("pageToken", "Cg+8/ZW1i==&v=2"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{
+                "name": "models/text-embedding-004",
+                "supportedGenerationMethods": ["embedContent"]
+            }]
+        })))
+        .mount(&server)
+        .await;
+    let provider = Gemini::new("g-key".to_string()).with_domain(server.uri());
+
+    let models = provider.list_models().await.unwrap();
+
+    assert_eq!(
+        models[0],
+        ModelInfo {
+            id: "gemini-2.0-flash".to_string(),
+            provider: "gemini".to_string(),
+            display_name: Some("Gemini 2.0 Flash".to_string()),
+            owned_by: Some("google".to_string()),
+            created: None,
+            input_token_limit: Some(1048576),
+            output_token_limit: Some(8192),
+            generation_methods: vec!["generateContent".to_string(), "countTokens".to_string()],
+        }
+    );
+    assert_eq!(models[1].id, "text-embedding-004");
+}
+
+#[tokio::test]
+async fn ollama_lists_local_models() {
+    let server = MockServer::start().await;
+    Mock::given(method("GET"))
+        .and(path("/api/tags"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "models": [{
+                "name": "llama3.2:latest",
+                "model": "llama3.2:latest",
+                "modified_at": "2024-05-01T10:11:12.123456789-07:00",
+                "size": 2019393189u64,
+                "details": {"family": "llama", "parameter_size": "3.2B"}
+            }]
+        })))
+        .mount(&server)
+        .await;
+    let provider = Ollama::new(server.uri());
+
+    let models = provider.list_models().await.unwrap();
+
+    assert_eq!(models[0].id, "llama3.2:latest");
+    assert_eq!(models[0].created, Some(1714583472));
+}
+
+#[tokio::test]
+async fn providers_without_a_listing_fail() {
+    let error = MockProvider::new().list_models().await.unwrap_err();
+    assert!(matches!(error, LlmApiError::ProviderError(_)));
+}
@@ -0,0 +1,162 @@
+#![cfg(feature = "openai")]
+
+use llm_api_rs::moderation::{ModerationProvider, ModerationRequest};
+use llm_api_rs::{
+    ChatCompletionRequest, ChatMessage, LlmApiError, LlmProvider, MockProvider, ModeratedProvider,
+    OpenAI,
+};
+use serde_json::json;
+use wiremock::matchers::{body_json, method, path};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn scores(harassment: f64) -> serde_json::Value {
+    json!({
+        "harassment": harassment, "harassment/threatening": 0.001, "hate": 0.002,
+        "hate/threatening": 0.0, "illicit": 0.0, "illicit/violent": 0.0, "self-harm": 0.0,
+        "self-harm/intent": 0.0, "self-harm/instructions": 0.0, "sexual": 0.0,
+        "sexual/minors": 0.0, "violence": 0.01, "violence/graphic": 0.0
+    })
+}
+
+fn categories(harassment: bool) -> serde_json::Value {
+    json!({
+        "harassment": harassment, "harassment/threatening": false, "hate": false,
+        "hate/threatening": false, "illicit": null, "illicit/violent": null, "self-harm": false,
+        "self-harm/intent": false, "self-harm/instructions": false, "sexual": false,
+        "sexual/minors": false, "violence": false, "violence/graphic": false
+    })
+}
+
+fn moderation(flagged: bool) -> serde_json::Value {
+    json!({
+        "id": "modr-1",
+        "model": "omni-moderation-latest",
+        "results": [{
+            "flagged": flagged,
+            "categories": categories(flagged),
+            "category_scores": scores(if flagged { 0.93 } else { 0.01 })
+        }]
+    })
+}
+
+fn message(role: &str, content: &str) -> ChatMessage {
+    ChatMessage {
+        role: role.to_string(),
+        content: content.to_string(),
+        ..Default::default()
+    }
+}
+
+fn chat(messages: Vec<ChatMessage>) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "gpt-4o".to_string(),
+        messages,
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn openai_moderates_text() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/moderations"))
+        .and(body_json(json!({"input": ["You are awful"]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(moderation(true)))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let response = provider
+        .moderate(ModerationRequest::new().with_text("You are awful"))
+        .await
+        .unwrap();
+
+    assert!(response.flagged());
+    let result = &response.results[0];
+    assert!(result.categories.harassment);
+    assert!(!result.categories.illicit);
+    assert_eq!(result.scores.harassment, 0.93);
+    assert_eq!(result.flagged_categories(), vec!["harassment"]);
+}
+
+#[tokio::test]
+async fn openai_moderates_images_as_one_input() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/moderations"))
+        .and(body_json(json!({
+            "model": "omni-moderation-latest",
+            "input": [
+                {"type": "text", "text": "Is this fine?"},
+                {"type": "image_url", "image_url": {"url": "https://example.com/cat.png"}}
+            ]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(moderation(false)))
+        .mount(&server)
+        .await;
+    let provider = OpenAI::new("sk".to_string()).with_domain(server.uri());
+
+    let request = ModerationRequest::new()
+        .with_model("omni-moderation-latest")
+        .with_text("Is this fine?")
+        .with_image_url("https://example.com/cat.png");
+    let response = provider.moderate(request).await.unwrap();
+
+    assert!(!response.flagged());
+    assert!(response.results[0].flagged_categories().is_empty());
+}
+
+#[tokio::test]
+async fn guard_blocks_flagged_input() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/moderations"))
+        .and(body_json(json!({"input": ["You are awful"]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(moderation(true)))
+        .mount(&server)
+        .await;
+    let moderator = OpenAI::new("sk".to_string()).with_domain(server.uri());
+    let guarded = ModeratedProvider::new(MockProvider::new().with_reply("Hi"), moderator);
+
+    let error = guarded
+        .chat_completion(chat(vec![
+            message("user", "Hello"),
+            message("assistant", "Hi!"),
+            message("user", "You are awful"),
+        ]))
+        .await
+        .unwrap_err();
+
+    assert!(
+        matches!(error, LlmApiError::ContentFlagged { categories } if categories == vec!["harassment"])
+    );
+    assert!(guarded.inner().requests().is_empty());
+}
+
+#[tokio::test]
+async fn guard_can_check_every_user_message() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/moderations"))
+        .and(body_json(json!({"input": ["Hello", "How are you?"]})))
+        .respond_with(ResponseTemplate::new(200).set_body_json(moderation(false)))
+        .expect(1)
+        .mount(&server)
+        .await;
+    let moderator = OpenAI::new("sk".to_string()).with_domain(server.uri());
+    let guarded = ModeratedProvider::new(MockProvider::new().with_reply("Fine."), moderator)
+        .with_all_messages(true);
+
+    let response = guarded
+        .chat_completion(chat(vec![
+            message("system", "Be brief."),
+            message("user", "Hello"),
+            message("assistant", "Hi!"),
+            message("user", "How are you?"),
+        ]))
+        .await
+        .unwrap();
+
+    assert_eq!(response.choices[0].message.content, "Fine.");
+    assert_eq!(guarded.inner().requests().len(), 1);
+}
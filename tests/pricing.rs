@@ -0,0 +1,157 @@
+use llm_api_rs::pricing::{ModelPrice, PricingTable, SpendTracker};
+use llm_api_rs::{ChatChoice, ChatCompletionResponse, ChatMessage, ChatUsage};
+
+fn response(model: &str, usage: ChatUsage) -> ChatCompletionResponse {
+    ChatCompletionResponse {
+        id: "id".to_string(),
+        model: model.to_string(),
+        choices: vec![ChatChoice {
+            message: ChatMessage::default(),
+            finish_reason: "stop".to_string(),
+        }],
+        usage: Some(usage),
+        ..Default::default()
+    }
+}
+
+fn usage(input: u32, output: u32) -> ChatUsage {
+    ChatUsage {
+        input_tokens: Some(input),
+        output_tokens: Some(output),
+        ..Default::default()
+    }
+}
+
+fn assert_close(actual: f64, expected: f64) {
+    assert!(
+        (actual - expected).abs() < 1e-9,
+        "{} != {}",
+        actual,
+        expected
+    );
+}
+
+#[test]
+fn prices_cached_and_reasoning_tokens_separately() {
+    let price = ModelPrice::new(2.0, 8.0)
+        .with_cached_input(0.5)
+        .with_reasoning(16.0);
+    let usage = ChatUsage {
+        input_tokens: Some(1_000_000),
+        output_tokens: Some(1_000_000),
+        cached_input_tokens: Some(400_000),
+        reasoning_tokens: Some(250_000),
+        ..Default::default()
+    };
+    // 600k * 2 + 400k * 0.5 + 750k * 8 + 250k * 16, per million.
+    assert_close(price.cost(&usage), 1.2 + 0.2 + 6.0 + 4.0);
+}
+
+#[test]
+fn prices_anthropic_cache_writes_above_input() {
+    let table = PricingTable::builtin();
+    let price = table
+        .price("anthropic", "claude-sonnet-4-20250514")
+        .unwrap();
+    assert_eq!(price.cache_write, Some(3.75));
+
+    let usage = ChatUsage {
+        input_tokens: Some(1_000_000),
+        output_tokens: Some(0),
+        cached_input_tokens: Some(200_000),
+        cache_write_tokens: Some(500_000),
+        ..Default::default()
+    };
+    // 300k * 3 + 200k * 0.3 + 500k * 3.75, per million.
+    assert_close(price.cost(&usage), 0.9 + 0.06 + 1.875);
+}
+
+#[test]
+fn matches_snapshots_but_not_other_models() {
+    let table = PricingTable::builtin();
+    let mini = table.price("openai", "gpt-4o-mini-2024-07-18").unwrap();
+    assert_close(mini.input, 0.15);
+    let full = table.price("openai", "gpt-4o-2024-08-06").unwrap();
+    assert_close(full.input, 2.50);
+    assert!(table.price("openai", "unknown-model").is_none());
+    assert!(table.price("nobody", "gpt-4o").is_none());
+
+    let pro = table.price("openai", "o3-pro-2025-06-10").unwrap();
+    assert_close(pro.input, 20.00);
+    assert_close(pro.output, 80.00);
+    let lite = table
+        .price("gemini", "gemini-2.5-flash-lite-preview-06-17")
+        .unwrap();
+    assert_close(lite.input, 0.10);
+    assert_close(lite.output, 0.40);
+    assert!(table.price("openai", "o3-deep-research").is_none());
+}
+
+#[test]
+fn json_overrides_replace_builtin_prices() {
+    let path = std::env::temp_dir().join(format!("llm-api-rs-prices-{}.json", std::process::id()));
+    std::fs::write(
+        &path,
+        r#"{"openai": {"gpt-4o": {"input": 1.0, "output": 2.0}}, "ollama": {"llama3": {"input": 0.0, "output": 0.0}}}"#,
+    )
+    .unwrap();
+
+    let mut table = PricingTable::builtin();
+    table.load_overrides(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert_eq!(
+        table.price("openai", "gpt-4o"),
+        Some(&ModelPrice::new(1.0, 2.0))
+    );
+    assert!(table.price("ollama", "llama3:8b").is_some());
+    // Untouched entries keep their built-in price.
+    assert_close(table.price("openai", "gpt-4o-mini").unwrap().input, 0.15);
+}
+
+#[cfg(feature = "toml")]
+#[test]
+fn toml_overrides() {
+    let path = std::env::temp_dir().join(format!("llm-api-rs-prices-{}.toml", std::process::id()));
+    std::fs::write(
+        &path,
+        "[anthropic.\"claude-sonnet-4\"]\ninput = 3.0\ncached_input = 0.3\noutput = 15.0\n",
+    )
+    .unwrap();
+
+    let mut table = PricingTable::empty();
+    table.load_overrides(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    let price = table
+        .price("anthropic", "claude-sonnet-4-20250514")
+        .unwrap();
+    assert_eq!(price.cached_input, Some(0.3));
+}
+
+#[test]
+fn aggregates_spend_per_tag() {
+    let pricing = PricingTable::empty().with_price("openai", "gpt-4o", ModelPrice::new(2.0, 10.0));
+    let tracker = SpendTracker::new(pricing);
+
+    let cost = tracker.record(
+        "search",
+        "openai",
+        &response("gpt-4o", usage(500_000, 100_000)),
+    );
+    assert_close(cost.unwrap(), 1.0 + 1.0);
+    tracker.record("search", "openai", &response("gpt-4o", usage(1_000_000, 0)));
+    tracker.record("chat", "openai", &response("other", usage(10, 10)));
+
+    let search = tracker.spend("search");
+    assert_eq!(search.calls, 2);
+    assert_eq!(search.input_tokens, 1_500_000);
+    assert_close(search.cost, 4.0);
+
+    let chat = tracker.spend("chat");
+    assert_eq!(chat.unpriced_calls, 1);
+    assert_close(chat.cost, 0.0);
+
+    assert_close(tracker.total_cost(), 4.0);
+    assert_eq!(tracker.totals().len(), 2);
+}
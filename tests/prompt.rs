@@ -0,0 +1,212 @@
+use llm_api_rs::prompt::PromptTemplate;
+use llm_api_rs::LlmApiError;
+use serde::Serialize;
+use serde_json::json;
+
+const SENTIMENT: &str = "\
+--- system
+You classify the sentiment of {{ product }} reviews.
+{{> format }}
+
+--- examples shots
+--- user
+{{ review }}
+--- assistant
+{{ label }}
+--- end
+
+--- user
+{{review}}
+";
+
+#[derive(Serialize)]
+struct Shot {
+    review: &'static str,
+    label: &'static str,
+}
+
+#[derive(Serialize)]
+struct Sentiment {
+    product: &'static str,
+    review: &'static str,
+    shots: Vec<Shot>,
+}
+
+fn contents(request: &llm_api_rs::ChatCompletionRequest) -> Vec<(&str, &str)> {
+    request
+        .messages
+        .iter()
+        .map(|m| (m.role.as_str(), m.content.as_str()))
+        .collect()
+}
+
+#[test]
+fn renders_typed_variables_examples_and_partials() {
+    let template = PromptTemplate::parse(SENTIMENT)
+        .unwrap()
+        .with_partial("format", "Answer with one word: {{> labels }}.")
+        .with_partial("labels", "positive or negative");
+    let variables = Sentiment {
+        product: "headphone",
+        review: "Too quiet.",
+        shots: vec![
+            Shot {
+                review: "Great bass!",
+                label: "positive",
+            },
+            Shot {
+                review: "Broke in a week.",
+                label: "negative",
+            },
+        ],
+    };
+
+    let request = template.render("gpt-4o-mini", &variables).unwrap();
+
+    assert_eq!(request.model, "gpt-4o-mini");
+    assert_eq!(
+        contents(&request),
+        [
+            (
+                "system",
+                "You classify the sentiment of headphone reviews.\nAnswer with one word: positive or negative."
+            ),
+            ("user", "Great bass!"),
+            ("assistant", "positive"),
+            ("user", "Broke in a week."),
+            ("assistant", "negative"),
+            ("user", "Too quiet."),
+        ]
+    );
+}
+
+#[test]
+fn reports_every_missing_variable() {
+    let template = PromptTemplate::parse(SENTIMENT).unwrap();
+    assert_eq!(
+        template.variables().into_iter().collect::<Vec<_>>(),
+        ["product", "review", "shots"]
+    );
+
+    let error = template
+        .render_messages(&json!({"review": "Fine.", "product": null}))
+        .unwrap_err();
+    match error {
+        LlmApiError::ConfigError(message) => assert_eq!(
+            message,
+            "missing prompt variables: > format, product, shots"
+        ),
+        other => panic!("unexpected error: {:?}", other),
+    }
+}
+
+#[test]
+fn dotted_names_and_non_string_values() {
+    let template = PromptTemplate::parse(
+        "--- user\nHi {{ user.name }}, you have {{ user.unread }} messages in {{ folders }}.",
+    )
+    .unwrap();
+
+    let messages = template
+        .render_messages(&json!({
+            "user": {"name": "Ann", "unread": 3},
+            "folders": ["inbox", "spam"]
+        }))
+        .unwrap();
+
+    assert_eq!(
+        messages[0].content,
+        r#"Hi Ann, you have 3 messages in ["inbox","spam"]."#
+    );
+}
+
+#[test]
+fn rejects_malformed_templates() {
+    for source in [
+        "Hello\n--- user\nHi",
+        "--- narrator\nOnce upon a time",
+        "--- examples shots\n--- user\n{{ q }}",
+        "--- end",
+        "--- user\nHi\n--- system\nBe brief.",
+        "--- examples shots\n--- system\nBe brief.\n--- end",
+    ] {
+        assert!(
+            matches!(
+                PromptTemplate::parse(source),
+                Err(LlmApiError::ConfigError(_))
+            ),
+            "{:?}",
+            source
+        );
+    }
+}
+
+#[test]
+fn loads_templates_from_files() {
+    let path = std::env::temp_dir().join(format!("llm-api-rs-prompt-{}.txt", std::process::id()));
+    std::fs::write(&path, SENTIMENT).unwrap();
+
+    let template = PromptTemplate::from_file(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+
+    assert!(template.variables().contains("shots"));
+    assert!(PromptTemplate::from_file(&path).is_err());
+}
+
+#[cfg(feature = "anthropic")]
+#[tokio::test]
+async fn rendered_system_sections_reach_anthropic_as_the_system_prompt() {
+    use llm_api_rs::{Anthropic, LlmProvider};
+    use wiremock::matchers::{body_json, method, path};
+    use wiremock::{Mock, MockServer, ResponseTemplate};
+
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_json(json!({
+            "system": "You classify the sentiment of headphone reviews.\nAnswer with one word.",
+            "messages": [
+                {"role": "user", "content": "Great bass!"},
+                {"role": "assistant", "content": "positive"},
+                {"role": "user", "content": "Too quiet."}
+            ],
+            "model": "claude-3-5-haiku-latest",
+            "max_tokens": 1000,
+            "temperature": null
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-haiku-20241022",
+            "content": [{"type": "text", "text": "negative"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 30, "output_tokens": 1}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let template = PromptTemplate::parse(SENTIMENT)
+        .unwrap()
+        .with_partial("format", "Answer with one word.");
+    let request = template
+        .render(
+            "claude-3-5-haiku-latest",
+            &Sentiment {
+                product: "headphone",
+                review: "Too quiet.",
+                shots: vec![Shot {
+                    review: "Great bass!",
+                    label: "positive",
+                }],
+            },
+        )
+        .unwrap();
+    let response = Anthropic::new("sk-ant-test".to_string())
+        .with_domain(server.uri())
+        .chat_completion(request)
+        .await
+        .unwrap();
+    assert_eq!(response.choices[0].message.content, "negative");
+}
@@ -0,0 +1,1072 @@
+#![cfg(all(
+    feature = "openai",
+    feature = "anthropic",
+    feature = "gemini",
+    feature = "ollama",
+    feature = "deepseek",
+    feature = "xai",
+    feature = "mistral",
+    feature = "cohere"
+))]
+
+// Provider wire-format tests against a local HTTP server.
+// Each test pins the exact JSON a provider sends and checks how a canned reply is parsed.
+
+use llm_api_rs::conversation::Conversation;
+use llm_api_rs::providers::ollama::Ollama;
+use llm_api_rs::providers::openai::ResponsesOptions;
+use llm_api_rs::{
+    Anthropic, ChatCompletionRequest, ChatMessage, Cohere, ContentPart, DeepSeek, FileHandle,
+    FunctionCall, Gemini, LlmApiError, LlmProvider, Mistral, OpenAI, Tool, ToolCall, XAI,
+};
+use serde_json::json;
+use wiremock::matchers::{body_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn request(model: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Hello!".to_string(),
+            ..Default::default()
+        }],
+        temperature: Some(0.5),
+        max_tokens: Some(50),
+        ..Default::default()
+    }
+}
+
+fn weather_parameters() -> serde_json::Value {
+    json!({"type": "object", "properties": {"city": {"type": "string"}}})
+}
+
+fn weather_tool() -> Tool {
+    Tool::function(
+        "get_weather",
+        "Current weather for a city",
+        weather_parameters(),
+    )
+}
+
+#[tokio::test]
+async fn openai_chat_completion() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer sk-test"))
+        .and(body_json(json!({
+            "model": "gpt-4o-mini",
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "temperature": 0.5,
+            "max_tokens": 50
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-1",
+            "object": "chat.completion",
+            "model": "gpt-4o-mini-2024-07-18",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hi there!"},
+                "finish_reason": "stop"
+            }],
+            "usage": {
+                "prompt_tokens": 2006,
+                "completion_tokens": 300,
+                "prompt_tokens_details": {"cached_tokens": 1920},
+                "completion_tokens_details": {"reasoning_tokens": 0}
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::new("sk-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(request("gpt-4o-mini"))
+        .await
+        .unwrap();
+
+    assert_eq!(res.id, "chatcmpl-1");
+    assert_eq!(res.model, "gpt-4o-mini-2024-07-18");
+    assert_eq!(res.choices[0].message.role, "assistant");
+    assert_eq!(res.choices[0].message.content, "Hi there!");
+    assert_eq!(res.choices[0].finish_reason, "stop");
+    let usage = res.usage.unwrap();
+    assert_eq!(usage.input_tokens, Some(2006));
+    assert_eq!(usage.output_tokens, Some(300));
+    assert_eq!(usage.cached_input_tokens, Some(1920));
+}
+
+#[tokio::test]
+async fn openai_chat_tool_call() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_json(json!({
+            "model": "gpt-4o-mini",
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "temperature": 0.5,
+            "max_tokens": 50,
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Current weather for a city",
+                    "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+                }
+            }]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-2",
+            "model": "gpt-4o-mini",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [{
+                        "id": "call_1",
+                        "type": "function",
+                        "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                    }]
+                },
+                "finish_reason": "tool_calls"
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::new("sk-test".to_string()).with_domain(server.uri());
+    let mut req = request("gpt-4o-mini");
+    req.tools = Some(vec![weather_tool()]);
+    let res = client.chat_completion(req).await.unwrap();
+
+    let message = &res.choices[0].message;
+    assert_eq!(message.content, "");
+    let call = &message.tool_calls.as_ref().unwrap()[0];
+    assert_eq!(call.id, "call_1");
+    assert_eq!(call.function.arguments, r#"{"city":"Paris"}"#);
+    assert_eq!(res.choices[0].finish_reason, "tool_calls");
+}
+
+#[tokio::test]
+async fn openai_responses_api() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/responses"))
+        .and(header("authorization", "Bearer sk-test"))
+        .and(body_json(json!({
+            "model": "o4-mini",
+            "input": [{"type": "message", "role": "user", "content": "Hello!"}],
+            "temperature": 0.5,
+            "max_output_tokens": 50,
+            "tools": [{
+                "type": "function",
+                "name": "get_weather",
+                "description": "Current weather for a city",
+                "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+            }],
+            "previous_response_id": "resp_0",
+            "reasoning": {"summary": "auto"}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "resp_1",
+            "object": "response",
+            "model": "o4-mini-2025-04-16",
+            "status": "completed",
+            "incomplete_details": null,
+            "output": [
+                {"type": "reasoning", "id": "rs_1", "summary": [
+                    {"type": "summary_text", "text": "The user wants the weather."}
+                ]},
+                {"type": "function_call", "id": "fc_1", "call_id": "call_1",
+                 "name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+            ],
+            "usage": {"input_tokens": 12, "output_tokens": 30}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::new("sk-test".to_string()).with_domain(server.uri());
+    let mut req = request("o4-mini");
+    req.tools = Some(vec![weather_tool()]);
+    let options = ResponsesOptions {
+        previous_response_id: Some("resp_0".to_string()),
+        reasoning_summary: Some("auto".to_string()),
+        ..Default::default()
+    };
+    let res = client.create_response(req, options).await.unwrap();
+
+    assert_eq!(res.id, "resp_1");
+    let message = &res.choices[0].message;
+    assert_eq!(
+        message.reasoning.as_deref(),
+        Some("The user wants the weather.")
+    );
+    let calls = message.tool_calls.as_ref().unwrap();
+    assert_eq!(calls[0].id, "call_1");
+    assert_eq!(calls[0].function.name, "get_weather");
+    assert_eq!(res.choices[0].finish_reason, "tool_calls");
+    let usage = res.usage.unwrap();
+    assert_eq!(usage.input_tokens, Some(12));
+    assert_eq!(usage.output_tokens, Some(30));
+}
+
+// A reply with a reasoning summary, sent back as history.
+fn request_after_reasoning(model: &str) -> ChatCompletionRequest {
+    let mut req = request(model);
+    req.messages.push(ChatMessage {
+        role: "assistant".to_string(),
+        content: "Hi there!".to_string(),
+        reasoning: Some("The user greeted me.".to_string()),
+        ..Default::default()
+    });
+    req.messages.push(ChatMessage {
+        role: "user".to_string(),
+        content: "Bye!".to_string(),
+        ..Default::default()
+    });
+    req
+}
+
+#[tokio::test]
+async fn reasoning_is_not_sent_back() {
+    let messages = json!([
+        {"role": "user", "content": "Hello!"},
+        {"role": "assistant", "content": "Hi there!"},
+        {"role": "user", "content": "Bye!"}
+    ]);
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_json(json!({
+            "model": "o4-mini",
+            "messages": messages,
+            "temperature": 0.5,
+            "max_tokens": 50
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "chatcmpl-3",
+            "model": "o4-mini",
+            "choices": [{"message": {"role": "assistant", "content": "Bye!"}, "finish_reason": "stop"}]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_json(json!({
+            "messages": messages,
+            "model": "claude-3-5-haiku-latest",
+            "max_tokens": 50,
+            "temperature": 0.5
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_3",
+            "role": "assistant",
+            "model": "claude-3-5-haiku-20241022",
+            "content": [{"type": "text", "text": "Bye!"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 20, "output_tokens": 2}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .and(body_json(json!({
+            "messages": messages,
+            "model": "llama3.2",
+            "stream": false
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "model": "llama3.2",
+            "message": {"role": "assistant", "content": "Bye!"},
+            "done": true
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let openai = OpenAI::new("sk-test".to_string()).with_domain(server.uri());
+    openai
+        .chat_completion(request_after_reasoning("o4-mini"))
+        .await
+        .unwrap();
+    let anthropic = Anthropic::new("sk-ant-test".to_string()).with_domain(server.uri());
+    anthropic
+        .chat_completion(request_after_reasoning("claude-3-5-haiku-latest"))
+        .await
+        .unwrap();
+    let ollama = Ollama::new(server.uri());
+    ollama
+        .chat_completion(request_after_reasoning("llama3.2"))
+        .await
+        .unwrap();
+}
+
+#[tokio::test]
+async fn anthropic_chat_completion() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(header("x-api-key", "sk-ant-test"))
+        .and(header("anthropic-version", "2023-06-01"))
+        .and(body_json(json!({
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "model": "claude-3-5-haiku-latest",
+            "max_tokens": 50,
+            "temperature": 0.5
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-haiku-20241022",
+            "content": [{"type": "text", "text": "Hi there!"}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 9, "output_tokens": 4}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Anthropic::new("sk-ant-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(request("claude-3-5-haiku-latest"))
+        .await
+        .unwrap();
+
+    assert_eq!(res.id, "msg_1");
+    assert_eq!(res.choices[0].message.content, "Hi there!");
+    assert_eq!(res.choices[0].finish_reason, "end_turn");
+    let usage = res.usage.unwrap();
+    assert_eq!(usage.input_tokens, Some(9));
+    assert_eq!(usage.output_tokens, Some(4));
+}
+
+#[tokio::test]
+async fn gemini_chat_completion() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:generateContent"))
+        .and(query_param("key", "gm-test"))
+        .and(body_json(json!({
+            "contents": [{"role": "user", "parts": [{"text": "Hello!"}]}],
+            "generation_config": {"temperature": 0.5, "maxOutputTokens": 50}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "Hi there!"}]},
+                "finishReason": "STOP"
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Gemini::new("gm-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(request("gemini-2.0-flash"))
+        .await
+        .unwrap();
+
+    assert_eq!(res.model, "gemini-2.0-flash");
+    assert_eq!(res.choices[0].message.role, "model");
+    assert_eq!(res.choices[0].message.content, "Hi there!");
+    assert_eq!(res.choices[0].finish_reason, "STOP");
+}
+
+#[tokio::test]
+async fn system_prompts_are_sent_apart_from_the_messages() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_json(json!({
+            "system": "Be brief.",
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "model": "claude-3-5-haiku-latest",
+            "max_tokens": 1000,
+            "temperature": null
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_1",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-haiku-20241022",
+            "content": [{"type": "text", "text": "Hi."}],
+            "stop_reason": "end_turn",
+            "usage": {"input_tokens": 12, "output_tokens": 2}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:generateContent"))
+        .and(body_json(json!({
+            "systemInstruction": {"parts": [{"text": "Be brief."}]},
+            "contents": [{"role": "user", "parts": [{"text": "Hello!"}]}],
+            "generation_config": {}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "Hi."}]},
+                "finishReason": "STOP"
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let anthropic = Anthropic::new("sk-ant-test".to_string()).with_domain(server.uri());
+    let mut conversation =
+        Conversation::new("claude-3-5-haiku-latest").with_system_prompt("Be brief.");
+    assert_eq!(
+        conversation.send(&anthropic, "Hello!").await.unwrap(),
+        "Hi."
+    );
+
+    let gemini = Gemini::new("gm-test".to_string()).with_domain(server.uri());
+    let mut conversation = Conversation::new("gemini-2.0-flash").with_system_prompt("Be brief.");
+    assert_eq!(conversation.send(&gemini, "Hello!").await.unwrap(), "Hi.");
+
+    // A system message later in the conversation has nowhere to go.
+    let mut late_system = request("claude-3-5-haiku-latest");
+    late_system.messages.push(ChatMessage {
+        role: "system".to_string(),
+        content: "Be brief.".to_string(),
+        ..Default::default()
+    });
+    let error = anthropic.chat_completion(late_system).await.unwrap_err();
+    assert!(matches!(error, LlmApiError::ConfigError(_)), "{:?}", error);
+}
+
+#[tokio::test]
+async fn ollama_chat_completion() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .and(body_json(json!({
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "model": "llama3.2",
+            "stream": false
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "model": "llama3.2",
+            "message": {"role": "assistant", "content": "Hi there!"},
+            "done": true
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Ollama::new(server.uri());
+    let res = client.chat_completion(request("llama3.2")).await.unwrap();
+
+    assert_eq!(res.model, "llama3.2");
+    assert_eq!(res.choices[0].message.content, "Hi there!");
+}
+
+#[tokio::test]
+async fn deepseek_chat_completion() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer ds-test"))
+        .and(body_json(json!({
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "model": "deepseek-chat",
+            "temperature": 0.5,
+            "max_tokens": 50
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ds-1",
+            "model": "deepseek-chat",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hi there!"},
+                "finish_reason": "stop"
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = DeepSeek::new("ds-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(request("deepseek-chat"))
+        .await
+        .unwrap();
+
+    assert_eq!(res.id, "ds-1");
+    assert_eq!(res.choices[0].message.content, "Hi there!");
+}
+
+#[tokio::test]
+async fn xai_chat_completion() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer xai-test"))
+        .and(body_json(json!({
+            "messages": [{"role": "user", "content": [{"type": "text", "text": "Hello!"}]}],
+            "model": "grok-2-latest",
+            "temperature": 0.5,
+            "max_tokens": 50
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "xai-1",
+            "model": "grok-2-1212",
+            "choices": [{
+                "index": 0,
+                "message": {"role": "assistant", "content": "Hi there!"},
+                "finish_reason": "stop"
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = XAI::new("xai-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(request("grok-2-latest"))
+        .await
+        .unwrap();
+
+    assert_eq!(res.model, "grok-2-1212");
+    assert_eq!(res.choices[0].message.content, "Hi there!");
+}
+
+#[tokio::test]
+async fn mistral_tool_call() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(header("authorization", "Bearer ms-test"))
+        .and(body_json(json!({
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "model": "mistral-small-latest",
+            "temperature": 0.5,
+            "max_tokens": 50,
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Current weather for a city",
+                    "parameters": {"type": "object", "properties": {"city": {"type": "string"}}}
+                }
+            }],
+            "safe_prompt": true
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ms-1",
+            "model": "mistral-small-latest",
+            "choices": [{
+                "index": 0,
+                "message": {
+                    "role": "assistant",
+                    "content": null,
+                    "tool_calls": [
+                        {"id": "abc123", "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}},
+                        {"id": "def456", "type": null, "function": {"name": "get_weather", "arguments": "{\"city\":\"Lyon\"}"}}
+                    ]
+                },
+                "finish_reason": "tool_calls"
+            }],
+            "usage": {"prompt_tokens": 70, "completion_tokens": 20, "total_tokens": 90}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Mistral::new("ms-test".to_string())
+        .with_domain(server.uri())
+        .with_safe_prompt(true);
+    let mut req = request("mistral-small-latest");
+    req.tools = Some(vec![weather_tool()]);
+    let res = client.chat_completion(req).await.unwrap();
+
+    let message = &res.choices[0].message;
+    assert_eq!(message.content, "");
+    let calls = message.tool_calls.as_ref().unwrap();
+    assert_eq!(calls[0].id, "abc123");
+    assert_eq!(calls[1].id, "def456");
+    assert!(calls.iter().all(|call| call.call_type == "function"));
+    assert_eq!(res.choices[0].finish_reason, "tool_calls");
+    assert_eq!(res.usage.unwrap().input_tokens, Some(70));
+}
+
+// A request that offers `weather_tool` and already holds one call to it and its result.
+fn tool_round_trip(model: &str) -> ChatCompletionRequest {
+    let mut req = request(model);
+    req.messages.push(ChatMessage {
+        role: "assistant".to_string(),
+        tool_calls: Some(vec![ToolCall {
+            id: "call_1".to_string(),
+            call_type: "function".to_string(),
+            function: FunctionCall {
+                name: "get_weather".to_string(),
+                arguments: "{\"city\":\"Paris\"}".to_string(),
+            },
+        }]),
+        ..Default::default()
+    });
+    req.messages.push(ChatMessage {
+        role: "tool".to_string(),
+        content: "Sunny".to_string(),
+        tool_call_id: Some("call_1".to_string()),
+        ..Default::default()
+    });
+    req.tools = Some(vec![weather_tool()]);
+    req
+}
+
+#[tokio::test]
+async fn anthropic_tool_use() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages"))
+        .and(body_json(json!({
+            "messages": [
+                {"role": "user", "content": "Hello!"},
+                {"role": "assistant", "content": [
+                    {"type": "tool_use", "id": "call_1", "name": "get_weather", "input": {"city": "Paris"}}
+                ]},
+                {"role": "user", "content": [
+                    {"type": "tool_result", "tool_use_id": "call_1", "content": "Sunny"}
+                ]}
+            ],
+            "model": "claude-3-5-haiku-latest",
+            "max_tokens": 50,
+            "temperature": 0.5,
+            "tools": [{
+                "name": "get_weather",
+                "description": "Current weather for a city",
+                "input_schema": weather_parameters()
+            }]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "msg_2",
+            "type": "message",
+            "role": "assistant",
+            "model": "claude-3-5-haiku-20241022",
+            "content": [
+                {"type": "text", "text": "Checking Lyon too."},
+                {"type": "tool_use", "id": "toolu_2", "name": "get_weather", "input": {"city": "Lyon"}}
+            ],
+            "stop_reason": "tool_use",
+            "usage": {"input_tokens": 60, "output_tokens": 20}
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Anthropic::new("sk-ant-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(tool_round_trip("claude-3-5-haiku-latest"))
+        .await
+        .unwrap();
+
+    assert_eq!(res.choices.len(), 1);
+    let message = &res.choices[0].message;
+    assert_eq!(message.content, "Checking Lyon too.");
+    let calls = message.tool_calls.as_ref().unwrap();
+    assert_eq!(calls[0].id, "toolu_2");
+    assert_eq!(calls[0].function.name, "get_weather");
+    assert_eq!(calls[0].function.arguments, "{\"city\":\"Lyon\"}");
+    assert_eq!(res.choices[0].finish_reason, "tool_use");
+}
+
+#[tokio::test]
+async fn gemini_function_calling() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:generateContent"))
+        .and(body_json(json!({
+            "contents": [
+                {"role": "user", "parts": [{"text": "Hello!"}]},
+                {"role": "model", "parts": [
+                    {"functionCall": {"id": "call_1", "name": "get_weather", "args": {"city": "Paris"}}}
+                ]},
+                {"role": "user", "parts": [
+                    {"functionResponse": {"id": "call_1", "name": "get_weather", "response": {"content": "Sunny"}}}
+                ]}
+            ],
+            "tools": [{"functionDeclarations": [{
+                "name": "get_weather",
+                "description": "Current weather for a city",
+                "parameters": weather_parameters()
+            }]}],
+            "generation_config": {"temperature": 0.5, "maxOutputTokens": 50}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [
+                    {"functionCall": {"name": "get_weather", "args": {"city": "Lyon"}}}
+                ]},
+                "finishReason": "STOP"
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Gemini::new("gm-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(tool_round_trip("gemini-2.0-flash"))
+        .await
+        .unwrap();
+
+    let message = &res.choices[0].message;
+    assert_eq!(message.content, "");
+    let calls = message.tool_calls.as_ref().unwrap();
+    assert_eq!(calls[0].id, "get_weather-0");
+    assert_eq!(calls[0].function.arguments, "{\"city\":\"Lyon\"}");
+}
+
+#[tokio::test]
+async fn gemini_parallel_calls_to_one_function() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:generateContent"))
+        .and(body_json(json!({
+            "contents": [
+                {"role": "user", "parts": [{"text": "Hello!"}]},
+                {"role": "model", "parts": [
+                    {"functionCall": {"name": "get_weather", "args": {"city": "Paris"}}},
+                    {"functionCall": {"name": "get_weather", "args": {"city": "Lyon"}}}
+                ]},
+                {"role": "user", "parts": [
+                    {"functionResponse": {"name": "get_weather", "response": {"content": "Sunny"}}},
+                    {"functionResponse": {"name": "get_weather", "response": {"content": "Rainy"}}}
+                ]}
+            ],
+            "generation_config": {"temperature": 0.5, "maxOutputTokens": 50}
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [{"text": "Sunny in Paris, rainy in Lyon."}]},
+                "finishReason": "STOP"
+            }]
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:generateContent"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "candidates": [{
+                "content": {"role": "model", "parts": [
+                    {"functionCall": {"name": "get_weather", "args": {"city": "Paris"}}},
+                    {"functionCall": {"name": "get_weather", "args": {"city": "Lyon"}}}
+                ]},
+                "finishReason": "STOP"
+            }]
+        })))
+        .up_to_n_times(1)
+        .mount(&server)
+        .await;
+    let client = Gemini::new("gm-test".to_string()).with_domain(server.uri());
+
+    let first = client
+        .chat_completion(request("gemini-2.0-flash"))
+        .await
+        .unwrap();
+    let reply = first.choices[0].message.clone();
+    let calls = reply.tool_calls.clone().unwrap();
+    assert_eq!(calls[0].id, "get_weather-0");
+    assert_eq!(calls[1].id, "get_weather-1");
+
+    let mut next = request("gemini-2.0-flash");
+    next.messages.push(reply);
+    for (call, output) in calls.iter().zip(["Sunny", "Rainy"]) {
+        next.messages.push(ChatMessage {
+            role: "tool".to_string(),
+            content: output.to_string(),
+            tool_call_id: Some(call.id.clone()),
+            ..Default::default()
+        });
+    }
+    let res = client.chat_completion(next).await.unwrap();
+    assert_eq!(
+        res.choices[0].message.content,
+        "Sunny in Paris, rainy in Lyon."
+    );
+}
+
+#[tokio::test]
+async fn ollama_tool_call() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/api/chat"))
+        .and(body_json(json!({
+            "messages": [
+                {"role": "user", "content": "Hello!"},
+                {"role": "assistant", "content": "", "tool_calls": [
+                    {"function": {"name": "get_weather", "arguments": {"city": "Paris"}}}
+                ]},
+                {"role": "tool", "content": "Sunny", "tool_name": "get_weather"}
+            ],
+            "model": "llama3.2",
+            "stream": false,
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Current weather for a city",
+                    "parameters": weather_parameters()
+                }
+            }]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "model": "llama3.2",
+            "message": {
+                "role": "assistant",
+                "content": "",
+                "tool_calls": [{"function": {"name": "get_weather", "arguments": {"city": "Lyon"}}}]
+            },
+            "done": true
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Ollama::new(server.uri());
+    let res = client
+        .chat_completion(tool_round_trip("llama3.2"))
+        .await
+        .unwrap();
+
+    let calls = res.choices[0].message.tool_calls.as_ref().unwrap();
+    assert_eq!(calls[0].id, "call_0");
+    assert_eq!(calls[0].function.arguments, "{\"city\":\"Lyon\"}");
+}
+
+// DeepSeek and xAI take tools in the OpenAI shape.
+fn openai_style_tool_call_reply(id: &str) -> serde_json::Value {
+    json!({
+        "id": id,
+        "model": "model",
+        "choices": [{
+            "index": 0,
+            "message": {
+                "role": "assistant",
+                "content": null,
+                "tool_calls": [{
+                    "id": "call_2",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Lyon\"}"}
+                }]
+            },
+            "finish_reason": "tool_calls"
+        }]
+    })
+}
+
+#[tokio::test]
+async fn deepseek_tool_call() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_json(json!({
+            "messages": [
+                {"role": "user", "content": "Hello!"},
+                {"role": "assistant", "content": "", "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                }]},
+                {"role": "tool", "content": "Sunny", "tool_call_id": "call_1"}
+            ],
+            "model": "deepseek-chat",
+            "temperature": 0.5,
+            "max_tokens": 50,
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Current weather for a city",
+                    "parameters": weather_parameters()
+                }
+            }]
+        })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(openai_style_tool_call_reply("ds-2")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = DeepSeek::new("ds-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(tool_round_trip("deepseek-chat"))
+        .await
+        .unwrap();
+
+    let message = &res.choices[0].message;
+    assert_eq!(message.content, "");
+    assert_eq!(message.tool_calls.as_ref().unwrap()[0].id, "call_2");
+    assert_eq!(res.choices[0].finish_reason, "tool_calls");
+}
+
+#[tokio::test]
+async fn xai_tool_call() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/chat/completions"))
+        .and(body_json(json!({
+            "messages": [
+                {"role": "user", "content": [{"type": "text", "text": "Hello!"}]},
+                {"role": "assistant", "content": [{"type": "text", "text": ""}], "tool_calls": [{
+                    "id": "call_1",
+                    "type": "function",
+                    "function": {"name": "get_weather", "arguments": "{\"city\":\"Paris\"}"}
+                }]},
+                {"role": "tool", "content": [{"type": "text", "text": "Sunny"}], "tool_call_id": "call_1"}
+            ],
+            "model": "grok-2-latest",
+            "temperature": 0.5,
+            "max_tokens": 50,
+            "tools": [{
+                "type": "function",
+                "function": {
+                    "name": "get_weather",
+                    "description": "Current weather for a city",
+                    "parameters": weather_parameters()
+                }
+            }]
+        })))
+        .respond_with(
+            ResponseTemplate::new(200).set_body_json(openai_style_tool_call_reply("xai-2")),
+        )
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = XAI::new("xai-test".to_string()).with_domain(server.uri());
+    let res = client
+        .chat_completion(tool_round_trip("grok-2-latest"))
+        .await
+        .unwrap();
+
+    let calls = res.choices[0].message.tool_calls.as_ref().unwrap();
+    assert_eq!(calls[0].function.name, "get_weather");
+    assert_eq!(calls[0].function.arguments, "{\"city\":\"Lyon\"}");
+}
+
+#[tokio::test]
+async fn cohere_chat_with_documents() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v2/chat"))
+        .and(header("authorization", "Bearer co-test"))
+        .and(body_json(json!({
+            "messages": [{"role": "user", "content": "Hello!"}],
+            "model": "command-r-plus-08-2024",
+            "temperature": 0.5,
+            "max_tokens": 50,
+            "documents": [{"id": "doc-1", "data": {"text": "Paris is sunny."}}]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "co-1",
+            "finish_reason": "COMPLETE",
+            "message": {
+                "role": "assistant",
+                "content": [{"type": "text", "text": "It is sunny."}],
+                "citations": [{
+                    "start": 6,
+                    "end": 11,
+                    "text": "sunny",
+                    "sources": [{"type": "document", "id": "doc-1", "document": {"text": "Paris is sunny."}}]
+                }]
+            },
+            "usage": {
+                "billed_units": {"input_tokens": 8, "output_tokens": 4},
+                "tokens": {"input_tokens": 120, "output_tokens": 40}
+            }
+        })))
+        .expect(1)
+        .mount(&server)
+        .await;
+
+    let client = Cohere::new("co-test".to_string()).with_domain(server.uri());
+    let documents = vec![llm_api_rs::providers::cohere::CohereDocument {
+        id: Some("doc-1".to_string()),
+        data: json!({"text": "Paris is sunny."}),
+    }];
+    let res = client
+        .chat_with_documents(request("command-r-plus-08-2024"), documents)
+        .await
+        .unwrap();
+
+    assert_eq!(res.response.choices[0].message.content, "It is sunny.");
+    assert_eq!(res.response.choices[0].finish_reason, "stop");
+    assert_eq!(res.response.usage.unwrap().input_tokens, Some(8));
+    assert_eq!(res.citations[0].text, "sunny");
+    assert_eq!(res.citations[0].sources[0].id.as_deref(), Some("doc-1"));
+}
+
+#[tokio::test]
+async fn text_only_providers_reject_file_parts() {
+    // No mocks are mounted: the request must fail before it is sent.
+    let server = MockServer::start().await;
+    let providers: Vec<(&str, Box<dyn LlmProvider>)> = vec![
+        (
+            "deepseek",
+            Box::new(DeepSeek::new("ds".to_string()).with_domain(server.uri())),
+        ),
+        (
+            "xai",
+            Box::new(XAI::new("xai".to_string()).with_domain(server.uri())),
+        ),
+        (
+            "mistral",
+            Box::new(Mistral::new("ms".to_string()).with_domain(server.uri())),
+        ),
+        (
+            "cohere",
+            Box::new(Cohere::new("co".to_string()).with_domain(server.uri())),
+        ),
+        ("ollama", Box::new(Ollama::new(server.uri()))),
+    ];
+    let mut req = request("some-model");
+    req.messages[0].parts = vec![ContentPart::File(FileHandle {
+        provider: "openai".to_string(),
+        id: "file-1".to_string(),
+        filename: "report.pdf".to_string(),
+        mime_type: "application/pdf".to_string(),
+        size_bytes: 8,
+        uri: None,
+    })];
+
+    for (name, provider) in providers {
+        let error = provider.chat_completion(req.clone()).await.unwrap_err();
+        assert!(
+            matches!(&error, LlmApiError::ConfigError(m) if m == &format!("{} does not support file parts", name)),
+            "{}: {:?}",
+            name,
+            error
+        );
+    }
+    assert!(server.received_requests().await.unwrap().is_empty());
+}
+
+#[tokio::test]
+async fn error_status_is_an_error() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(401).set_body_json(json!({
+            "error": {"message": "Incorrect API key provided", "type": "invalid_request_error"}
+        })))
+        .mount(&server)
+        .await;
+
+    let client = OpenAI::new("bad".to_string()).with_domain(server.uri());
+    assert!(client
+        .chat_completion(request("gpt-4o-mini"))
+        .await
+        .is_err());
+}
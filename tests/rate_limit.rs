@@ -0,0 +1,248 @@
+use llm_api_rs::core::client::APIClient;
+use llm_api_rs::core::middleware::{HttpRequest, HttpResponse, Middleware};
+use llm_api_rs::core::rate_limit::{RateLimiter, RateLimits};
+use llm_api_rs::LlmApiError;
+use reqwest::header::{HeaderMap, HeaderValue};
+use reqwest::Method;
+use serde_json::json;
+use std::sync::Arc;
+use std::time::Duration;
+use tokio::time::Instant;
+use wiremock::matchers::path;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn request(id: u64, model: &str) -> HttpRequest {
+    HttpRequest {
+        id,
+        method: Method::POST,
+        url: "https://api.openai.com/v1/chat/completions".to_string(),
+        headers: HeaderMap::new(),
+        body: json!({"model": model, "messages": [{"role": "user", "content": "Hi"}]}),
+    }
+}
+
+fn response(status: u16, headers: &[(&'static str, &'static str)]) -> HttpResponse {
+    let mut header_map = HeaderMap::new();
+    for (name, value) in headers {
+        header_map.insert(*name, HeaderValue::from_static(value));
+    }
+    HttpResponse {
+        status,
+        headers: header_map,
+        body: String::new(),
+    }
+}
+
+async fn complete(limiter: &RateLimiter, request: &HttpRequest) {
+    limiter
+        .after_response(request, &mut response(200, &[]))
+        .await
+        .unwrap();
+}
+
+#[tokio::test(start_paused = true)]
+async fn requests_per_minute_queue_until_the_window_slides() {
+    let limiter = RateLimiter::new(RateLimits {
+        requests_per_minute: Some(2),
+        ..Default::default()
+    });
+    let start = Instant::now();
+
+    for id in 1..=3 {
+        let mut request = request(id, "gpt-4o-mini");
+        limiter.before_request(&mut request).await.unwrap();
+        complete(&limiter, &request).await;
+    }
+
+    assert!(start.elapsed() >= Duration::from_secs(60));
+    assert!(start.elapsed() < Duration::from_secs(61));
+}
+
+#[tokio::test(start_paused = true)]
+async fn max_concurrent_waits_for_a_release() {
+    let limiter = Arc::new(RateLimiter::new(RateLimits {
+        max_concurrent: Some(1),
+        ..Default::default()
+    }));
+    let mut first = request(1, "gpt-4o-mini");
+    limiter.before_request(&mut first).await.unwrap();
+
+    let waiter = tokio::spawn({
+        let limiter = limiter.clone();
+        async move {
+            let mut second = request(2, "gpt-4o-mini");
+            limiter.before_request(&mut second).await.unwrap();
+            Instant::now()
+        }
+    });
+
+    tokio::time::sleep(Duration::from_secs(5)).await;
+    assert!(!waiter.is_finished());
+
+    let released_at = Instant::now();
+    limiter
+        .on_error(&first, &LlmApiError::NetworkError("reset".to_string()))
+        .await;
+    assert_eq!(waiter.await.unwrap(), released_at);
+}
+
+#[tokio::test]
+async fn cancelled_calls_give_their_slot_back() {
+    let server = MockServer::start().await;
+    Mock::given(path("/slow"))
+        .respond_with(ResponseTemplate::new(200).set_delay(Duration::from_secs(30)))
+        .mount(&server)
+        .await;
+    Mock::given(path("/fast"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({})))
+        .mount(&server)
+        .await;
+    let limiter = Arc::new(RateLimiter::new(RateLimits {
+        max_concurrent: Some(1),
+        ..Default::default()
+    }));
+    let client = APIClient::new().with_middleware(limiter);
+    let body = json!({"model": "gpt-4o-mini"});
+
+    let slow = client.send_request::<_, serde_json::Value>(
+        format!("{}/slow", server.uri()),
+        vec![],
+        &body,
+    );
+    assert!(tokio::time::timeout(Duration::from_millis(100), slow)
+        .await
+        .is_err());
+
+    let fast = client.send_request::<_, serde_json::Value>(
+        format!("{}/fast", server.uri()),
+        vec![],
+        &body,
+    );
+    let response = tokio::time::timeout(Duration::from_secs(5), fast).await;
+    assert!(response.expect("slot was not released").is_ok());
+}
+
+#[tokio::test(start_paused = true)]
+async fn rate_limit_headers_pause_until_reset() {
+    let limiter = RateLimiter::new(RateLimits::default());
+    let start = Instant::now();
+
+    let mut first = request(1, "gpt-4o-mini");
+    limiter.before_request(&mut first).await.unwrap();
+    limiter
+        .after_response(
+            &first,
+            &mut response(
+                200,
+                &[
+                    ("x-ratelimit-remaining-requests", "0"),
+                    ("x-ratelimit-reset-requests", "1m30s"),
+                ],
+            ),
+        )
+        .await
+        .unwrap();
+
+    let mut second = request(2, "gpt-4o-mini");
+    limiter.before_request(&mut second).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_secs(90));
+
+    limiter
+        .after_response(&second, &mut response(429, &[("retry-after", "7")]))
+        .await
+        .unwrap();
+    let mut third = request(3, "gpt-4o-mini");
+    limiter.before_request(&mut third).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_secs(97));
+}
+
+#[tokio::test(start_paused = true)]
+async fn unusable_reset_headers_are_ignored() {
+    for value in ["inf", "NaN", "1e300", "1e19", "99999999999999999999h"] {
+        let limiter = RateLimiter::new(RateLimits::default());
+        let start = Instant::now();
+
+        let mut first = request(1, "gpt-4o-mini");
+        limiter.before_request(&mut first).await.unwrap();
+        limiter
+            .after_response(
+                &first,
+                &mut response(
+                    429,
+                    &[
+                        ("retry-after", value),
+                        ("x-ratelimit-remaining-requests", "0"),
+                        ("x-ratelimit-reset-requests", value),
+                    ],
+                ),
+            )
+            .await
+            .unwrap();
+
+        // The reset falls back to one second and the retry-after is dropped.
+        let mut second = request(2, "gpt-4o-mini");
+        limiter.before_request(&mut second).await.unwrap();
+        assert_eq!(start.elapsed(), Duration::from_secs(1), "{}", value);
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn models_with_their_own_limits_use_separate_budgets() {
+    let limiter = RateLimiter::new(RateLimits {
+        requests_per_minute: Some(1),
+        ..Default::default()
+    })
+    .with_model_limits(
+        "gpt-4o",
+        RateLimits {
+            requests_per_minute: Some(1),
+            ..Default::default()
+        },
+    );
+    let start = Instant::now();
+
+    let mut shared = request(1, "gpt-4o-mini");
+    limiter.before_request(&mut shared).await.unwrap();
+    let mut own = request(2, "gpt-4o");
+    limiter.before_request(&mut own).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::ZERO);
+
+    let mut shared_again = request(3, "o3-mini");
+    limiter.before_request(&mut shared_again).await.unwrap();
+    assert_eq!(start.elapsed(), Duration::from_secs(60));
+}
+
+#[tokio::test(start_paused = true)]
+async fn tokens_per_minute_use_the_estimator() {
+    let limiter = RateLimiter::new(RateLimits {
+        tokens_per_minute: Some(1_000),
+        ..Default::default()
+    })
+    .with_token_estimator(|body| body["max_tokens"].as_u64().unwrap_or(0) as u32);
+    let start = Instant::now();
+
+    for id in 1..=2 {
+        let mut request = request(id, "gpt-4o-mini");
+        request.body["max_tokens"] = json!(600);
+        limiter.before_request(&mut request).await.unwrap();
+    }
+
+    assert_eq!(start.elapsed(), Duration::from_secs(60));
+}
+
+#[tokio::test(start_paused = true)]
+async fn oversized_estimates_do_not_overflow() {
+    let limiter = RateLimiter::new(RateLimits {
+        tokens_per_minute: Some(1_000),
+        ..Default::default()
+    })
+    .with_token_estimator(|_| u32::MAX);
+    let start = Instant::now();
+
+    for id in 1..=2 {
+        let mut request = request(id, "gpt-4o-mini");
+        limiter.before_request(&mut request).await.unwrap();
+    }
+
+    assert_eq!(start.elapsed(), Duration::from_secs(60));
+}
@@ -0,0 +1,298 @@
+use async_trait::async_trait;
+use llm_api_rs::core::tokens::{TokenCount, TokenCountSource};
+use llm_api_rs::{
+    ChatChoice, ChatCompletionRequest, ChatCompletionResponse, ChatMessage, CircuitBreaker,
+    LlmApiError, LlmProvider, MockProvider, ModelInfo, Route, Router, RoutingStrategy,
+};
+use std::sync::Arc;
+use std::time::Duration;
+
+fn request() -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: "ignored".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Hello".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+fn overloaded() -> LlmApiError {
+    LlmApiError::ApiError {
+        status: 529,
+        message: "overloaded".to_string(),
+    }
+}
+
+async fn reply(router: &Router) -> String {
+    let response = router.chat_completion(request()).await.unwrap();
+    response.choices[0].message.content.clone()
+}
+
+#[tokio::test]
+async fn falls_back_on_retryable_errors_with_the_route_model() {
+    let anthropic = Arc::new(MockProvider::new().with_error(overloaded()));
+    let openai = Arc::new(MockProvider::new().with_reply("from openai"));
+    let router = Router::new()
+        .with_route(Route::new(
+            "anthropic",
+            anthropic.clone(),
+            "claude-sonnet-4",
+        ))
+        .with_route(Route::new("openai", openai.clone(), "gpt-4o"));
+
+    assert_eq!(reply(&router).await, "from openai");
+    assert_eq!(anthropic.requests()[0].model, "claude-sonnet-4");
+    assert_eq!(openai.requests()[0].model, "gpt-4o");
+}
+
+#[tokio::test]
+async fn returns_non_retryable_errors_without_fallback() {
+    let primary = Arc::new(MockProvider::new().with_error(LlmApiError::ApiError {
+        status: 400,
+        message: "bad request".to_string(),
+    }));
+    let secondary = Arc::new(MockProvider::new().with_reply("unused"));
+    let router = Router::new()
+        .with_route(Route::new("primary", primary, "a"))
+        .with_route(Route::new("secondary", secondary.clone(), "b"));
+
+    let error = router.chat_completion(request()).await.unwrap_err();
+    assert_eq!(error.status(), Some(400));
+    assert!(secondary.requests().is_empty());
+}
+
+#[tokio::test]
+async fn weighted_round_robin_follows_weights() {
+    let heavy = Arc::new(MockProvider::new());
+    let light = Arc::new(MockProvider::new());
+    for _ in 0..4 {
+        heavy.push(Ok(reply_from("heavy")));
+        light.push(Ok(reply_from("light")));
+    }
+    let router = Router::new()
+        .with_strategy(RoutingStrategy::WeightedRoundRobin)
+        .with_route(Route::new("heavy", heavy, "a").with_weight(3))
+        .with_route(Route::new("light", light, "b"));
+
+    let mut replies = Vec::new();
+    for _ in 0..4 {
+        replies.push(reply(&router).await);
+    }
+    assert_eq!(replies, ["heavy", "heavy", "heavy", "light"]);
+}
+
+#[tokio::test(start_paused = true)]
+async fn circuit_opens_after_repeated_failures_and_probes_after_cooldown() {
+    let flaky = Arc::new(
+        MockProvider::new()
+            .with_error(overloaded())
+            .with_error(overloaded())
+            .with_reply("recovered"),
+    );
+    let backup = Arc::new(MockProvider::new());
+    for _ in 0..3 {
+        backup.push(Ok(reply_from("backup")));
+    }
+    let router = Router::new()
+        .with_circuit_breaker(CircuitBreaker {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(30),
+        })
+        .with_route(Route::new("flaky", flaky.clone(), "a"))
+        .with_route(Route::new("backup", backup, "b"));
+
+    assert_eq!(reply(&router).await, "backup");
+    assert_eq!(reply(&router).await, "backup");
+    assert_eq!(router.unavailable_routes(), ["flaky"]);
+
+    assert_eq!(reply(&router).await, "backup");
+    assert_eq!(flaky.requests().len(), 2);
+
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(reply(&router).await, "recovered");
+    assert!(router.unavailable_routes().is_empty());
+}
+
+#[tokio::test(start_paused = true)]
+async fn half_open_routes_stay_probeable_until_a_probe_fails() {
+    let primary = Arc::new(MockProvider::new());
+    let secondary = Arc::new(MockProvider::new());
+    for result in [Err(overloaded()), Ok(()), Err(overloaded()), Ok(()), Ok(())] {
+        primary.push(result.map(|()| reply_from("primary")));
+    }
+    primary.push(Err(overloaded()));
+    secondary.push(Err(overloaded()));
+    secondary.push(Err(overloaded()));
+    secondary.push(Ok(reply_from("secondary")));
+    let router = Router::new()
+        .with_circuit_breaker(CircuitBreaker {
+            failure_threshold: 2,
+            cooldown: Duration::from_secs(30),
+        })
+        .with_route(Route::new("primary", primary, "a"))
+        .with_route(Route::new("secondary", secondary.clone(), "b"));
+
+    // Both fail, then the primary recovers; twice, which opens the secondary.
+    assert!(router.chat_completion(request()).await.is_err());
+    assert_eq!(reply(&router).await, "primary");
+    assert!(router.chat_completion(request()).await.is_err());
+    assert_eq!(reply(&router).await, "primary");
+    assert_eq!(router.unavailable_routes(), ["secondary"]);
+
+    // Calls the primary serves do not probe the secondary or re-open it.
+    tokio::time::sleep(Duration::from_secs(30)).await;
+    assert_eq!(reply(&router).await, "primary");
+    assert!(router.unavailable_routes().is_empty());
+
+    tokio::time::sleep(Duration::from_secs(1)).await;
+    assert_eq!(reply(&router).await, "secondary");
+    assert_eq!(secondary.requests().len(), 3);
+}
+
+// Reports fixed token counts and models. Chat calls fail retryably unless `healthy`.
+struct Listing {
+    tokens: u32,
+    models: &'static [&'static str],
+    healthy: bool,
+}
+
+#[async_trait]
+impl LlmProvider for Listing {
+    async fn chat_completion(
+        &self,
+        _request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        if self.healthy {
+            Ok(reply_from("listing"))
+        } else {
+            Err(overloaded())
+        }
+    }
+
+    async fn count_tokens(
+        &self,
+        _request: &ChatCompletionRequest,
+    ) -> Result<TokenCount, LlmApiError> {
+        Ok(TokenCount {
+            input_tokens: self.tokens,
+            source: TokenCountSource::Provider,
+        })
+    }
+
+    async fn list_models(&self) -> Result<Vec<ModelInfo>, LlmApiError> {
+        Ok(self
+            .models
+            .iter()
+            .map(|id| ModelInfo {
+                id: id.to_string(),
+                provider: "listing".to_string(),
+                ..Default::default()
+            })
+            .collect())
+    }
+}
+
+async fn model_ids(router: &Router) -> Vec<String> {
+    let models = router.list_models().await.unwrap();
+    models.into_iter().map(|model| model.id).collect()
+}
+
+#[tokio::test]
+async fn count_tokens_and_list_models_skip_open_routes() {
+    let flaky = Arc::new(Listing {
+        tokens: 1,
+        models: &["a-large", "a-small"],
+        healthy: false,
+    });
+    let steady = Arc::new(Listing {
+        tokens: 2,
+        models: &["a-small", "b"],
+        healthy: true,
+    });
+    let router = Router::new()
+        .with_circuit_breaker(CircuitBreaker {
+            failure_threshold: 1,
+            cooldown: Duration::from_secs(30),
+        })
+        .with_route(Route::new("large", flaky.clone(), "a-large"))
+        .with_route(Route::new("small", flaky, "a-small"))
+        .with_route(Route::new("steady", steady, "b"));
+
+    assert_eq!(
+        router.count_tokens(&request()).await.unwrap().input_tokens,
+        1
+    );
+    assert_eq!(model_ids(&router).await, ["a-large", "a-small", "b"]);
+
+    // Opens both routes of the flaky provider.
+    assert_eq!(reply(&router).await, "listing");
+    assert_eq!(
+        router.count_tokens(&request()).await.unwrap().input_tokens,
+        2
+    );
+    assert_eq!(model_ids(&router).await, ["a-small", "b"]);
+}
+
+struct Slow {
+    delay: Duration,
+    name: &'static str,
+}
+
+#[async_trait]
+impl LlmProvider for Slow {
+    async fn chat_completion(
+        &self,
+        _request: ChatCompletionRequest,
+    ) -> Result<ChatCompletionResponse, LlmApiError> {
+        tokio::time::sleep(self.delay).await;
+        Ok(reply_from(self.name))
+    }
+}
+
+#[tokio::test(start_paused = true)]
+async fn least_latency_prefers_the_fastest_route() {
+    let router = Router::new()
+        .with_strategy(RoutingStrategy::LeastLatency)
+        .with_route(Route::new(
+            "slow",
+            Arc::new(Slow {
+                delay: Duration::from_secs(3),
+                name: "slow",
+            }),
+            "a",
+        ))
+        .with_route(Route::new(
+            "fast",
+            Arc::new(Slow {
+                delay: Duration::from_millis(200),
+                name: "fast",
+            }),
+            "b",
+        ));
+
+    // Unmeasured routes are tried first, in order.
+    assert_eq!(reply(&router).await, "slow");
+    assert_eq!(reply(&router).await, "fast");
+    assert_eq!(reply(&router).await, "fast");
+    assert_eq!(reply(&router).await, "fast");
+}
+
+fn reply_from(content: &str) -> ChatCompletionResponse {
+    ChatCompletionResponse {
+        id: "resp".to_string(),
+        model: "model".to_string(),
+        choices: vec![ChatChoice {
+            message: ChatMessage {
+                role: "assistant".to_string(),
+                content: content.to_string(),
+                ..Default::default()
+            },
+            finish_reason: "stop".to_string(),
+        }],
+        usage: None,
+        ..Default::default()
+    }
+}
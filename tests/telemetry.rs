@@ -0,0 +1,88 @@
+#![cfg(all(feature = "tracing", feature = "mistral"))]
+
+use llm_api_rs::{ChatCompletionRequest, ChatMessage, LlmProvider, Mistral};
+use serde_json::json;
+use std::collections::BTreeMap;
+use std::sync::{Arc, Mutex};
+use tracing::field::{Field, Visit};
+use tracing::span::{Attributes, Id, Record};
+use tracing::Subscriber;
+use tracing_subscriber::layer::{Context, SubscriberExt};
+use tracing_subscriber::Layer;
+use wiremock::matchers::method;
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+type Fields = Arc<Mutex<BTreeMap<String, String>>>;
+
+struct CaptureFields(Fields);
+
+impl Visit for CaptureFields {
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(field.name().to_string(), format!("{:?}", value));
+    }
+
+    fn record_str(&mut self, field: &Field, value: &str) {
+        self.0
+            .lock()
+            .unwrap()
+            .insert(field.name().to_string(), value.to_string());
+    }
+}
+
+struct CaptureLayer(Fields);
+
+impl<S: Subscriber> Layer<S> for CaptureLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, _id: &Id, _ctx: Context<'_, S>) {
+        attrs.record(&mut CaptureFields(self.0.clone()));
+    }
+
+    fn on_record(&self, _id: &Id, values: &Record<'_>, _ctx: Context<'_, S>) {
+        values.record(&mut CaptureFields(self.0.clone()));
+    }
+}
+
+#[tokio::test]
+async fn chat_completion_span_follows_gen_ai_conventions() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({
+            "id": "ms-1",
+            "model": "mistral-small-2409",
+            "choices": [{"message": {"role": "assistant", "content": "Hi!"}, "finish_reason": "stop"}],
+            "usage": {"prompt_tokens": 7, "completion_tokens": 2}
+        })))
+        .mount(&server)
+        .await;
+
+    let fields = Fields::default();
+    let subscriber = tracing_subscriber::registry().with(CaptureLayer(fields.clone()));
+    let _guard = tracing::subscriber::set_default(subscriber);
+
+    let provider = Mistral::new("ms-test".to_string()).with_domain(server.uri());
+    let request = ChatCompletionRequest {
+        model: "mistral-small-latest".to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "a secret prompt".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    };
+    provider.chat_completion(request).await.unwrap();
+
+    let fields = fields.lock().unwrap();
+    assert_eq!(fields["gen_ai.system"], "mistral_ai");
+    assert_eq!(fields["gen_ai.operation.name"], "chat");
+    assert_eq!(fields["gen_ai.request.model"], "mistral-small-latest");
+    assert_eq!(fields["gen_ai.response.model"], "mistral-small-2409");
+    assert_eq!(fields["gen_ai.response.finish_reasons"], r#"["stop"]"#);
+    assert_eq!(fields["gen_ai.usage.input_tokens"], "7");
+    assert_eq!(fields["gen_ai.usage.output_tokens"], "2");
+    // Content capture is opt-in.
+    assert!(fields
+        .values()
+        .all(|value| !value.contains("a secret prompt")));
+}
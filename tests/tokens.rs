@@ -0,0 +1,153 @@
+#![cfg(all(
+    feature = "openai",
+    feature = "anthropic",
+    feature = "gemini",
+    feature = "deepseek"
+))]
+
+use llm_api_rs::core::tokens::{TokenCount, TokenCountSource};
+use llm_api_rs::{
+    Anthropic, ChatCompletionRequest, ChatMessage, DeepSeek, Gemini, LlmProvider, MockProvider,
+    OpenAI,
+};
+use serde_json::json;
+use wiremock::matchers::{body_json, header, method, path, query_param};
+use wiremock::{Mock, MockServer, ResponseTemplate};
+
+fn request(model: &str) -> ChatCompletionRequest {
+    ChatCompletionRequest {
+        model: model.to_string(),
+        messages: vec![ChatMessage {
+            role: "user".to_string(),
+            content: "Hello, world!".to_string(),
+            ..Default::default()
+        }],
+        ..Default::default()
+    }
+}
+
+#[tokio::test]
+async fn anthropic_counts_with_its_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1/messages/count_tokens"))
+        .and(header("x-api-key", "sk-ant"))
+        .and(body_json(json!({
+            "model": "claude-sonnet-4-0",
+            "messages": [{"role": "user", "content": "Hello, world!"}]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"input_tokens": 12})))
+        .mount(&server)
+        .await;
+    let provider = Anthropic::new("sk-ant".to_string()).with_domain(server.uri());
+
+    let count = provider
+        .count_tokens(&request("claude-sonnet-4-0"))
+        .await
+        .unwrap();
+    assert_eq!(
+        count,
+        TokenCount {
+            input_tokens: 12,
+            source: TokenCountSource::Provider
+        }
+    );
+}
+
+#[tokio::test]
+async fn gemini_counts_with_its_endpoint() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:countTokens"))
+        .and(query_param("key", "g-key"))
+        .and(body_json(json!({
+            "contents": [{"role": "user", "parts": [{"text": "Hello, world!"}]}]
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"totalTokens": 5})))
+        .mount(&server)
+        .await;
+    let provider = Gemini::new("g-key".to_string()).with_domain(server.uri());
+
+    let count = provider
+        .count_tokens(&request("gemini-2.0-flash"))
+        .await
+        .unwrap();
+    assert_eq!(count.input_tokens, 5);
+    assert_eq!(count.source, TokenCountSource::Provider);
+}
+
+#[tokio::test]
+async fn gemini_counts_the_system_instruction() {
+    let server = MockServer::start().await;
+    Mock::given(method("POST"))
+        .and(path("/v1beta/models/gemini-2.0-flash:countTokens"))
+        .and(body_json(json!({
+            "generateContentRequest": {
+                "model": "models/gemini-2.0-flash",
+                "systemInstruction": {"parts": [{"text": "Be brief."}]},
+                "contents": [{"role": "user", "parts": [{"text": "Hello, world!"}]}]
+            }
+        })))
+        .respond_with(ResponseTemplate::new(200).set_body_json(json!({"totalTokens": 9})))
+        .mount(&server)
+        .await;
+    let provider = Gemini::new("g-key".to_string()).with_domain(server.uri());
+
+    let mut request = request("gemini-2.0-flash");
+    request.messages.insert(
+        0,
+        ChatMessage {
+            role: "system".to_string(),
+            content: "Be brief.".to_string(),
+            ..Default::default()
+        },
+    );
+    assert_eq!(
+        provider.count_tokens(&request).await.unwrap().input_tokens,
+        9
+    );
+}
+
+#[tokio::test]
+async fn other_providers_fall_back_to_an_estimate() {
+    let count = DeepSeek::new("sk".to_string())
+        .count_tokens(&request("deepseek-chat"))
+        .await
+        .unwrap();
+    assert_eq!(count.source, TokenCountSource::Estimate);
+    // 4 per message, 4 for "Hello, world!" and 3 to prime the reply.
+    assert_eq!(count.input_tokens, 11);
+
+    let mock = MockProvider::new();
+    assert_eq!(
+        mock.count_tokens(&request("any")).await.unwrap(),
+        TokenCount::estimate(&request("any"))
+    );
+}
+
+#[cfg(feature = "tiktoken")]
+#[tokio::test]
+async fn openai_counts_locally_with_tiktoken() {
+    let provider = OpenAI::new("sk".to_string());
+
+    let count = provider.count_tokens(&request("gpt-4o")).await.unwrap();
+    assert_eq!(count.source, TokenCountSource::Tokenizer);
+    // 3 per message, "user", "Hello", ",", " world", "!" and 3 to prime the reply.
+    assert_eq!(count.input_tokens, 11);
+
+    let count = provider
+        .count_tokens(&request("my-finetune"))
+        .await
+        .unwrap();
+    assert_eq!(count.source, TokenCountSource::Estimate);
+}
+
+#[cfg(not(feature = "tiktoken"))]
+#[tokio::test]
+async fn openai_estimates_without_tiktoken() {
+    let count = OpenAI::new("sk".to_string())
+        .count_tokens(&request("gpt-4o"))
+        .await
+        .unwrap();
+    assert_eq!(count.source, TokenCountSource::Estimate);
+}